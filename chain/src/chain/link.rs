@@ -61,6 +61,9 @@ pub struct Link<R: Resource> {
 
     /// Family of queues.
     family: gfx_hal::queue::QueueFamilyId,
+
+    /// Resource content is discarded at the end of the link.
+    discard: bool,
 }
 
 /// Node for the link.
@@ -92,6 +95,7 @@ where
             queue_count: 1,
             queues: Vec::new(),
             family: node.sid.family(),
+            discard: false,
         };
         link.ensure_queue(node.sid.queue().index());
         link.queues[node.sid.queue().index()] = Some(LinkQueueState::new(&node));
@@ -156,6 +160,18 @@ where
     //     self.stages
     // }
 
+    /// Mark resource content as discarded at the end of the link.
+    /// No submissions can be added to the link afterwards.
+    pub fn discard(&mut self) {
+        self.discard = true;
+    }
+
+    /// Check if resource content is discarded at the end of the link.
+    /// Next link can't rely on resource content.
+    pub fn discarded(&self) -> bool {
+        self.discard
+    }
+
     /// Check if the link is associated with only one queue.
     pub fn single_queue(&self) -> bool {
         self.queue_count == 1
@@ -165,7 +181,10 @@ where
     /// If compatible then the submission can be associated with the link.
     pub fn compatible(&self, node: &LinkNode<R>) -> bool {
         // If queue the same and states are compatible.
-        self.family == node.sid.family() && !(self.access | node.state.access).exclusive()
+        // Discarded content can't be accessed by later submissions.
+        !self.discard
+            && self.family == node.sid.family()
+            && !(self.access | node.state.access).exclusive()
     }
 
     /// Insert submission with specified state to the link.
@@ -29,6 +29,32 @@ pub struct Chains {
     pub images: ImageChains,
 }
 
+/// Error collecting chains for nodes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollectError {
+    /// Node reads image content that was discarded by previous node.
+    DiscardedImageRead {
+        /// Index of the node.
+        node: usize,
+        /// Id of the image.
+        image: Id,
+    },
+}
+
+impl std::fmt::Display for CollectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CollectError::DiscardedImageRead { node, image } => write!(
+                f,
+                "node {} reads image {:?} after its content was discarded",
+                node, image
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CollectError {}
+
 #[derive(PartialEq, PartialOrd, Eq, Ord)]
 struct Fitness {
     transfers: usize,
@@ -42,6 +68,7 @@ struct ResolvedNode {
     rev_deps: Vec<usize>,
     buffers: Vec<(usize, State<Buffer>)>,
     images: Vec<(usize, State<Image>)>,
+    discards: Vec<usize>,
 }
 
 impl Default for ResolvedNode {
@@ -53,6 +80,7 @@ impl Default for ResolvedNode {
             rev_deps: Vec::new(),
             buffers: Vec::new(),
             images: Vec::new(),
+            discards: Vec::new(),
         }
     }
 }
@@ -88,7 +116,8 @@ struct QueueData {
 
 /// Calculate automatic `Chains` for nodes.
 /// This function tries to find the most appropriate schedule for nodes execution.
-pub fn collect<Q>(nodes: Vec<Node>, max_queues: Q) -> Chains
+/// Fails if node reads image content discarded by another node.
+pub fn collect<Q>(nodes: Vec<Node>, max_queues: Q) -> Result<Chains, CollectError>
 where
     Q: Fn(gfx_hal::queue::QueueFamilyId) -> usize,
 {
@@ -131,7 +160,7 @@ where
                 &mut schedule,
                 &mut images,
                 &mut buffers,
-            );
+            )?;
             scheduled += 1;
         }
     } else {
@@ -159,17 +188,17 @@ where
                 &mut schedule,
                 &mut images,
                 &mut buffers,
-            );
+            )?;
             scheduled += 1;
         }
     }
     assert_eq!(scheduled, nodes.nodes.len(), "Dependency loop found!");
 
-    Chains {
+    Ok(Chains {
         schedule: reify_schedule(schedule),
         buffers: reify_chain(&nodes.buffers, buffers),
         images: reify_chain(&nodes.images, images),
-    }
+    })
 }
 
 fn fill<T: Default>(num: usize) -> Vec<T> {
@@ -249,6 +278,12 @@ where
         reified_nodes[id].id = id;
         reified_nodes[id].family = node.family;
         reified_nodes[id].queues = family_full[&family].clone();
+        reified_nodes[id].discards = node
+            .discards
+            .iter()
+            .filter(|k| node.images.contains_key(k))
+            .map(|&k| images.forward(k))
+            .collect();
         reified_nodes[id].buffers = node
             .buffers
             .into_iter()
@@ -346,7 +381,7 @@ fn schedule_node<'a>(
     schedule: &mut Vec<QueueData>,
     images: &mut Vec<ChainData<Image>>,
     buffers: &mut Vec<ChainData<Buffer>>,
-) {
+) -> Result<(), CollectError> {
     let ref mut queue_data = schedule[queue];
     queue_data.wait_factor = max(queue_data.wait_factor, wait_factor + 1);
     let sid = queue_data
@@ -362,10 +397,23 @@ fn schedule_node<'a>(
             sid,
             submission,
             state,
+            false,
             |s, i, l| s.set_buffer_link(i, l),
         );
     }
     for &(id, state) in &node.images {
+        let discarded = images[id]
+            .chain
+            .links()
+            .last()
+            .map_or(false, |link| link.discarded());
+        if discarded && reads_content(state.access) {
+            return Err(CollectError::DiscardedImageRead {
+                node: node.id,
+                image: nodes.images[id],
+            });
+        }
+
         add_to_chain(
             nodes.images[id],
             node.family,
@@ -373,6 +421,7 @@ fn schedule_node<'a>(
             sid,
             submission,
             state,
+            node.discards.contains(&id),
             |s, i, l| s.set_image_link(i, l),
         );
     }
@@ -383,6 +432,21 @@ fn schedule_node<'a>(
             ready_nodes.push(&nodes.nodes[rev_dep]);
         }
     }
+
+    Ok(())
+}
+
+/// Check if access reads image content left by previous accesses.
+fn reads_content(access: gfx_hal::image::Access) -> bool {
+    access.intersects(
+        gfx_hal::image::Access::INPUT_ATTACHMENT_READ
+            | gfx_hal::image::Access::SHADER_READ
+            | gfx_hal::image::Access::COLOR_ATTACHMENT_READ
+            | gfx_hal::image::Access::DEPTH_STENCIL_ATTACHMENT_READ
+            | gfx_hal::image::Access::TRANSFER_READ
+            | gfx_hal::image::Access::HOST_READ
+            | gfx_hal::image::Access::MEMORY_READ,
+    )
 }
 
 fn add_to_chain<R, S>(
@@ -392,6 +456,7 @@ fn add_to_chain<R, S>(
     sid: SubmissionId,
     submission: &mut Submission<S>,
     state: State<R>,
+    discard: bool,
     set_link: impl FnOnce(&mut Submission<S>, Id, usize),
 ) where
     R: Resource,
@@ -422,4 +487,8 @@ fn add_to_chain<R, S>(
     if let Some(link) = append {
         chain.add_link(link);
     }
+
+    if discard {
+        chain.last_link_mut().unwrap().discard();
+    }
 }
@@ -25,7 +25,7 @@ mod sync;
 
 pub use crate::{
     chain::{Chain, Link, LinkNode},
-    collect::{collect, Chains, Unsynchronized},
+    collect::{collect, Chains, CollectError, Unsynchronized},
     node::{BufferState, ImageState, Node, State},
     resource::{AccessFlags, Buffer, Image, Resource, UsageFlags},
     schedule::{Family, Queue, QueueId, Schedule, Submission, SubmissionId},
@@ -45,6 +45,10 @@ pub struct Node {
 
     /// Image category ids and required state.
     pub images: HashMap<Id, State<Image>>,
+
+    /// Image category ids which content is discarded by the node.
+    /// Those images have no readers after the node.
+    pub discards: Vec<Id>,
 }
 
 impl Node {
@@ -67,4 +71,9 @@ impl Node {
     pub fn images(&self) -> HashMapIter<'_, Id, State<Image>> {
         self.images.iter()
     }
+
+    /// Get ids of images which content is discarded by this node.
+    pub fn discards(&self) -> &[Id] {
+        &self.discards
+    }
 }
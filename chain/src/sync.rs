@@ -387,6 +387,17 @@ where
 
     for (prev_link, link) in pairs {
         log::trace!("Sync {:#?}:{:#?}", prev_link.access(), link.access());
+
+        // Discarded content doesn't have to be preserved by transition.
+        let prev_state = if prev_link.discarded() {
+            State {
+                layout: R::undefined_layout(),
+                ..prev_link.state()
+            }
+        } else {
+            prev_link.state()
+        };
+
         if prev_link.family() == link.family() {
             // Prefer to generate barriers on the acquire side, if possible.
             if prev_link.access().exclusive() && !link.access().exclusive() {
@@ -396,7 +407,7 @@ where
                 sync.get_sync(signal_sid)
                     .release
                     .pick::<R>()
-                    .insert(id, Barrier::new(prev_link.state()..link.state()));
+                    .insert(id, Barrier::new(prev_state..link.state()));
 
                 // Generate semaphores between queues in the previous link and the current one.
                 for (queue_id, queue) in link.queues() {
@@ -416,18 +427,37 @@ where
                 sync.get_sync(wait_sid)
                     .acquire
                     .pick()
-                    .insert(id, Barrier::new(prev_link.state()..link.state()));
+                    .insert(id, Barrier::new(prev_state..link.state()));
 
                 if !link.access().exclusive() {
-                    unimplemented!("This case is unimplemented");
+                    fan_out(sync, uid, link, wait_sid);
                 }
             }
+        } else if prev_link.discarded() {
+            let wait_sid = earliest(link, schedule);
+
+            // Content is discarded so there is no need to transfer ownership.
+            // Generate semaphores between queues in the previous link and the current one.
+            for (queue_id, queue) in prev_link.queues() {
+                let tail = SubmissionId::new(queue_id, queue.last);
+                generate_semaphore_pair(sync, uid, link, tail..wait_sid);
+            }
+
+            sync.get_sync(wait_sid)
+                .acquire
+                .pick::<R>()
+                .insert(id, Barrier::new(prev_state..link.state()));
+
+            if !link.access().exclusive() {
+                fan_out(sync, uid, link, wait_sid);
+            }
         } else {
             let signal_sid = latest(prev_link, schedule);
             let wait_sid = earliest(link, schedule);
 
             if !prev_link.access().exclusive() {
-                unimplemented!("This case is unimplemented");
+                // Release must happen after all queues sharing the resource are done with it.
+                fan_in(sync, uid, prev_link, signal_sid);
             }
 
             // Generate a semaphore between the signal and wait sides of the transfer.
@@ -452,12 +482,30 @@ where
             );
 
             if !link.access().exclusive() {
-                unimplemented!("This case is unimplemented");
+                fan_out(sync, uid, link, wait_sid);
             }
         }
     }
 }
 
+/// Generate semaphores from the submission that acquires the resource
+/// to heads of the other queues sharing the resource in the link.
+fn fan_out<R: Resource>(sync: &mut SyncTemp, id: Id, link: &Link<R>, acquire: SubmissionId) {
+    for (queue_id, queue) in link.queues() {
+        let head = SubmissionId::new(queue_id, queue.first);
+        generate_semaphore_pair(sync, id, link, acquire..head);
+    }
+}
+
+/// Generate semaphores from tails of the other queues sharing the resource in the link
+/// to the submission that releases the resource.
+fn fan_in<R: Resource>(sync: &mut SyncTemp, id: Id, link: &Link<R>, release: SubmissionId) {
+    for (queue_id, queue) in link.queues() {
+        let tail = SubmissionId::new(queue_id, queue.last);
+        generate_semaphore_pair(sync, id, link, tail..release);
+    }
+}
+
 fn optimize_submission(
     sid: SubmissionId,
     found: &mut HashMap<QueueId, usize>,
@@ -505,3 +553,162 @@ fn optimize<S>(schedule: &Schedule<S>, sync: &mut SyncTemp) {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{collect::Unsynchronized, node::BufferState, schedule::Submission, LinkNode};
+    use gfx_hal::{buffer::Access, pso::PipelineStage, queue::QueueFamilyId};
+
+    const ID: Id = Id(0);
+
+    fn state(access: Access, stages: PipelineStage) -> BufferState {
+        State {
+            access,
+            layout: (),
+            stages,
+            usage: Buffer::no_usage(),
+        }
+    }
+
+    fn submit(
+        schedule: &mut Schedule<Unsynchronized>,
+        family: usize,
+        queue: usize,
+        submit_order: usize,
+    ) -> SubmissionId {
+        schedule
+            .ensure_queue(QueueId::new(QueueFamilyId(family), queue))
+            .add_submission(submit_order, 0, submit_order, Unsynchronized)
+    }
+
+    fn link(nodes: Vec<(SubmissionId, BufferState)>) -> Link<Buffer> {
+        let mut nodes = nodes
+            .into_iter()
+            .map(|(sid, state)| LinkNode { sid, state });
+        let mut link = Link::new(nodes.next().unwrap());
+        nodes.for_each(|node| link.add_node(node));
+        link
+    }
+
+    fn sync_buffer(
+        schedule: Schedule<Unsynchronized>,
+        links: Vec<Link<Buffer>>,
+    ) -> Schedule<SyncData<usize, usize>> {
+        let mut chain = Chain::new();
+        for link in links {
+            chain.add_link(link);
+        }
+        let mut buffers = HashMap::default();
+        buffers.insert(ID, chain);
+
+        let chains = Chains {
+            schedule,
+            buffers,
+            images: HashMap::default(),
+        };
+
+        let mut semaphores = 0;
+        sync(&chains, || {
+            semaphores += 1;
+            (semaphores, semaphores)
+        })
+    }
+
+    /// Check that `wait` submission waits for semaphore signaled by `signal` submission.
+    fn waits_for(
+        wait: &Submission<SyncData<usize, usize>>,
+        signal: &Submission<SyncData<usize, usize>>,
+    ) -> bool {
+        wait.sync().wait.iter().any(|wait| {
+            signal
+                .sync()
+                .signal
+                .iter()
+                .any(|signal| signal.semaphore() == wait.semaphore())
+        })
+    }
+
+    #[test]
+    fn test_sync_shared_link_same_family() {
+        let mut schedule = Schedule::new();
+        let write = submit(&mut schedule, 0, 0, 0);
+        let read_0 = submit(&mut schedule, 0, 0, 1);
+        let read_1 = submit(&mut schedule, 0, 1, 2);
+
+        let read = state(Access::SHADER_READ, PipelineStage::FRAGMENT_SHADER);
+        let schedule = sync_buffer(
+            schedule,
+            vec![
+                link(vec![(
+                    write,
+                    state(Access::TRANSFER_WRITE, PipelineStage::TRANSFER),
+                )]),
+                link(vec![(read_0, read), (read_1, read)]),
+            ],
+        );
+
+        // Write is made visible to readers before the signal.
+        let release = &schedule[write].sync().release.buffers[&ID];
+        assert!(release.families.is_none());
+        assert_eq!(release.states.start.0, Access::TRANSFER_WRITE);
+        assert_eq!(release.states.end.0, Access::SHADER_READ);
+
+        // Reader on the same queue is ordered by submission, the other one waits.
+        assert!(schedule[read_0].sync().wait.is_empty());
+        assert_eq!(schedule[read_1].sync().wait.len(), 1);
+        assert_eq!(
+            schedule[read_1].sync().wait[0].stage(),
+            PipelineStage::FRAGMENT_SHADER
+        );
+        assert!(waits_for(&schedule[read_1], &schedule[write]));
+
+        // Next frame's write waits for both readers.
+        let acquire = &schedule[write].sync().acquire.buffers[&ID];
+        assert!(acquire.families.is_none());
+        assert_eq!(acquire.states.start.0, Access::SHADER_READ);
+        assert_eq!(acquire.states.end.0, Access::TRANSFER_WRITE);
+        assert!(waits_for(&schedule[write], &schedule[read_1]));
+    }
+
+    #[test]
+    fn test_sync_exclusive_to_shared_across_families() {
+        let mut schedule = Schedule::new();
+        let write = submit(&mut schedule, 1, 0, 0);
+        let read_0 = submit(&mut schedule, 0, 0, 1);
+        let read_1 = submit(&mut schedule, 0, 1, 2);
+
+        let read = state(Access::SHADER_READ, PipelineStage::FRAGMENT_SHADER);
+        let schedule = sync_buffer(
+            schedule,
+            vec![
+                link(vec![(
+                    write,
+                    state(Access::TRANSFER_WRITE, PipelineStage::TRANSFER),
+                )]),
+                link(vec![(read_0, read), (read_1, read)]),
+            ],
+        );
+
+        // Ownership is released by the writer and acquired by the earliest reader.
+        let release = &schedule[write].sync().release.buffers[&ID];
+        assert_eq!(release.families, Some(QueueFamilyId(1)..QueueFamilyId(0)));
+        assert_eq!(release.states.start.0, Access::TRANSFER_WRITE);
+        let acquire = &schedule[read_0].sync().acquire.buffers[&ID];
+        assert_eq!(acquire.families, Some(QueueFamilyId(1)..QueueFamilyId(0)));
+        assert_eq!(acquire.states.end.0, Access::SHADER_READ);
+        assert!(waits_for(&schedule[read_0], &schedule[write]));
+
+        // Other reader waits for the acquire.
+        assert!(schedule[read_1].sync().acquire.buffers.is_empty());
+        assert_eq!(schedule[read_1].sync().wait.len(), 1);
+        assert!(waits_for(&schedule[read_1], &schedule[read_0]));
+
+        // Ownership is released back by the latest reader after both readers are done.
+        let release = &schedule[read_1].sync().release.buffers[&ID];
+        assert_eq!(release.families, Some(QueueFamilyId(0)..QueueFamilyId(1)));
+        let acquire = &schedule[write].sync().acquire.buffers[&ID];
+        assert_eq!(acquire.families, Some(QueueFamilyId(0)..QueueFamilyId(1)));
+        assert!(waits_for(&schedule[write], &schedule[read_1]));
+    }
+}
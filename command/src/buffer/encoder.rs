@@ -372,6 +372,56 @@ where
         gfx_hal::command::CommandBuffer::set_depth_bias(self.raw, depth_bias);
     }
 
+    /// Begin query.
+    /// Query will count results of the commands recorded until [`end_query`].
+    ///
+    /// [`end_query`]: #method.end_query
+    ///
+    /// # Safety
+    ///
+    /// Query must be reset before it is begun.
+    /// Query must not be active.
+    /// `PRECISE` flag can be set only for occlusion queries
+    /// and requires `occlusionQueryPrecise` feature.
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/vkCmdBeginQuery.html
+    pub unsafe fn begin_query(
+        &mut self,
+        query: gfx_hal::query::Query<'_, B>,
+        flags: gfx_hal::query::ControlFlags,
+    ) {
+        gfx_hal::command::CommandBuffer::begin_query(self.raw, query, flags)
+    }
+
+    /// End query.
+    ///
+    /// # Safety
+    ///
+    /// Query must be active.
+    /// If query was begun inside render pass it must be ended inside the same subpass.
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/vkCmdEndQuery.html
+    pub unsafe fn end_query(&mut self, query: gfx_hal::query::Query<'_, B>) {
+        gfx_hal::command::CommandBuffer::end_query(self.raw, query)
+    }
+
+    /// Request a timestamp to be written to the query
+    /// once all previous commands reach specified `stage`.
+    ///
+    /// # Safety
+    ///
+    /// Query must be reset before it is written.
+    /// Query pool must be of `Timestamp` type.
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/vkCmdWriteTimestamp.html
+    pub unsafe fn write_timestamp(
+        &mut self,
+        stage: gfx_hal::pso::PipelineStage,
+        query: gfx_hal::query::Query<'_, B>,
+    ) {
+        gfx_hal::command::CommandBuffer::write_timestamp(self.raw, stage, query)
+    }
+
     /// Reborrow encoder.
     pub fn reborrow<K>(&mut self) -> EncoderCommon<'_, B, K>
     where
@@ -897,6 +947,154 @@ where
         )
     }
 
+    /// Copy image subresource range to buffer region.
+    ///
+    /// # Safety
+    ///
+    /// Same as `copy_buffer()`
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/vkCmdCopyImageToBuffer.html
+    pub unsafe fn copy_image_to_buffer(
+        &mut self,
+        src: &B::Image,
+        src_layout: gfx_hal::image::Layout,
+        dst: &B::Buffer,
+        regions: impl IntoIterator<Item = gfx_hal::command::BufferImageCopy>,
+    ) where
+        C: Supports<Transfer>,
+    {
+        self.capability.assert();
+
+        gfx_hal::command::CommandBuffer::copy_image_to_buffer(
+            self.inner.raw,
+            src,
+            src_layout,
+            dst,
+            regions,
+        )
+    }
+
+    /// Fill buffer region with repeated 4-byte `data` value.
+    ///
+    /// # Safety
+    ///
+    /// `range` must be in bounds of the `buffer`.
+    /// `range.start` and `range.end` must be multiple of 4.
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/vkCmdFillBuffer.html
+    pub unsafe fn fill_buffer(&mut self, buffer: &B::Buffer, range: std::ops::Range<u64>, data: u32)
+    where
+        C: Supports<Transfer>,
+    {
+        self.capability.assert();
+
+        gfx_hal::command::CommandBuffer::fill_buffer(self.inner.raw, buffer, range, data)
+    }
+
+    /// Update buffer region with `data` recorded inline into the command buffer.
+    ///
+    /// # Safety
+    ///
+    /// `offset` and `data.len()` must be multiple of 4.
+    /// `data.len()` must not be greater than 65536.
+    /// Region must be in bounds of the `buffer`.
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/vkCmdUpdateBuffer.html
+    pub unsafe fn update_buffer(&mut self, buffer: &B::Buffer, offset: u64, data: &[u8])
+    where
+        C: Supports<Transfer>,
+    {
+        self.capability.assert();
+
+        gfx_hal::command::CommandBuffer::update_buffer(self.inner.raw, buffer, offset, data)
+    }
+
+    /// Clear color image subresource ranges outside of render pass.
+    ///
+    /// # Safety
+    ///
+    /// `layout` must be `General` or `TransferDstOptimal`.
+    /// `ranges` must contain only color aspect.
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/vkCmdClearColorImage.html
+    pub unsafe fn clear_color_image(
+        &mut self,
+        image: &B::Image,
+        layout: gfx_hal::image::Layout,
+        color: gfx_hal::command::ClearColor,
+        ranges: impl IntoIterator<Item = gfx_hal::image::SubresourceRange>,
+    ) where
+        C: Supports<Graphics>,
+    {
+        self.capability.assert();
+
+        gfx_hal::command::CommandBuffer::clear_image(
+            self.inner.raw,
+            image,
+            layout,
+            gfx_hal::command::ClearValue { color },
+            ranges,
+        )
+    }
+
+    /// Clear depth-stencil image subresource ranges outside of render pass.
+    ///
+    /// # Safety
+    ///
+    /// `layout` must be `General` or `TransferDstOptimal`.
+    /// `ranges` must contain only depth and stencil aspects.
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/vkCmdClearDepthStencilImage.html
+    pub unsafe fn clear_depth_stencil_image(
+        &mut self,
+        image: &B::Image,
+        layout: gfx_hal::image::Layout,
+        depth_stencil: gfx_hal::command::ClearDepthStencil,
+        ranges: impl IntoIterator<Item = gfx_hal::image::SubresourceRange>,
+    ) where
+        C: Supports<Graphics>,
+    {
+        self.capability.assert();
+
+        gfx_hal::command::CommandBuffer::clear_image(
+            self.inner.raw,
+            image,
+            layout,
+            gfx_hal::command::ClearValue { depth_stencil },
+            ranges,
+        )
+    }
+
+    /// Resolve multisampled image regions into non-multisampled image.
+    ///
+    /// # Safety
+    ///
+    /// Same as `copy_image()`
+    /// `src` must be multisampled and `dst` must have single sample.
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/vkCmdResolveImage.html
+    pub unsafe fn resolve_image(
+        &mut self,
+        src: &B::Image,
+        src_layout: gfx_hal::image::Layout,
+        dst: &B::Image,
+        dst_layout: gfx_hal::image::Layout,
+        regions: impl IntoIterator<Item = gfx_hal::command::ImageResolve>,
+    ) where
+        C: Supports<Graphics>,
+    {
+        self.capability.assert();
+
+        gfx_hal::command::CommandBuffer::resolve_image(
+            self.inner.raw,
+            src,
+            src_layout,
+            dst,
+            dst_layout,
+            regions,
+        )
+    }
+
     /// Dispatch compute.
     ///
     /// # Safety
@@ -929,6 +1127,52 @@ where
 
         gfx_hal::command::CommandBuffer::dispatch_indirect(self.inner.raw, buffer, offset)
     }
+
+    /// Reset range of queries in the pool.
+    /// Queries must be reset before they are used.
+    ///
+    /// # Safety
+    ///
+    /// `queries` must be in bounds of the `pool`.
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/vkCmdResetQueryPool.html
+    pub unsafe fn reset_query_pool(
+        &mut self,
+        pool: &B::QueryPool,
+        queries: std::ops::Range<gfx_hal::query::Id>,
+    ) {
+        gfx_hal::command::CommandBuffer::reset_query_pool(self.inner.raw, pool, queries)
+    }
+
+    /// Copy results of range of queries in the pool into the buffer.
+    /// Result of each query is written at `offset + index * stride`.
+    ///
+    /// # Safety
+    ///
+    /// `queries` must be in bounds of the `pool`.
+    /// `buffer` must be large enough to hold all results
+    /// and `offset` and `stride` must be multiple of 4 or 8 if `BITS_64` flag is set.
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/vkCmdCopyQueryPoolResults.html
+    pub unsafe fn copy_query_pool_results(
+        &mut self,
+        pool: &B::QueryPool,
+        queries: std::ops::Range<gfx_hal::query::Id>,
+        buffer: &B::Buffer,
+        offset: u64,
+        stride: u64,
+        flags: gfx_hal::query::ResultFlags,
+    ) {
+        gfx_hal::command::CommandBuffer::copy_query_pool_results(
+            self.inner.raw,
+            pool,
+            queries,
+            buffer,
+            offset,
+            stride,
+            flags,
+        )
+    }
 }
 
 impl<B, C, U, L, R> CommandBuffer<B, C, RecordingState<U>, L, R>
@@ -1,7 +1,7 @@
 use std::cmp::min;
 
 use crate::{
-    command::FamilyId,
+    command::{Families, FamilyId, QueueId},
     memory::{DynamicConfig, HeapsConfig, LinearConfig},
     util::DeviceId,
 };
@@ -14,6 +14,9 @@ use crate::{
 /// [`BasicHeapsConfigure`] can be used as sane default.
 /// `queues` - [`QueuesConfigure`] implementation to configure device queues creation.
 /// [`OneGraphicsQueue`] can be used if only one graphics queue will satisfy requirements.
+/// [`DedicatedQueues`] can be used to get async compute and transfer queues where available.
+/// `requirements` - [`DeviceRequirements`] features and limits device must support.
+/// Adapters that don't satisfy them are skipped.
 ///
 /// [`DeviceConfigure`]: trait.DevicesConfigure.html
 /// [`BasicDevicesConfigure`]: struct.BasicDevicesConfigure.html
@@ -21,6 +24,8 @@ use crate::{
 /// [`BasicHeapsConfigure`]: struct.BasicHeapsConfigure.html
 /// [`QueuesConfigure`]: trait.QueuesConfigure.html
 /// [`OneGraphicsQueue`]: struct.OneGraphicsQueue.html
+/// [`DedicatedQueues`]: struct.DedicatedQueues.html
+/// [`DeviceRequirements`]: struct.DeviceRequirements.html
 #[derive(Clone, derivative::Derivative)]
 #[derivative(Debug, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -33,8 +38,151 @@ pub struct Config<D = BasicDevicesConfigure, H = BasicHeapsConfigure, Q = OneGra
 
     /// Config for queue families.
     pub queues: Q,
+
+    /// Features and limits required from device.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub requirements: DeviceRequirements,
 }
 
+/// Features and limits device must support.
+///
+/// Required features are always enabled.
+/// Optional features are enabled if supported.
+/// `Factory::features` tells which features were enabled.
+#[derive(Clone, Debug, derivative::Derivative)]
+#[derivative(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceRequirements {
+    /// Features device must support.
+    #[derivative(Default(value = "gfx_hal::Features::empty()"))]
+    pub required_features: gfx_hal::Features,
+
+    /// Features enabled only if device supports them.
+    /// Defaults to all features, so every feature device supports is enabled.
+    #[derivative(Default(value = "gfx_hal::Features::all()"))]
+    pub optional_features: gfx_hal::Features,
+
+    /// Minimal values of limits device must satisfy.
+    /// Only maximum counts and sizes are checked.
+    /// Fields left with default zero values don't constrain the device.
+    pub min_limits: gfx_hal::Limits,
+}
+
+impl DeviceRequirements {
+    /// Require features.
+    pub fn with_required_features(mut self, features: gfx_hal::Features) -> Self {
+        self.required_features |= features;
+        self
+    }
+
+    /// Request features to be enabled if supported.
+    /// Optional features include all features by default,
+    /// use [`with_only_optional_features`] to enable fewer of them.
+    ///
+    /// [`with_only_optional_features`]: #method.with_only_optional_features
+    pub fn with_optional_features(mut self, features: gfx_hal::Features) -> Self {
+        self.optional_features |= features;
+        self
+    }
+
+    /// Replace set of features to be enabled if supported.
+    pub fn with_only_optional_features(mut self, features: gfx_hal::Features) -> Self {
+        self.optional_features = features;
+        self
+    }
+
+    /// Set minimal limits.
+    pub fn with_min_limits(mut self, limits: gfx_hal::Limits) -> Self {
+        self.min_limits = limits;
+        self
+    }
+
+    /// Check if features and limits satisfy requirements.
+    pub fn check(
+        &self,
+        features: gfx_hal::Features,
+        limits: &gfx_hal::Limits,
+    ) -> Result<(), MissingCapabilities> {
+        let mut missing = MissingCapabilities {
+            features: self.required_features - features,
+            limits: Vec::new(),
+        };
+
+        macro_rules! check_limits {
+            ($($field:ident),* $(,)*) => {
+                $(
+                    if self.min_limits.$field > limits.$field {
+                        missing.limits.push(stringify!($field));
+                    }
+                )*
+            };
+        }
+
+        check_limits!(
+            max_image_1d_size,
+            max_image_2d_size,
+            max_image_3d_size,
+            max_image_cube_size,
+            max_image_array_layers,
+            max_texel_elements,
+            max_uniform_buffer_range,
+            max_storage_buffer_range,
+            max_push_constants_size,
+            max_memory_allocation_count,
+            max_sampler_allocation_count,
+            max_bound_descriptor_sets,
+            max_per_stage_descriptor_samplers,
+            max_per_stage_descriptor_uniform_buffers,
+            max_per_stage_descriptor_storage_buffers,
+            max_per_stage_descriptor_sampled_images,
+            max_per_stage_descriptor_storage_images,
+            max_per_stage_descriptor_input_attachments,
+            max_per_stage_resources,
+            max_vertex_input_attributes,
+            max_vertex_input_bindings,
+            max_vertex_input_attribute_offset,
+            max_vertex_input_binding_stride,
+            max_vertex_output_components,
+            max_draw_indexed_index_value,
+            max_draw_indirect_count,
+            max_sampler_anisotropy,
+            max_viewports,
+        );
+
+        if missing.features.is_empty() && missing.limits.is_empty() {
+            Ok(())
+        } else {
+            Err(missing)
+        }
+    }
+
+    /// Get features to enable on device with specified supported features.
+    pub fn features(&self, supported: gfx_hal::Features) -> gfx_hal::Features {
+        self.required_features | (self.optional_features & supported)
+    }
+}
+
+/// Capabilities device lacks to satisfy requirements.
+#[derive(Clone, Debug)]
+pub struct MissingCapabilities {
+    /// Required features that are not supported.
+    pub features: gfx_hal::Features,
+
+    /// Names of limits that are lower than required.
+    pub limits: Vec<&'static str>,
+}
+
+impl std::fmt::Display for MissingCapabilities {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Missing features: {:?}. Insufficient limits: {:?}",
+            self.features, self.limits
+        )
+    }
+}
+impl std::error::Error for MissingCapabilities {}
+
 /// Queues configuration.
 ///
 /// Method [`configure`] receives collection of queue families and
@@ -115,6 +263,162 @@ unsafe impl QueuesConfigure for SavedQueueConfig {
     }
 }
 
+/// Queue families layout.
+/// Tells which families should be used for graphics, compute and transfer operations.
+///
+/// Compute and transfer families fall back to graphics family
+/// when adapter doesn't expose dedicated ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct QueueLayout {
+    /// Family for graphics operations.
+    pub graphics: FamilyId,
+
+    /// Family for compute operations.
+    /// Differs from `graphics` if async compute is available.
+    pub compute: FamilyId,
+
+    /// Family for transfer operations.
+    /// Differs from `graphics` and `compute` if dedicated transfer family is available.
+    pub transfer: FamilyId,
+}
+
+impl QueueLayout {
+    /// Pick layout among queue families of the adapter.
+    /// Returns `None` if there is no graphics family.
+    pub fn pick(device: DeviceId, families: &[impl gfx_hal::queue::QueueFamily]) -> Option<Self> {
+        Self::from_types(families.iter().filter(|f| f.max_queues() > 0).map(|f| {
+            (
+                FamilyId {
+                    device,
+                    index: f.id().0,
+                },
+                f.queue_type(),
+            )
+        }))
+    }
+
+    /// Get layout of created queue families.
+    /// Returns `None` if there is no graphics family.
+    pub fn from_families<B>(families: &Families<B>) -> Option<Self>
+    where
+        B: gfx_hal::Backend,
+    {
+        Self::from_types(
+            families
+                .as_slice()
+                .iter()
+                .map(|family| (family.id(), family.capability())),
+        )
+    }
+
+    fn from_types(
+        families: impl Iterator<Item = (FamilyId, gfx_hal::queue::QueueType)> + Clone,
+    ) -> Option<Self> {
+        let graphics = families.clone().find(|(_, ty)| ty.supports_graphics())?.0;
+
+        let compute = families
+            .clone()
+            .find(|(_, ty)| ty.supports_compute() && !ty.supports_graphics())
+            .map_or(graphics, |(id, _)| id);
+
+        let transfer = families
+            .clone()
+            .find(|(_, ty)| {
+                ty.supports_transfer() && !ty.supports_graphics() && !ty.supports_compute()
+            })
+            .map_or(graphics, |(id, _)| id);
+
+        Some(QueueLayout {
+            graphics,
+            compute,
+            transfer,
+        })
+    }
+
+    /// Check if compute family is separate from graphics family.
+    pub fn async_compute(&self) -> bool {
+        self.compute != self.graphics
+    }
+
+    /// Check if transfer family is separate from graphics and compute families.
+    pub fn dedicated_transfer(&self) -> bool {
+        self.transfer != self.graphics && self.transfer != self.compute
+    }
+
+    /// Get first queue of graphics family.
+    pub fn graphics_queue(&self) -> QueueId {
+        QueueId {
+            family: self.graphics,
+            index: 0,
+        }
+    }
+
+    /// Get first queue of compute family.
+    pub fn compute_queue(&self) -> QueueId {
+        QueueId {
+            family: self.compute,
+            index: 0,
+        }
+    }
+
+    /// Get first queue of transfer family.
+    pub fn transfer_queue(&self) -> QueueId {
+        QueueId {
+            family: self.transfer,
+            index: 0,
+        }
+    }
+}
+
+/// QueuePicker that picks one graphics queue
+/// and one queue of dedicated compute and transfer families if available.
+///
+/// When adapter exposes only one family this is equivalent to [`OneGraphicsQueue`].
+/// Use [`QueueLayout::from_families`] to find out which families were picked,
+/// so that nodes and uploads can be submitted to them.
+///
+/// [`OneGraphicsQueue`]: struct.OneGraphicsQueue.html
+/// [`QueueLayout::from_families`]: struct.QueueLayout.html#method.from_families
+#[derive(Clone, Copy, Debug, derivative::Derivative)]
+#[derivative(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DedicatedQueues {
+    /// Request queue of dedicated compute family.
+    #[derivative(Default(value = "true"))]
+    pub compute: bool,
+
+    /// Request queue of dedicated transfer family.
+    #[derivative(Default(value = "true"))]
+    pub transfer: bool,
+}
+
+unsafe impl QueuesConfigure for DedicatedQueues {
+    type Priorities = [f32; 1];
+    type Families = Vec<(FamilyId, [f32; 1])>;
+    fn configure(
+        self,
+        device: DeviceId,
+        families: &[impl gfx_hal::queue::QueueFamily],
+    ) -> Vec<(FamilyId, [f32; 1])> {
+        let layout = match QueueLayout::pick(device, families) {
+            Some(layout) => layout,
+            None => return Vec::new(),
+        };
+
+        log::debug!("Queue layout picked: {:#?}", layout);
+
+        let mut picked = vec![layout.graphics];
+        if self.compute && !picked.contains(&layout.compute) {
+            picked.push(layout.compute);
+        }
+        if self.transfer && !picked.contains(&layout.transfer) {
+            picked.push(layout.transfer);
+        }
+
+        picked.into_iter().map(|id| (id, [1.0])).collect()
+    }
+}
+
 /// Heaps configuration.
 ///
 /// Method [`configure`] receives memory properties and
@@ -220,20 +524,74 @@ unsafe impl HeapsConfigure for SavedHeapsConfig {
     }
 }
 
+/// Error returned when no adapter satisfies devices configuration.
+#[derive(Clone, Debug)]
+pub struct AdapterNotFound {
+    /// Adapters that were available.
+    pub adapters: Vec<gfx_hal::adapter::AdapterInfo>,
+
+    /// Error in adapter filter that prevented matching any adapter.
+    pub filter_error: Option<String>,
+}
+
+impl std::fmt::Display for AdapterNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(error) = &self.filter_error {
+            write!(f, "Invalid adapter filter: {}. ", error)?;
+        }
+
+        if self.adapters.is_empty() {
+            return write!(f, "No adapters present");
+        }
+
+        write!(f, "No suitable adapter found. Available adapters:")?;
+        for (index, info) in self.adapters.iter().enumerate() {
+            write!(
+                f,
+                "\n  {}: {} (vendor: {:#06x}, device: {:#06x}, type: {:?})",
+                index, info.name, info.vendor, info.device, info.device_type
+            )?;
+        }
+        Ok(())
+    }
+}
+impl std::error::Error for AdapterNotFound {}
+
+impl AdapterNotFound {
+    fn new<B>(adapters: &[gfx_hal::adapter::Adapter<B>]) -> Self
+    where
+        B: gfx_hal::Backend,
+    {
+        AdapterNotFound {
+            adapters: adapters
+                .iter()
+                .map(|adapter| adapter.info.clone())
+                .collect(),
+            filter_error: None,
+        }
+    }
+}
+
 /// Devices configuration.
 /// Picks physical device to use.
 pub trait DevicesConfigure {
     /// Pick adapter from the slice.
-    ///
-    /// # Panics
-    ///
-    /// This function may panic if empty slice is provided.
-    ///
-    fn pick<B>(&self, adapters: &[gfx_hal::adapter::Adapter<B>]) -> usize
+    /// Returns error listing available adapters if none is suitable.
+    fn pick<B>(&self, adapters: &[gfx_hal::adapter::Adapter<B>]) -> Result<usize, AdapterNotFound>
     where
         B: gfx_hal::Backend;
 }
 
+fn device_type_priority(device_type: &gfx_hal::adapter::DeviceType) -> u32 {
+    match device_type {
+        gfx_hal::adapter::DeviceType::DiscreteGpu => 0,
+        gfx_hal::adapter::DeviceType::IntegratedGpu => 1,
+        gfx_hal::adapter::DeviceType::VirtualGpu => 2,
+        gfx_hal::adapter::DeviceType::Cpu => 3,
+        _ => 4,
+    }
+}
+
 /// Basics adapters config.
 ///
 /// It picks first device with highest priority.
@@ -241,29 +599,271 @@ pub trait DevicesConfigure {
 ///
 /// To pick among presented discret GPUs,
 /// or to intentionally pick integrated GPU when discrete GPU is available
-/// a custom [`DeviceConfigure`] implementationcan be used instead.
+/// [`MatchingDevicesConfigure`] or a custom [`DeviceConfigure`] implementation can be used instead.
 ///
+/// [`MatchingDevicesConfigure`]: struct.MatchingDevicesConfigure.html
 /// [`DeviceConfigure`]: trait.DevicesConfigure.html
 #[derive(Clone, Copy, Debug, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BasicDevicesConfigure;
 
 impl DevicesConfigure for BasicDevicesConfigure {
-    fn pick<B>(&self, adapters: &[gfx_hal::adapter::Adapter<B>]) -> usize
+    fn pick<B>(&self, adapters: &[gfx_hal::adapter::Adapter<B>]) -> Result<usize, AdapterNotFound>
     where
         B: gfx_hal::Backend,
     {
         adapters
             .iter()
             .enumerate()
-            .min_by_key(|(_, adapter)| match adapter.info.device_type {
-                gfx_hal::adapter::DeviceType::DiscreteGpu => 0,
-                gfx_hal::adapter::DeviceType::IntegratedGpu => 1,
-                gfx_hal::adapter::DeviceType::VirtualGpu => 2,
-                gfx_hal::adapter::DeviceType::Cpu => 3,
-                _ => 4,
-            })
-            .expect("No adapters present")
-            .0
+            .min_by_key(|(_, adapter)| device_type_priority(&adapter.info.device_type))
+            .map(|(index, _)| index)
+            .ok_or_else(|| AdapterNotFound::new(adapters))
+    }
+}
+
+/// Adapters config that picks adapter by name, vendor or device id.
+///
+/// Among matching adapters the one with highest device type priority is picked
+/// the same way as [`BasicDevicesConfigure`] does.
+///
+/// Filter can be overridden with environment variable (`RENDY_ADAPTER` by default).
+/// Its value is a comma-separated list of `name=<substring>`, `vendor=<id>` and `device=<id>`
+/// entries. Ids can be decimal or hexadecimal with `0x` prefix.
+/// Entry without `=` is treated as name substring.
+/// For example `RENDY_ADAPTER=vendor=0x10de` picks NVIDIA adapter.
+/// Unknown keys and invalid ids make picking fail.
+///
+/// [`BasicDevicesConfigure`]: struct.BasicDevicesConfigure.html
+#[derive(Clone, Debug, derivative::Derivative)]
+#[derivative(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MatchingDevicesConfigure {
+    /// Case-insensitive substring of adapter name.
+    pub name: Option<String>,
+
+    /// PCI id of adapter vendor.
+    pub vendor: Option<usize>,
+
+    /// PCI id of adapter device.
+    pub device: Option<usize>,
+
+    /// Environment variable that overrides filter.
+    #[derivative(Default(value = "Some(\"RENDY_ADAPTER\".into())"))]
+    pub env: Option<String>,
+}
+
+impl MatchingDevicesConfigure {
+    /// Create config that matches any adapter unless overridden by `RENDY_ADAPTER` variable.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match adapters with name containing `name`.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Match adapters of the vendor.
+    pub fn with_vendor(mut self, vendor: usize) -> Self {
+        self.vendor = Some(vendor);
+        self
+    }
+
+    /// Match adapters with device id.
+    pub fn with_device(mut self, device: usize) -> Self {
+        self.device = Some(device);
+        self
+    }
+
+    /// Set environment variable that overrides filter.
+    pub fn with_env(mut self, env: impl Into<String>) -> Self {
+        self.env = Some(env.into());
+        self
+    }
+
+    /// Ignore environment variables.
+    pub fn without_env(mut self) -> Self {
+        self.env = None;
+        self
+    }
+
+    /// Get filter overridden by environment variable if it is set.
+    fn filter(&self) -> Result<std::borrow::Cow<'_, Self>, String> {
+        match self.env.as_ref().and_then(|env| std::env::var(env).ok()) {
+            Some(value) => Self::parse_filter(&value).map(std::borrow::Cow::Owned),
+            None => Ok(std::borrow::Cow::Borrowed(self)),
+        }
+    }
+
+    /// Parse filter from the value of the environment variable.
+    /// Fails on unknown keys and invalid ids,
+    /// so explicit selection never silently matches any adapter.
+    fn parse_filter(value: &str) -> Result<Self, String> {
+        let mut filter = MatchingDevicesConfigure {
+            name: None,
+            vendor: None,
+            device: None,
+            env: None,
+        };
+
+        for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (key, value) = match entry.find('=') {
+                Some(eq) => (entry[..eq].trim(), entry[eq + 1..].trim()),
+                None => ("name", entry),
+            };
+
+            match key {
+                "name" => filter.name = Some(value.to_owned()),
+                "vendor" => filter.vendor = Some(parse_id(value)?),
+                "device" => filter.device = Some(parse_id(value)?),
+                _ => return Err(format!("unknown key '{}'", key)),
+            }
+        }
+
+        Ok(filter)
+    }
+
+    fn matches(&self, info: &gfx_hal::adapter::AdapterInfo) -> bool {
+        self.name.as_ref().map_or(true, |name| {
+            info.name.to_lowercase().contains(&name.to_lowercase())
+        }) && self.vendor.map_or(true, |vendor| info.vendor == vendor)
+            && self.device.map_or(true, |device| info.device == device)
+    }
+}
+
+fn parse_id(value: &str) -> Result<usize, String> {
+    let parsed = if value.starts_with("0x") || value.starts_with("0X") {
+        usize::from_str_radix(&value[2..], 16)
+    } else {
+        value.parse()
+    };
+
+    parsed.map_err(|err| format!("invalid id '{}': {}", value, err))
+}
+
+impl DevicesConfigure for MatchingDevicesConfigure {
+    fn pick<B>(&self, adapters: &[gfx_hal::adapter::Adapter<B>]) -> Result<usize, AdapterNotFound>
+    where
+        B: gfx_hal::Backend,
+    {
+        let filter = self.filter().map_err(|error| AdapterNotFound {
+            filter_error: Some(error),
+            ..AdapterNotFound::new(adapters)
+        })?;
+        adapters
+            .iter()
+            .enumerate()
+            .filter(|(_, adapter)| filter.matches(&adapter.info))
+            .min_by_key(|(_, adapter)| device_type_priority(&adapter.info.device_type))
+            .map(|(index, _)| index)
+            .ok_or_else(|| AdapterNotFound::new(adapters))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use gfx_hal::queue::QueueType;
+
+    fn layout(types: &[QueueType]) -> Option<QueueLayout> {
+        let device = DeviceId::new(crate::util::InstanceId::new());
+        let families: Vec<_> = types
+            .iter()
+            .enumerate()
+            .map(|(index, &ty)| (FamilyId { device, index }, ty))
+            .collect();
+        QueueLayout::from_types(families.into_iter())
+    }
+
+    #[test]
+    fn test_queue_layout_single_family() {
+        let layout = layout(&[QueueType::General]).unwrap();
+        assert_eq!(layout.graphics.index, 0);
+        assert_eq!(layout.compute.index, 0);
+        assert_eq!(layout.transfer.index, 0);
+        assert!(!layout.async_compute());
+        assert!(!layout.dedicated_transfer());
+    }
+
+    #[test]
+    fn test_queue_layout_dedicated_families() {
+        let layout =
+            layout(&[QueueType::Transfer, QueueType::Compute, QueueType::General]).unwrap();
+        assert_eq!(layout.graphics.index, 2);
+        assert_eq!(layout.compute.index, 1);
+        assert_eq!(layout.transfer.index, 0);
+        assert!(layout.async_compute());
+        assert!(layout.dedicated_transfer());
+    }
+
+    #[test]
+    fn test_queue_layout_without_graphics() {
+        assert!(layout(&[QueueType::Compute, QueueType::Transfer]).is_none());
+    }
+
+    #[test]
+    fn test_parse_adapter_id() {
+        assert_eq!(parse_id("4318"), Ok(4318));
+        assert_eq!(parse_id("0x10de"), Ok(0x10de));
+        assert_eq!(parse_id("0X10DE"), Ok(0x10de));
+        assert!(parse_id("nvidia").is_err());
+        assert!(parse_id("0x").is_err());
+    }
+
+    #[test]
+    fn test_parse_adapter_filter() {
+        let filter =
+            MatchingDevicesConfigure::parse_filter("GeForce, vendor=0x10de, device = 7").unwrap();
+        assert_eq!(filter.name.as_ref().map(String::as_str), Some("GeForce"));
+        assert_eq!(filter.vendor, Some(0x10de));
+        assert_eq!(filter.device, Some(7));
+
+        let filter = MatchingDevicesConfigure::parse_filter("").unwrap();
+        assert!(filter.name.is_none() && filter.vendor.is_none() && filter.device.is_none());
+
+        assert!(MatchingDevicesConfigure::parse_filter("vendor=nvidia").is_err());
+        assert!(MatchingDevicesConfigure::parse_filter("driver=1").is_err());
+    }
+
+    #[test]
+    fn test_device_requirements_check() {
+        let limits = gfx_hal::Limits {
+            max_image_2d_size: 4096,
+            max_sampler_anisotropy: 16.0,
+            ..Default::default()
+        };
+        let supported = gfx_hal::Features::SAMPLER_ANISOTROPY;
+
+        assert!(DeviceRequirements::default()
+            .check(supported, &limits)
+            .is_ok());
+
+        let requirements = DeviceRequirements::default()
+            .with_required_features(
+                gfx_hal::Features::SAMPLER_ANISOTROPY | gfx_hal::Features::GEOMETRY_SHADER,
+            )
+            .with_min_limits(gfx_hal::Limits {
+                max_image_2d_size: 8192,
+                max_sampler_anisotropy: 16.0,
+                ..Default::default()
+            });
+        let missing = requirements.check(supported, &limits).unwrap_err();
+        assert_eq!(missing.features, gfx_hal::Features::GEOMETRY_SHADER);
+        assert_eq!(missing.limits, vec!["max_image_2d_size"]);
+    }
+
+    #[test]
+    fn test_device_requirements_features() {
+        let supported = gfx_hal::Features::SAMPLER_ANISOTROPY;
+        assert_eq!(DeviceRequirements::default().features(supported), supported);
+
+        let requirements = DeviceRequirements::default()
+            .with_only_optional_features(gfx_hal::Features::empty())
+            .with_required_features(gfx_hal::Features::GEOMETRY_SHADER);
+        assert_eq!(
+            requirements.features(supported),
+            gfx_hal::Features::GEOMETRY_SHADER
+        );
     }
 }
@@ -4,11 +4,18 @@ use {
         command::{
             families_from_device, CommandPool, Families, Family, FamilyId, Fence, QueueType, Reset,
         },
-        config::{Config, DevicesConfigure, HeapsConfigure, QueuesConfigure},
+        config::{
+            AdapterNotFound, Config, DevicesConfigure, HeapsConfigure, MissingCapabilities,
+            QueuesConfigure,
+        },
         descriptor::DescriptorAllocator,
-        memory::{self, Heaps, MemoryUsage, TotalMemoryUtilization, Write},
+        memory::{
+            self, Block, Heaps, HeapsError, MemoryBlock, MemoryUsage, TotalMemoryUtilization, Write,
+        },
         resource::*,
-        upload::{BufferState, ImageState, ImageStateOrLayout, Uploader},
+        upload::{
+            download_image_size, BufferState, Download, ImageState, ImageStateOrLayout, Uploader,
+        },
         util::{rendy_backend_match, rendy_with_slow_safety_checks, Device, DeviceId, Instance},
         wsi::{Surface, SwapchainError, Target},
     },
@@ -22,7 +29,7 @@ use {
         Backend, Features, Limits,
     },
     smallvec::SmallVec,
-    std::{borrow::BorrowMut, cmp::max, mem::ManuallyDrop},
+    std::{borrow::BorrowMut, cmp::max, collections::HashMap, mem::ManuallyDrop},
     thread_profiler::profile_scope,
 };
 
@@ -36,6 +43,16 @@ struct ResourceHub<B: Backend> {
     sets: ResourceTracker<DescriptorSet<B>>,
     samplers: ResourceTracker<Sampler<B>>,
     samplers_cache: parking_lot::RwLock<SamplerCache<B>>,
+    render_passes: ResourceTracker<RenderPass<B>>,
+    pipeline_layouts: ResourceTracker<PipelineLayout<B>>,
+    graphics_pipelines: ResourceTracker<GraphicsPipeline<B>>,
+    layouts_cache: parking_lot::RwLock<ResourceCache<DescriptorSetLayout<B>>>,
+    render_passes_cache: parking_lot::RwLock<ResourceCache<RenderPass<B>>>,
+    /// Compatibility keys of cached render passes by address of raw render pass.
+    /// Updated whenever render passes are added to or removed from the cache.
+    render_pass_compatibility: parking_lot::RwLock<HashMap<usize, CacheKey>>,
+    pipeline_layouts_cache: parking_lot::RwLock<ResourceCache<PipelineLayout<B>>>,
+    graphics_pipelines_cache: parking_lot::RwLock<ResourceCache<GraphicsPipeline<B>>>,
 }
 
 impl<B> ResourceHub<B>
@@ -53,6 +70,12 @@ where
         self.sets
             .cleanup(|s| s.dispose(allocator), &next, &complete);
         self.views.cleanup(|v| v.dispose(device), &next, &complete);
+        self.graphics_pipelines
+            .cleanup(|p| p.dispose(device), &next, &complete);
+        self.pipeline_layouts
+            .cleanup(|l| l.dispose(device), &next, &complete);
+        self.render_passes
+            .cleanup(|r| r.dispose(device), &next, &complete);
         self.layouts
             .cleanup(|l| l.dispose(device), &next, &complete);
         self.buffers
@@ -70,8 +93,16 @@ where
         allocator: &mut DescriptorAllocator<B>,
     ) {
         drop(self.samplers_cache);
+        drop(self.graphics_pipelines_cache);
+        drop(self.pipeline_layouts_cache);
+        drop(self.render_passes_cache);
+        drop(self.render_pass_compatibility);
+        drop(self.layouts_cache);
         self.sets.dispose(|s| s.dispose(allocator));
         self.views.dispose(|v| v.dispose(device));
+        self.graphics_pipelines.dispose(|p| p.dispose(device));
+        self.pipeline_layouts.dispose(|l| l.dispose(device));
+        self.render_passes.dispose(|r| r.dispose(device));
         self.layouts.dispose(|l| l.dispose(device));
         self.buffers.dispose(|b| b.dispose(device, heaps));
         self.images.dispose(|i| i.dispose(device, heaps));
@@ -90,6 +121,60 @@ pub enum UploadError {
     Upload(OutOfMemory),
 }
 
+/// Failure initializing `Factory`.
+#[derive(Debug)]
+pub enum InitError {
+    /// No adapter satisfies devices configuration.
+    Adapter(AdapterNotFound),
+    /// No adapter matching devices configuration satisfies device requirements.
+    /// Contains capabilities each rejected adapter lacks.
+    Unsupported(Vec<(gfx_hal::adapter::AdapterInfo, MissingCapabilities)>),
+    /// Failed to create device.
+    Creation(gfx_hal::device::CreationError),
+}
+
+impl From<gfx_hal::device::CreationError> for InitError {
+    fn from(error: gfx_hal::device::CreationError) -> Self {
+        InitError::Creation(error)
+    }
+}
+
+impl std::fmt::Display for InitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InitError::Adapter(error) => write!(f, "{}", error),
+            InitError::Unsupported(adapters) => {
+                write!(f, "No adapter satisfies device requirements:")?;
+                for (info, missing) in adapters {
+                    write!(f, "\n  {}: {}", info.name, missing)?;
+                }
+                Ok(())
+            }
+            InitError::Creation(error) => write!(f, "Failed to create device: {:?}", error),
+        }
+    }
+}
+impl std::error::Error for InitError {}
+
+/// Failure loading or saving pipeline cache.
+#[derive(Debug)]
+pub enum PipelineCacheError {
+    /// Failed to read or write the file.
+    Io(std::io::Error),
+    /// Failed to create or read the cache.
+    OutOfMemory(OutOfMemory),
+}
+
+impl std::fmt::Display for PipelineCacheError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PipelineCacheError::Io(error) => write!(f, "{}", error),
+            PipelineCacheError::OutOfMemory(error) => write!(f, "{:?}", error),
+        }
+    }
+}
+impl std::error::Error for PipelineCacheError {}
+
 /// Higher level device interface.
 /// Manges memory, resources and queue families.
 #[derive(derivative::Derivative)]
@@ -102,6 +187,9 @@ pub struct Factory<B: Backend> {
     uploader: Uploader<B>,
     blitter: Blitter<B>,
     families_indices: Vec<usize>,
+    features: Features,
+    #[derivative(Debug = "ignore")]
+    pipeline_cache: ManuallyDrop<B::PipelineCache>,
     #[derivative(Debug = "ignore")]
     device: Device<B>,
     #[derivative(Debug = "ignore")]
@@ -130,6 +218,9 @@ where
             log::trace!("Uploader disposed");
             self.blitter.dispose(&self.device);
             log::trace!("Blitter disposed");
+            self.device
+                .destroy_pipeline_cache(std::ptr::read(&*self.pipeline_cache));
+            log::trace!("Pipeline cache disposed");
             std::ptr::read(&mut *self.resources).dispose(
                 &self.device,
                 self.heaps.get_mut(),
@@ -261,6 +352,150 @@ where
         Ok(self.resources.images.escape(image))
     }
 
+    /// Allocate memory block that isn't owned by any resource.
+    /// Multiple resources can be bound to it using
+    /// [`create_aliased_buffer`] and [`create_aliased_image`].
+    ///
+    /// [`create_aliased_buffer`]: #method.create_aliased_buffer
+    /// [`create_aliased_image`]: #method.create_aliased_image
+    pub fn allocate_memory(
+        &self,
+        mask: u32,
+        memory_usage: impl MemoryUsage,
+        size: u64,
+        align: u64,
+    ) -> Result<MemoryBlock<B>, HeapsError> {
+        profile_scope!("allocate_memory");
+
+        self.heaps
+            .lock()
+            .allocate(&self.device, mask, memory_usage, size, align)
+    }
+
+    /// Free memory block allocated with [`allocate_memory`].
+    ///
+    /// # Safety
+    ///
+    /// Resources bound to the block must not be used by any pending commands
+    /// and must not be used afterwards.
+    ///
+    /// [`allocate_memory`]: #method.allocate_memory
+    pub unsafe fn free_memory(&self, block: MemoryBlock<B>) {
+        self.heaps.lock().free(&self.device, block);
+    }
+
+    /// Create buffer without memory bound to it.
+    /// Returns raw buffer and its memory requirements.
+    ///
+    /// # Safety
+    ///
+    /// Raw buffer must be bound with [`create_aliased_buffer`].
+    ///
+    /// [`create_aliased_buffer`]: #method.create_aliased_buffer
+    pub unsafe fn create_unbound_buffer(
+        &self,
+        info: BufferInfo,
+    ) -> Result<(B::Buffer, gfx_hal::memory::Requirements), BufferCreationError> {
+        Buffer::create_unbound(&self.device, info)
+    }
+
+    /// Bind buffer created with [`create_unbound_buffer`] to the memory block at `offset`.
+    /// The block is not owned by the buffer and can be aliased by other resources.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be created with `info` by this `Factory`.
+    /// `block` must be allocated with [`allocate_memory`] and satisfy buffer memory requirements at `offset`.
+    /// `block` must not be freed while buffer is in use.
+    /// Accesses to aliasing resources must be properly synchronized.
+    /// `raw` is destroyed if binding fails.
+    ///
+    /// [`create_unbound_buffer`]: #method.create_unbound_buffer
+    /// [`allocate_memory`]: #method.allocate_memory
+    pub unsafe fn create_aliased_buffer(
+        &self,
+        raw: B::Buffer,
+        info: BufferInfo,
+        block: &MemoryBlock<B>,
+        offset: u64,
+    ) -> Result<Escape<Buffer<B>>, BufferCreationError> {
+        let buffer = Buffer::bind_aliased(
+            &self.device,
+            raw,
+            info,
+            block.memory(),
+            block.range().start + offset,
+        )?;
+        Ok(self.resources.buffers.escape(buffer))
+    }
+
+    /// Destroy buffer created with [`create_unbound_buffer`] that wasn't bound to memory.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be created by this `Factory` and must not be bound.
+    ///
+    /// [`create_unbound_buffer`]: #method.create_unbound_buffer
+    pub unsafe fn destroy_unbound_buffer(&self, raw: B::Buffer) {
+        self.device.destroy_buffer(raw);
+    }
+
+    /// Create image without memory bound to it.
+    /// Returns raw image and its memory requirements.
+    ///
+    /// # Safety
+    ///
+    /// Raw image must be bound with [`create_aliased_image`].
+    ///
+    /// [`create_aliased_image`]: #method.create_aliased_image
+    pub unsafe fn create_unbound_image(
+        &self,
+        info: ImageInfo,
+    ) -> Result<(B::Image, gfx_hal::memory::Requirements), ImageCreationError> {
+        Image::create_unbound(&self.device, info)
+    }
+
+    /// Bind image created with [`create_unbound_image`] to the memory block at `offset`.
+    /// The block is not owned by the image and can be aliased by other resources.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be created with `info` by this `Factory`.
+    /// `block` must be allocated with [`allocate_memory`] and satisfy image memory requirements at `offset`.
+    /// `block` must not be freed while image is in use.
+    /// Accesses to aliasing resources must be properly synchronized.
+    /// `raw` is destroyed if binding fails.
+    ///
+    /// [`create_unbound_image`]: #method.create_unbound_image
+    /// [`allocate_memory`]: #method.allocate_memory
+    pub unsafe fn create_aliased_image(
+        &self,
+        raw: B::Image,
+        info: ImageInfo,
+        block: &MemoryBlock<B>,
+        offset: u64,
+    ) -> Result<Escape<Image<B>>, ImageCreationError> {
+        let image = Image::bind_aliased(
+            &self.device,
+            raw,
+            info,
+            block.memory(),
+            block.range().start + offset,
+        )?;
+        Ok(self.resources.images.escape(image))
+    }
+
+    /// Destroy image created with [`create_unbound_image`] that wasn't bound to memory.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be created by this `Factory` and must not be bound.
+    ///
+    /// [`create_unbound_image`]: #method.create_unbound_image
+    pub unsafe fn destroy_unbound_image(&self, raw: B::Image) {
+        self.device.destroy_image(raw);
+    }
+
     /// Fetch image format details for a particular `ImageInfo`.
     pub fn image_format_properties(&self, info: ImageInfo) -> Option<FormatProperties> {
         self.physical().image_format_properties(
@@ -434,6 +669,9 @@ where
     ///
     /// If buffer is used by device then `last` state must match the last usage state of the buffer
     /// before updating happen.
+    /// If `last` state belongs to another queue then resource is released by that queue
+    /// upon next [`flush_uploads`] or [`maintain`] call,
+    /// so all its operations on that queue must be submitted before.
     /// In order to guarantee that updated content will be made visible to next device operation
     /// that reads content of the buffer range the `next` must match buffer usage state in that operation.
     pub unsafe fn upload_buffer<T>(
@@ -480,6 +718,9 @@ where
     ///
     /// If buffer is used by device then `last` state must match the last usage state of the buffer
     /// before updating happen.
+    /// If `last` state belongs to another queue then resource is released by that queue
+    /// upon next [`flush_uploads`] or [`maintain`] call,
+    /// so all its operations on that queue must be submitted before.
     /// In order to guarantee that updated content will be made visible to next device operation
     /// that reads content of the buffer range the `next` must match buffer usage state in that operation.
     pub unsafe fn upload_from_staging_buffer(
@@ -510,15 +751,18 @@ where
     /// Image must be created by this `Factory`.
     /// If image is used by device then `last` state must match the last usage state of the image
     /// before transition.
+    /// If `last` state belongs to another queue then resource is released by that queue
+    /// upon next [`flush_uploads`] or [`maintain`] call,
+    /// so all its operations on that queue must be submitted before.
     pub unsafe fn transition_image(
         &self,
         image: Handle<Image<B>>,
         image_range: SubresourceRange,
         last: impl Into<ImageStateOrLayout>,
         next: ImageState,
-    ) {
+    ) -> Result<(), OutOfMemory> {
         self.uploader
-            .transition_image(image, image_range, last.into(), next);
+            .transition_image(&self.device, image, image_range, last.into(), next)
     }
 
     /// Update image layers content with provided data.
@@ -540,6 +784,9 @@ where
     /// Image must be created by this `Factory`.
     /// If image is used by device then `last` state must match the last usage state of the image
     /// before updating happen.
+    /// If `last` state belongs to another queue then resource is released by that queue
+    /// upon next [`flush_uploads`] or [`maintain`] call,
+    /// so all its operations on that queue must be submitted before.
     /// In order to guarantee that updated content will be made visible to next device operation
     /// that reads content of the image layers the `next` must match image usage state in that operation.
     pub unsafe fn upload_image<T>(
@@ -604,6 +851,118 @@ where
             .map_err(UploadError::Upload)
     }
 
+    /// Download buffer range content into host-visible staging buffer.
+    ///
+    /// Copy operation will actually be submitted to the `state.queue`
+    /// upon next [`flush_uploads`] or [`maintain`] call to this `Factory`, and
+    /// is guaranteed to take place after all previous operations that have been
+    /// submitted to the same graphics queue on this `Factory` since last
+    /// [`flush_uploads`] or [`maintain`] call.
+    ///
+    /// Returned `Download` can be polled or waited on
+    /// and yields the content once complete.
+    ///
+    /// # Safety
+    ///
+    /// If buffer is used by device then `state` must match the last usage state of the buffer
+    /// before downloading happen. Buffer will be left in the same state.
+    ///
+    /// [`flush_uploads`]: #method.flush_uploads
+    /// [`maintain`]: #method.maintain
+    pub unsafe fn download_buffer(
+        &self,
+        buffer: &Buffer<B>,
+        range: std::ops::Range<u64>,
+        state: BufferState,
+    ) -> Result<Download<B>, UploadError> {
+        assert!(buffer.info().usage.contains(buffer::Usage::TRANSFER_SRC));
+        assert!(range.start < range.end && range.end <= buffer.size());
+
+        let staging = self
+            .create_buffer(
+                BufferInfo {
+                    size: range.end - range.start,
+                    usage: buffer::Usage::TRANSFER_DST,
+                },
+                memory::Download,
+            )
+            .map_err(UploadError::Create)?;
+
+        self.uploader
+            .download_buffer(&self.device, buffer, range.start, staging, state)
+            .map_err(UploadError::Upload)
+    }
+
+    /// Download image layers content into host-visible staging buffer.
+    /// Texels of the region are tightly packed in the downloaded content.
+    ///
+    /// Copy operation will actually be submitted to the `state.queue`
+    /// upon next [`flush_uploads`] or [`maintain`] call to this `Factory`, and
+    /// is guaranteed to take place after all previous operations that have been
+    /// submitted to the same graphics queue on this `Factory` since last
+    /// [`flush_uploads`] or [`maintain`] call.
+    ///
+    /// Returned `Download` can be polled or waited on
+    /// and yields the content once complete.
+    ///
+    /// # Safety
+    ///
+    /// Image must be created by this `Factory`.
+    /// `state` must match the last usage state of the image
+    /// before downloading happen. Image will be left in the same state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the layer range is empty or the region doesn't fit into the mip level.
+    ///
+    /// [`flush_uploads`]: #method.flush_uploads
+    /// [`maintain`]: #method.maintain
+    pub unsafe fn download_image(
+        &self,
+        image: Handle<Image<B>>,
+        image_layers: SubresourceLayers,
+        image_offset: image::Offset,
+        image_extent: Extent,
+        state: ImageState,
+    ) -> Result<Download<B>, UploadError> {
+        assert!(image.info().usage.contains(image::Usage::TRANSFER_SRC));
+        assert!(image_layers.level < image.info().levels);
+
+        let total_bytes = download_image_size(
+            image.kind(),
+            image.format(),
+            &image_layers,
+            image_offset,
+            image_extent,
+        );
+
+        let staging = self
+            .create_buffer(
+                BufferInfo {
+                    size: total_bytes,
+                    usage: buffer::Usage::TRANSFER_DST,
+                },
+                memory::Download,
+            )
+            .map_err(UploadError::Create)?;
+
+        self.uploader
+            .download_image(
+                &self.device,
+                image,
+                image_layers,
+                image_offset,
+                image_extent,
+                staging,
+                state,
+            )
+            .map_err(UploadError::Upload)
+    }
+
+    pub(crate) fn uploader(&self) -> &Uploader<B> {
+        &self.uploader
+    }
+
     /// Get blitter instance
     pub fn blitter(&self) -> &Blitter<B> {
         &self.blitter
@@ -720,6 +1079,71 @@ where
         &self.adapter.physical_device
     }
 
+    /// Get pipeline cache managed by the `Factory`.
+    /// Pipelines created by graph nodes use this cache.
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/man/html/VkPipelineCache.html
+    pub fn pipeline_cache(&self) -> &B::PipelineCache {
+        &self.pipeline_cache
+    }
+
+    /// Get data of the pipeline cache.
+    /// It can be used to populate cache on next run with [`load_pipeline_cache`].
+    ///
+    /// [`load_pipeline_cache`]: #method.load_pipeline_cache
+    pub fn pipeline_cache_data(&self) -> Result<Vec<u8>, OutOfMemory> {
+        unsafe { self.device.get_pipeline_cache_data(&self.pipeline_cache) }
+    }
+
+    /// Merge data previously retrieved with [`pipeline_cache_data`] into the pipeline cache.
+    /// Data created by incompatible device or driver is ignored.
+    ///
+    /// [`pipeline_cache_data`]: #method.pipeline_cache_data
+    pub fn load_pipeline_cache(&self, data: &[u8]) -> Result<(), OutOfMemory> {
+        profile_scope!("load_pipeline_cache");
+
+        unsafe {
+            let loaded = self.device.create_pipeline_cache(Some(data))?;
+            let result = self
+                .device
+                .merge_pipeline_caches(&self.pipeline_cache, Some(&loaded));
+            self.device.destroy_pipeline_cache(loaded);
+            result
+        }
+    }
+
+    /// Merge pipeline cache data from the file.
+    /// Missing file is not an error as there is nothing cached yet.
+    pub fn load_pipeline_cache_file(
+        &self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<(), PipelineCacheError> {
+        let data = match std::fs::read(path) {
+            Ok(data) => data,
+            Err(ref err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(PipelineCacheError::Io(err)),
+        };
+        self.load_pipeline_cache(&data)
+            .map_err(PipelineCacheError::OutOfMemory)
+    }
+
+    /// Save pipeline cache data to the file.
+    pub fn save_pipeline_cache_file(
+        &self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<(), PipelineCacheError> {
+        let data = self
+            .pipeline_cache_data()
+            .map_err(PipelineCacheError::OutOfMemory)?;
+        std::fs::write(path, data).map_err(PipelineCacheError::Io)
+    }
+
+    /// Get features enabled on the device.
+    /// Includes required features and supported optional features from `Config`.
+    pub fn features(&self) -> Features {
+        self.features
+    }
+
     /// Create new semaphore.
     pub fn create_semaphore(&self) -> Result<B::Semaphore, OutOfMemory> {
         profile_scope!("create_semaphore");
@@ -878,6 +1302,34 @@ where
         unsafe { self.device.destroy_fence(fence.into_inner()) }
     }
 
+    /// Create new typed query pool with `count` queries.
+    ///
+    /// Resulting pool must be destroyed using [`destroy_relevant_query_pool`].
+    ///
+    /// [`destroy_relevant_query_pool`]: #method.destroy_relevant_query_pool
+    pub fn create_relevant_query_pool<Q>(
+        &self,
+        ty: Q,
+        count: gfx_hal::query::Id,
+    ) -> Result<QueryPool<B, Q>, gfx_hal::query::CreationError>
+    where
+        Q: QueryType,
+    {
+        QueryPool::create(&self.device, ty, count)
+    }
+
+    /// Destroy typed query pool.
+    ///
+    /// # Safety
+    ///
+    /// Query pool must not be used by any pending commands.
+    pub unsafe fn destroy_relevant_query_pool<Q>(&self, pool: QueryPool<B, Q>)
+    where
+        Q: QueryType,
+    {
+        pool.dispose(&self.device);
+    }
+
     /// Create new command pool for specified family.
     pub fn create_command_pool<R>(
         &self,
@@ -978,6 +1430,268 @@ where
         Ok(self.resources.layouts.escape(layout))
     }
 
+    /// Get cached descriptor set layout with specified bindings or create new one.
+    pub fn get_descriptor_set_layout(
+        &self,
+        bindings: Vec<DescriptorSetLayoutBinding>,
+    ) -> Result<Handle<DescriptorSetLayout<B>>, OutOfMemory> {
+        let layouts = &self.resources.layouts;
+        let info = DescriptorSetInfo { bindings };
+        let key = CacheKey::new(&info);
+
+        ResourceCache::get_with_upgradable_lock(
+            self.resources.layouts_cache.upgradable_read(),
+            parking_lot::RwLockUpgradableReadGuard::upgrade,
+            key,
+            || Ok(layouts.handle(self.create_relevant_descriptor_set_layout(info.bindings)?)),
+        )
+    }
+
+    /// Create render pass.
+    ///
+    /// This function returns relevant value, that is, the value cannot be dropped.
+    /// However render pass can be destroyed using [`destroy_relevant_render_pass`] function.
+    ///
+    /// [`destroy_relevant_render_pass`]: #method.destroy_relevant_render_pass
+    pub fn create_relevant_render_pass(
+        &self,
+        attachments: Vec<gfx_hal::pass::Attachment>,
+        subpasses: Vec<gfx_hal::pass::SubpassDesc<'_>>,
+        dependencies: Vec<gfx_hal::pass::SubpassDependency>,
+    ) -> Result<RenderPass<B>, OutOfMemory> {
+        unsafe { RenderPass::create(&self.device, attachments, subpasses, dependencies) }
+    }
+
+    /// Destroy render pass.
+    ///
+    /// # Safety
+    ///
+    /// Render pass must not be used by any pending commands or referenced anywhere.
+    ///
+    pub unsafe fn destroy_relevant_render_pass(&self, render_pass: RenderPass<B>) {
+        render_pass.dispose(&self.device);
+    }
+
+    /// Create render pass.
+    ///
+    /// This function (unlike [`create_relevant_render_pass`]) returns value that can be dropped.
+    ///
+    /// [`create_relevant_render_pass`]: #method.create_relevant_render_pass
+    pub fn create_render_pass(
+        &self,
+        attachments: Vec<gfx_hal::pass::Attachment>,
+        subpasses: Vec<gfx_hal::pass::SubpassDesc<'_>>,
+        dependencies: Vec<gfx_hal::pass::SubpassDependency>,
+    ) -> Result<Escape<RenderPass<B>>, OutOfMemory> {
+        let render_pass = self.create_relevant_render_pass(attachments, subpasses, dependencies)?;
+        Ok(self.resources.render_passes.escape(render_pass))
+    }
+
+    /// Get cached render pass with specified description or create new one.
+    /// Graphics pipelines created for cached render passes
+    /// are cached by [`get_graphics_pipeline`] too.
+    ///
+    /// [`get_graphics_pipeline`]: #method.get_graphics_pipeline
+    pub fn get_render_pass(
+        &self,
+        attachments: Vec<gfx_hal::pass::Attachment>,
+        subpasses: Vec<gfx_hal::pass::SubpassDesc<'_>>,
+        dependencies: Vec<gfx_hal::pass::SubpassDependency>,
+    ) -> Result<Handle<RenderPass<B>>, OutOfMemory> {
+        let render_passes = &self.resources.render_passes;
+        let key = RenderPass::<B>::key(&attachments, &subpasses, &dependencies);
+
+        ResourceCache::get_with_upgradable_lock(
+            self.resources.render_passes_cache.upgradable_read(),
+            parking_lot::RwLockUpgradableReadGuard::upgrade,
+            key,
+            || {
+                let render_pass = render_passes.handle(self.create_relevant_render_pass(
+                    attachments,
+                    subpasses,
+                    dependencies,
+                )?);
+                self.resources.render_pass_compatibility.write().insert(
+                    render_pass_address::<B>(render_pass.raw()),
+                    render_pass.compatibility_key(),
+                );
+                Ok(render_pass)
+            },
+        )
+    }
+
+    /// Create pipeline layout with specified descriptor set layouts and push constants.
+    pub fn create_relevant_pipeline_layout(
+        &self,
+        sets: Vec<Handle<DescriptorSetLayout<B>>>,
+        push_constants: Vec<(gfx_hal::pso::ShaderStageFlags, std::ops::Range<u32>)>,
+    ) -> Result<PipelineLayout<B>, OutOfMemory> {
+        unsafe { PipelineLayout::create(&self.device, sets, push_constants) }
+    }
+
+    /// Create pipeline layout with specified descriptor set layouts and push constants.
+    pub fn create_pipeline_layout(
+        &self,
+        sets: Vec<Handle<DescriptorSetLayout<B>>>,
+        push_constants: Vec<(gfx_hal::pso::ShaderStageFlags, std::ops::Range<u32>)>,
+    ) -> Result<Escape<PipelineLayout<B>>, OutOfMemory> {
+        let layout = self.create_relevant_pipeline_layout(sets, push_constants)?;
+        Ok(self.resources.pipeline_layouts.escape(layout))
+    }
+
+    /// Get cached pipeline layout with specified descriptor set layouts and push constants
+    /// or create new one.
+    /// Layouts are keyed by descriptor set layout bindings,
+    /// so descriptor set layouts of the cached layout may differ from ones specified.
+    pub fn get_pipeline_layout(
+        &self,
+        sets: Vec<Handle<DescriptorSetLayout<B>>>,
+        push_constants: Vec<(gfx_hal::pso::ShaderStageFlags, std::ops::Range<u32>)>,
+    ) -> Result<Handle<PipelineLayout<B>>, OutOfMemory> {
+        let pipeline_layouts = &self.resources.pipeline_layouts;
+        let key = CacheKey::new(&(
+            sets.iter().map(|set| set.info()).collect::<Vec<_>>(),
+            &push_constants,
+        ));
+
+        ResourceCache::get_with_upgradable_lock(
+            self.resources.pipeline_layouts_cache.upgradable_read(),
+            parking_lot::RwLockUpgradableReadGuard::upgrade,
+            key,
+            || {
+                Ok(pipeline_layouts
+                    .handle(self.create_relevant_pipeline_layout(sets, push_constants)?))
+            },
+        )
+    }
+
+    /// Create graphics pipeline.
+    /// `desc.layout` must be raw pipeline layout of the `layout`.
+    pub fn create_relevant_graphics_pipeline(
+        &self,
+        desc: &gfx_hal::pso::GraphicsPipelineDesc<'_, B>,
+        layout: Handle<PipelineLayout<B>>,
+    ) -> Result<GraphicsPipeline<B>, gfx_hal::pso::CreationError> {
+        unsafe { GraphicsPipeline::create(&self.device, desc, layout, Some(self.pipeline_cache())) }
+    }
+
+    /// Create graphics pipeline.
+    /// `desc.layout` must be raw pipeline layout of the `layout`.
+    pub fn create_graphics_pipeline(
+        &self,
+        desc: &gfx_hal::pso::GraphicsPipelineDesc<'_, B>,
+        layout: Handle<PipelineLayout<B>>,
+    ) -> Result<Escape<GraphicsPipeline<B>>, gfx_hal::pso::CreationError> {
+        let pipeline = self.create_relevant_graphics_pipeline(desc, layout)?;
+        Ok(self.resources.graphics_pipelines.escape(pipeline))
+    }
+
+    /// Get cached graphics pipeline or create new one.
+    /// `desc.layout` must be raw pipeline layout of the `layout`.
+    ///
+    /// `key` must identify everything in `desc` except subpass,
+    /// that is shaders, layout, vertex input, rasterization, blend and depth-stencil states.
+    /// Subpass is identified by its index and compatibility of the render pass.
+    ///
+    /// Pipeline is cached only if render pass was acquired with [`get_render_pass`].
+    /// Otherwise new pipeline is created.
+    ///
+    /// Cached pipeline may be bound to another but equivalent layout.
+    /// Use [`GraphicsPipeline::layout`] to get actual layout of the pipeline.
+    ///
+    /// [`get_render_pass`]: #method.get_render_pass
+    /// [`GraphicsPipeline::layout`]: ../rendy_resource/struct.GraphicsPipeline.html#method.layout
+    pub fn get_graphics_pipeline(
+        &self,
+        key: CacheKey,
+        desc: &gfx_hal::pso::GraphicsPipelineDesc<'_, B>,
+        layout: Handle<PipelineLayout<B>>,
+    ) -> Result<Handle<GraphicsPipeline<B>>, gfx_hal::pso::CreationError> {
+        let graphics_pipelines = &self.resources.graphics_pipelines;
+
+        let compatibility = self
+            .resources
+            .render_pass_compatibility
+            .read()
+            .get(&render_pass_address::<B>(desc.subpass.main_pass))
+            .cloned();
+
+        let compatibility = match compatibility {
+            Some(compatibility) => compatibility,
+            None => {
+                log::debug!("Render pass is not cached. Create uncached graphics pipeline");
+                return Ok(self.create_graphics_pipeline(desc, layout)?.into());
+            }
+        };
+
+        let key = key
+            .combine(compatibility)
+            .combine(CacheKey::new(&desc.subpass.index));
+
+        ResourceCache::get_with_upgradable_lock(
+            self.resources.graphics_pipelines_cache.upgradable_read(),
+            parking_lot::RwLockUpgradableReadGuard::upgrade,
+            key,
+            || Ok(graphics_pipelines.handle(self.create_relevant_graphics_pipeline(desc, layout)?)),
+        )
+    }
+
+    /// Drop handle of graphics pipeline acquired with [`get_graphics_pipeline`]
+    /// and evict the pipeline from the cache if it is not used anywhere else.
+    /// Pipeline is destroyed once commands submitted before are complete.
+    ///
+    /// [`get_graphics_pipeline`]: #method.get_graphics_pipeline
+    pub fn release_graphics_pipeline(&self, pipeline: Handle<GraphicsPipeline<B>>) {
+        self.resources
+            .graphics_pipelines_cache
+            .write()
+            .release(pipeline);
+    }
+
+    /// Drop cached handlers of descriptor set layouts, render passes, pipeline layouts
+    /// and graphics pipelines.
+    /// Resources are destroyed once they are not used anymore.
+    /// Caches are never cleared automatically.
+    pub fn clear_pipeline_caches(&self) {
+        self.resources.graphics_pipelines_cache.write().clear();
+        self.resources.pipeline_layouts_cache.write().clear();
+        {
+            let mut render_passes = self.resources.render_passes_cache.write();
+            render_passes.clear();
+            self.resources.render_pass_compatibility.write().clear();
+        }
+        self.resources.layouts_cache.write().clear();
+    }
+
+    /// Drop cached handlers of descriptor set layouts, render passes, pipeline layouts
+    /// and graphics pipelines that are not used outside of the caches.
+    /// Layouts of evicted pipelines stay cached until the pipelines are destroyed.
+    ///
+    /// Caches are not evicted automatically, as graph rebuilt after disposal
+    /// can reuse pipelines of the old one.
+    /// Call this function once such resources are not going to be requested again.
+    pub fn evict_unused_pipeline_caches(&self) {
+        self.resources
+            .graphics_pipelines_cache
+            .write()
+            .evict_unused();
+        self.resources.pipeline_layouts_cache.write().evict_unused();
+        {
+            let mut render_passes = self.resources.render_passes_cache.write();
+            render_passes.evict_unused();
+            *self.resources.render_pass_compatibility.write() = render_passes
+                .iter()
+                .map(|render_pass| {
+                    (
+                        render_pass_address::<B>(render_pass.raw()),
+                        render_pass.compatibility_key(),
+                    )
+                })
+                .collect();
+        }
+        self.resources.layouts_cache.write().evict_unused();
+    }
+
     /// Create descriptor sets with specified layout.
     pub fn create_relevant_descriptor_set(
         &self,
@@ -1052,7 +1766,7 @@ where
 #[allow(unused_variables)]
 pub fn init<B>(
     config: Config<impl DevicesConfigure, impl HeapsConfigure, impl QueuesConfigure>,
-) -> Result<(Factory<B>, Families<B>), gfx_hal::device::CreationError>
+) -> Result<(Factory<B>, Families<B>), InitError>
 where
     B: Backend,
 {
@@ -1065,23 +1779,33 @@ where
     });
 }
 
+/// Address of the raw render pass.
+/// Cached render passes are not moved, so address identifies them while they are cached.
+fn render_pass_address<B: Backend>(raw: &B::RenderPass) -> usize {
+    let ptr: *const B::RenderPass = raw;
+    ptr as usize
+}
+
 /// Initialize `Factory` and Queue `Families` associated with Device
 /// using existing `Instance`.
 pub fn init_with_instance<B>(
     instance: impl gfx_hal::Instance<Backend = B>,
     config: Config<impl DevicesConfigure, impl HeapsConfigure, impl QueuesConfigure>,
-) -> Result<(Factory<B>, Families<B>), gfx_hal::device::CreationError>
+) -> Result<(Factory<B>, Families<B>), InitError>
 where
     B: Backend,
 {
     rendy_with_slow_safety_checks!(
         log::warn!("Slow safety checks are enabled! Disable them in production by enabling the 'no-slow-safety-checks' feature!")
     );
-    let mut adapters = instance.enumerate_adapters();
+    let adapters = instance.enumerate_adapters();
 
     if adapters.is_empty() {
         log::warn!("No physical devices found");
-        return Err(gfx_hal::device::CreationError::InitializationFailed);
+        return Err(InitError::Adapter(AdapterNotFound {
+            adapters: Vec::new(),
+            filter_error: None,
+        }));
     }
 
     log::debug!(
@@ -1092,7 +1816,45 @@ where
             .collect::<SmallVec<[_; 32]>>()
     );
 
-    let picked = config.devices.pick(&adapters);
+    let infos = adapters
+        .iter()
+        .map(|adapter| adapter.info.clone())
+        .collect();
+
+    let mut unsupported = Vec::new();
+    let mut rejected = Vec::new();
+    let mut adapters = adapters
+        .into_iter()
+        .filter_map(|adapter| {
+            let physical = &adapter.physical_device;
+            match config
+                .requirements
+                .check(physical.features(), &physical.limits())
+            {
+                Ok(()) => Some(adapter),
+                Err(missing) => {
+                    log::debug!("Physical device {} skipped: {}", adapter.info.name, missing);
+                    unsupported.push((adapter.info.clone(), missing));
+                    rejected.push(adapter);
+                    None
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let picked = match config.devices.pick(&adapters) {
+        Ok(picked) => picked,
+        Err(error) => {
+            // Configuration may match only adapters lacking required capabilities.
+            return Err(match config.devices.pick(&rejected) {
+                Ok(_) => InitError::Unsupported(unsupported),
+                Err(_) => InitError::Adapter(AdapterNotFound {
+                    adapters: infos,
+                    ..error
+                }),
+            });
+        }
+    };
     if picked >= adapters.len() {
         panic!("Physical device pick config returned index out of bound");
     }
@@ -1117,6 +1879,12 @@ where
     let instance = Instance::new(instance);
     let device_id = DeviceId::new(instance.id());
 
+    let features = config
+        .requirements
+        .features(adapter.physical_device.features());
+
+    log::debug!("Features enabled: {:?}", features);
+
     let (device, families) = {
         let families = config
             .queues
@@ -1138,11 +1906,7 @@ where
         let Gpu {
             device,
             mut queue_groups,
-        } = unsafe {
-            adapter
-                .physical_device
-                .open(&create_queues, adapter.physical_device.features())
-        }?;
+        } = unsafe { adapter.physical_device.open(&create_queues, features) }?;
 
         let families = unsafe {
             families_from_device(
@@ -1173,6 +1937,9 @@ where
         .map(|f| parking_lot::RwLock::new(vec![0; f.as_slice().len()]))
         .collect();
 
+    let pipeline_cache = unsafe { device.create_pipeline_cache(None) }
+        .map_err(gfx_hal::device::CreationError::OutOfMemory)?;
+
     let factory = Factory {
         descriptor_allocator: ManuallyDrop::new(
             parking_lot::Mutex::new(DescriptorAllocator::new()),
@@ -1184,6 +1951,8 @@ where
         blitter: unsafe { Blitter::new(&device, &families) }
             .map_err(gfx_hal::device::CreationError::OutOfMemory)?,
         families_indices: families.indices().into(),
+        features,
+        pipeline_cache: ManuallyDrop::new(pipeline_cache),
         epochs,
         device,
         adapter,
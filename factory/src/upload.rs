@@ -5,11 +5,15 @@ use {
             CommandBuffer, CommandPool, Families, Family, IndividualReset, InitialState, OneShot,
             PendingOnceState, PrimaryLevel, QueueId, RecordingState, Submission, Transfer,
         },
+        factory::Factory,
         resource::{Buffer, Escape, Handle, Image},
         util::Device,
     },
-    gfx_hal::device::{Device as _, OutOfMemory},
-    std::{collections::VecDeque, iter::once},
+    gfx_hal::{
+        device::{Device as _, MapError, OomOrDeviceLost, OutOfMemory},
+        queue::QueueFamilyId,
+    },
+    std::{collections::VecDeque, iter::once, ops::Range, sync::Arc},
 };
 
 /// State of the buffer on device.
@@ -120,6 +124,143 @@ impl From<gfx_hal::image::Layout> for ImageStateOrLayout {
     }
 }
 
+/// Pending download of buffer or image content into host-visible staging buffer.
+///
+/// Copy operation will actually be submitted to the graphics device queue
+/// upon next [`flush_uploads`] or [`maintain`] call to the `Factory`.
+/// Content can be read once download is complete.
+///
+/// [`flush_uploads`]: struct.Factory.html#method.flush_uploads
+/// [`maintain`]: struct.Factory.html#method.maintain
+#[derive(derivative::Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct Download<B: gfx_hal::Backend> {
+    staging: Arc<parking_lot::Mutex<Escape<Buffer<B>>>>,
+    queue: QueueId,
+    batch: u64,
+    format: Option<gfx_hal::format::Format>,
+}
+
+impl<B> Download<B>
+where
+    B: gfx_hal::Backend,
+{
+    /// Queue that performs the download.
+    pub fn queue(&self) -> QueueId {
+        self.queue
+    }
+
+    /// Format of the downloaded image.
+    /// `None` for buffer downloads.
+    pub fn format(&self) -> Option<gfx_hal::format::Format> {
+        self.format
+    }
+
+    /// Size of the downloaded content in bytes.
+    pub fn size(&self) -> u64 {
+        self.staging.lock().size()
+    }
+
+    /// Check if download is complete.
+    /// Returns `false` if download wasn't flushed yet.
+    pub fn is_complete(&self, factory: &Factory<B>) -> bool {
+        unsafe {
+            factory
+                .uploader()
+                .is_complete(factory.device(), self.queue, self.batch)
+        }
+    }
+
+    /// Wait for download to complete.
+    ///
+    /// # Panics
+    ///
+    /// Panics if download wasn't flushed yet.
+    pub fn wait(&self, factory: &Factory<B>) -> Result<(), OomOrDeviceLost> {
+        unsafe {
+            factory
+                .uploader()
+                .wait(factory.device(), self.queue, self.batch)
+        }
+    }
+
+    /// Read downloaded content as slice of `T`.
+    /// Returns `None` if download is not complete.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is zero-sized
+    /// or size of downloaded content is not multiple of size of `T`.
+    pub fn read<T>(&self, factory: &Factory<B>) -> Result<Option<Vec<T>>, MapError>
+    where
+        T: 'static + Copy,
+    {
+        let element = std::mem::size_of::<T>() as u64;
+        assert_ne!(element, 0, "Content can't be read as zero-sized type");
+
+        if !self.is_complete(factory) {
+            return Ok(None);
+        }
+
+        let mut staging = self.staging.lock();
+        let size = staging.size();
+        assert_content_size(size, element);
+
+        let mut mapped = staging.map(factory.device(), 0..size)?;
+        let content = unsafe {
+            // Download is complete. Staging buffer is not used by device.
+            mapped.read::<T>(factory.device(), 0..size)?.to_vec()
+        };
+        Ok(Some(content))
+    }
+
+    /// Read downloaded content as bytes.
+    /// Returns `None` if download is not complete.
+    pub fn bytes(&self, factory: &Factory<B>) -> Result<Option<Vec<u8>>, MapError> {
+        self.read::<u8>(factory)
+    }
+}
+
+fn assert_content_size(size: u64, element: u64) {
+    assert_eq!(
+        size % element,
+        0,
+        "Content size must be multiple of element size"
+    );
+}
+
+/// Size in bytes of the tightly packed content of the image region.
+/// Partial blocks at the edge of the level are copied whole.
+///
+/// # Panics
+///
+/// Panics if the layer range is empty or the region doesn't fit into the mip level.
+pub(crate) fn download_image_size(
+    kind: gfx_hal::image::Kind,
+    format: gfx_hal::format::Format,
+    layers: &gfx_hal::image::SubresourceLayers,
+    offset: gfx_hal::image::Offset,
+    extent: gfx_hal::image::Extent,
+) -> u64 {
+    assert!(layers.layers.start < layers.layers.end);
+    assert!(layers.layers.end <= kind.num_layers());
+    assert!(extent.width > 0 && extent.height > 0 && extent.depth > 0);
+    assert!(offset.x >= 0 && offset.y >= 0 && offset.z >= 0);
+
+    let level_extent = kind.level_extent(layers.level);
+    assert!(offset.x as u32 + extent.width <= level_extent.width);
+    assert!(offset.y as u32 + extent.height <= level_extent.height);
+    assert!(offset.z as u32 + extent.depth <= level_extent.depth);
+
+    let desc = format.surface_desc();
+    let blocks = |size: u32, dim: u8| ((size + dim as u32 - 1) / dim as u32) as u64;
+    let blocks_count = blocks(extent.width, desc.dim.0)
+        * blocks(extent.height, desc.dim.1)
+        * extent.depth as u64
+        * (layers.layers.end - layers.layers.start) as u64;
+    (desc.bits as u64 / 8) * blocks_count
+}
+
 #[derive(Debug)]
 pub(crate) struct Uploader<B: gfx_hal::Backend> {
     family_uploads: Vec<Option<parking_lot::Mutex<FamilyUploads<B>>>>,
@@ -150,11 +291,22 @@ where
                 next: Vec::new(),
                 pending: VecDeque::new(),
                 command_buffers: Vec::new(),
+                releases: Vec::new(),
+                pending_releases: VecDeque::new(),
+                release_buffers: Vec::new(),
+                semaphores: Vec::new(),
                 barriers: Barriers::new(
                     gfx_hal::pso::PipelineStage::TRANSFER,
                     gfx_hal::buffer::Access::TRANSFER_WRITE,
                     gfx_hal::image::Access::TRANSFER_WRITE,
                 ),
+                download_barriers: Barriers::new(
+                    gfx_hal::pso::PipelineStage::TRANSFER,
+                    gfx_hal::buffer::Access::TRANSFER_READ
+                        | gfx_hal::buffer::Access::TRANSFER_WRITE,
+                    gfx_hal::image::Access::TRANSFER_READ,
+                ),
+                batches: 0,
             }));
         }
 
@@ -175,25 +327,51 @@ where
         last: Option<BufferState>,
         next: BufferState,
     ) -> Result<(), OutOfMemory> {
+        let acquire = match last {
+            Some(last) if last.queue != next.queue => Some(self.release(
+                device,
+                last.queue,
+                next.queue,
+                last.stage,
+                true,
+                |families| gfx_hal::memory::Barrier::Buffer {
+                    states: last.access..gfx_hal::buffer::Access::empty(),
+                    families: Some(families),
+                    target: buffer.raw(),
+                    range: None..None,
+                },
+            )?),
+            _ => None,
+        };
+
         let mut family_uploads = self.family_uploads[next.queue.family.index]
             .as_ref()
             .unwrap()
             .lock();
 
-        if let Some(last) = last {
-            if last.queue != next.queue {
-                unimplemented!("Can't sync resources across queues");
-            }
-        }
+        let last_stage = match &acquire {
+            // Ownership acquire barrier synchronizes with the transfer.
+            Some(acquire) if acquire.families.is_some() => gfx_hal::pso::PipelineStage::empty(),
+            _ => last.map_or(gfx_hal::pso::PipelineStage::empty(), |l| l.stage),
+        };
 
         family_uploads.barriers.add_buffer(
-            last.map_or(gfx_hal::pso::PipelineStage::empty(), |l| l.stage),
+            last_stage,
             gfx_hal::buffer::Access::empty(),
             next.stage,
             next.access,
         );
 
         let next_upload = family_uploads.next_upload(device, next.queue.index)?;
+        if let Some(acquire) = acquire {
+            next_upload.acquire(acquire, |families| gfx_hal::memory::Barrier::Buffer {
+                states: gfx_hal::buffer::Access::empty()..gfx_hal::buffer::Access::TRANSFER_WRITE,
+                families: Some(families),
+                target: buffer.raw(),
+                range: None..None,
+            });
+        }
+
         let mut encoder = next_upload.command_buffer.encoder();
         encoder.copy_buffer(
             staging.raw(),
@@ -216,47 +394,81 @@ where
     ///
     pub(crate) unsafe fn transition_image(
         &self,
+        device: &Device<B>,
         image: Handle<Image<B>>,
         image_range: gfx_hal::image::SubresourceRange,
         last: ImageStateOrLayout,
         next: ImageState,
-    ) {
+    ) -> Result<(), OutOfMemory> {
         use gfx_hal::image::{Access, Layout};
 
-        let mut family_uploads = self.family_uploads[next.queue.family.index]
-            .as_ref()
-            .unwrap()
-            .lock();
-
-        let (last_stage, mut last_access, last_layout) = match last.into() {
-            ImageStateOrLayout::State(last) => {
-                if last.queue != next.queue {
-                    unimplemented!("Can't sync resources across queues");
-                }
-                (last.stage, last.access, last.layout)
+        let (acquire, last_stage, mut last_access, last_layout) = match last {
+            ImageStateOrLayout::State(last) if last.queue != next.queue => {
+                let acquire = self.release(
+                    device,
+                    last.queue,
+                    next.queue,
+                    last.stage,
+                    last.layout != Layout::Undefined,
+                    |families| gfx_hal::memory::Barrier::Image {
+                        states: (last.access, last.layout)..(Access::empty(), next.layout),
+                        families: Some(families),
+                        target: image.raw(),
+                        range: image_range.clone(),
+                    },
+                )?;
+                (Some(acquire), last.stage, Access::empty(), last.layout)
             }
+            ImageStateOrLayout::State(last) => (None, last.stage, last.access, last.layout),
             ImageStateOrLayout::Layout(last_layout) => (
+                None,
                 gfx_hal::pso::PipelineStage::TOP_OF_PIPE,
                 Access::empty(),
                 last_layout,
             ),
         };
 
-        if last_layout == Layout::Undefined || last_layout == next.layout {
+        let mut family_uploads = self.family_uploads[next.queue.family.index]
+            .as_ref()
+            .unwrap()
+            .lock();
+
+        let (last_stage, transition_layout) = match &acquire {
+            // Layout transition is performed by ownership transfer.
+            Some(acquire) if acquire.families.is_some() => {
+                (gfx_hal::pso::PipelineStage::empty(), next.layout)
+            }
+            _ => (last_stage, last_layout),
+        };
+
+        if transition_layout == Layout::Undefined || transition_layout == next.layout {
             last_access = Access::empty();
         }
 
         family_uploads.barriers.add_image(
             image.clone(),
-            image_range,
+            image_range.clone(),
             last_stage,
             last_access,
-            last_layout,
+            transition_layout,
             next.layout,
             next.stage,
             next.access,
             next.layout,
         );
+
+        if let Some(acquire) = acquire {
+            // Transition must wait for release even if nothing is uploaded.
+            let next_upload = family_uploads.next_upload(device, next.queue.index)?;
+            next_upload.acquire(acquire, |families| gfx_hal::memory::Barrier::Image {
+                states: (Access::empty(), last_layout)..(Access::TRANSFER_WRITE, next.layout),
+                families: Some(families),
+                target: image.raw(),
+                range: image_range,
+            });
+        }
+
+        Ok(())
     }
 
     /// # Safety
@@ -279,11 +491,6 @@ where
     ) -> Result<(), OutOfMemory> {
         use gfx_hal::image::{Access, Layout};
 
-        let mut family_uploads = self.family_uploads[next.queue.family.index]
-            .as_ref()
-            .unwrap()
-            .lock();
-
         let whole_extent = if image_layers.level == 0 {
             image.kind().extent()
         } else {
@@ -299,49 +506,69 @@ where
             layers: image_layers.layers.clone(),
         };
 
-        let (last_stage, mut last_access, last_layout) = match last.into() {
-            ImageStateOrLayout::State(last) => {
-                if last.queue != next.queue {
-                    unimplemented!("Can't sync resources across queues");
-                }
-                (
+        let last_layout = match last {
+            _ if whole_level => Layout::Undefined,
+            ImageStateOrLayout::State(last) => last.layout,
+            ImageStateOrLayout::Layout(last_layout) => last_layout,
+        };
+
+        let target_layout = match (last_layout, next.layout) {
+            (Layout::TransferDstOptimal, _) => Layout::TransferDstOptimal,
+            (_, Layout::General) => Layout::General,
+            (Layout::General, _) => Layout::General,
+            _ => Layout::TransferDstOptimal,
+        };
+
+        let (acquire, last_stage, last_access) = match last {
+            ImageStateOrLayout::State(last) if last.queue != next.queue => {
+                let acquire = self.release(
+                    device,
+                    last.queue,
+                    next.queue,
                     last.stage,
-                    last.access,
-                    if whole_level {
-                        Layout::Undefined
-                    } else {
-                        last.layout
+                    last_layout != Layout::Undefined,
+                    |families| gfx_hal::memory::Barrier::Image {
+                        states: (last.access, last_layout)..(Access::empty(), target_layout),
+                        families: Some(families),
+                        target: image.raw(),
+                        range: image_range.clone(),
                     },
-                )
+                )?;
+                (Some(acquire), last.stage, Access::empty())
             }
-            ImageStateOrLayout::Layout(last_layout) => (
+            ImageStateOrLayout::State(last) => (None, last.stage, last.access),
+            ImageStateOrLayout::Layout(_) => (
+                None,
                 gfx_hal::pso::PipelineStage::TOP_OF_PIPE,
                 Access::empty(),
-                if whole_level {
-                    Layout::Undefined
-                } else {
-                    last_layout
-                },
             ),
         };
 
-        let target_layout = match (last_layout, next.layout) {
-            (Layout::TransferDstOptimal, _) => Layout::TransferDstOptimal,
-            (_, Layout::General) => Layout::General,
-            (Layout::General, _) => Layout::General,
-            _ => Layout::TransferDstOptimal,
+        let mut family_uploads = self.family_uploads[next.queue.family.index]
+            .as_ref()
+            .unwrap()
+            .lock();
+
+        let (last_stage, mut last_access, transition_layout) = match &acquire {
+            // Layout transition is performed by ownership transfer.
+            Some(acquire) if acquire.families.is_some() => (
+                gfx_hal::pso::PipelineStage::empty(),
+                Access::empty(),
+                target_layout,
+            ),
+            _ => (last_stage, last_access, last_layout),
         };
 
-        if last_layout == Layout::Undefined || last_layout == target_layout {
+        if transition_layout == Layout::Undefined || transition_layout == target_layout {
             last_access = Access::empty();
         }
 
         family_uploads.barriers.add_image(
             image.clone(),
-            image_range,
+            image_range.clone(),
             last_stage,
             last_access,
-            last_layout,
+            transition_layout,
             target_layout,
             next.stage,
             next.access,
@@ -349,6 +576,15 @@ where
         );
 
         let next_upload = family_uploads.next_upload(device, next.queue.index)?;
+        if let Some(acquire) = acquire {
+            next_upload.acquire(acquire, |families| gfx_hal::memory::Barrier::Image {
+                states: (Access::empty(), last_layout)..(Access::TRANSFER_WRITE, target_layout),
+                families: Some(families),
+                target: image.raw(),
+                range: image_range,
+            });
+        }
+
         let mut encoder = next_upload.command_buffer.encoder();
         encoder.copy_buffer_to_image(
             staging.raw(),
@@ -368,6 +604,243 @@ where
         Ok(())
     }
 
+    /// # Safety
+    ///
+    /// `device` must be the same that was used to create this `Uploader`.
+    /// `buffer` and `staging` must belong to the `device`.
+    ///
+    pub(crate) unsafe fn download_buffer(
+        &self,
+        device: &Device<B>,
+        buffer: &Buffer<B>,
+        offset: u64,
+        staging: Escape<Buffer<B>>,
+        state: BufferState,
+    ) -> Result<Download<B>, OutOfMemory> {
+        let mut family_uploads = self.family_uploads[state.queue.family.index]
+            .as_ref()
+            .unwrap()
+            .lock();
+
+        family_uploads.download_barriers.add_buffer(
+            state.stage,
+            state.access,
+            state.stage | gfx_hal::pso::PipelineStage::HOST,
+            state.access | gfx_hal::buffer::Access::HOST_READ,
+        );
+
+        let next_upload = family_uploads.next_upload(device, state.queue.index)?;
+        let mut encoder = next_upload.command_buffer.encoder();
+        encoder.copy_buffer(
+            buffer.raw(),
+            staging.raw(),
+            Some(gfx_hal::command::BufferCopy {
+                src: offset,
+                dst: 0,
+                size: staging.size(),
+            }),
+        );
+
+        let staging = Arc::new(parking_lot::Mutex::new(staging));
+        next_upload.download_buffers.push(staging.clone());
+
+        Ok(Download {
+            staging,
+            queue: state.queue,
+            batch: next_upload.batch,
+            format: None,
+        })
+    }
+
+    /// # Safety
+    ///
+    /// `device` must be the same that was used to create this `Uploader`.
+    /// `image` and `staging` must belong to the `device`.
+    ///
+    pub(crate) unsafe fn download_image(
+        &self,
+        device: &Device<B>,
+        image: Handle<Image<B>>,
+        image_layers: gfx_hal::image::SubresourceLayers,
+        image_offset: gfx_hal::image::Offset,
+        image_extent: gfx_hal::image::Extent,
+        staging: Escape<Buffer<B>>,
+        state: ImageState,
+    ) -> Result<Download<B>, OutOfMemory> {
+        use gfx_hal::image::Layout;
+
+        let mut family_uploads = self.family_uploads[state.queue.family.index]
+            .as_ref()
+            .unwrap()
+            .lock();
+
+        let image_range = gfx_hal::image::SubresourceRange {
+            aspects: image_layers.aspects,
+            levels: image_layers.level..image_layers.level + 1,
+            layers: image_layers.layers.clone(),
+        };
+
+        let target_layout = match state.layout {
+            Layout::General => Layout::General,
+            _ => Layout::TransferSrcOptimal,
+        };
+
+        family_uploads.download_barriers.add_image(
+            image.clone(),
+            image_range,
+            state.stage,
+            state.access,
+            state.layout,
+            target_layout,
+            state.stage,
+            state.access,
+            state.layout,
+        );
+
+        family_uploads.download_barriers.add_buffer(
+            gfx_hal::pso::PipelineStage::empty(),
+            gfx_hal::buffer::Access::empty(),
+            gfx_hal::pso::PipelineStage::HOST,
+            gfx_hal::buffer::Access::HOST_READ,
+        );
+
+        let next_upload = family_uploads.next_upload(device, state.queue.index)?;
+        let mut encoder = next_upload.command_buffer.encoder();
+        encoder.copy_image_to_buffer(
+            image.raw(),
+            target_layout,
+            staging.raw(),
+            Some(gfx_hal::command::BufferImageCopy {
+                buffer_offset: 0,
+                buffer_width: 0,
+                buffer_height: 0,
+                image_layers,
+                image_offset,
+                image_extent,
+            }),
+        );
+
+        let staging = Arc::new(parking_lot::Mutex::new(staging));
+        next_upload.download_buffers.push(staging.clone());
+
+        Ok(Download {
+            staging,
+            queue: state.queue,
+            batch: next_upload.batch,
+            format: Some(image.format()),
+        })
+    }
+
+    /// Record release of the resource by `last` queue.
+    /// Returns semaphore that `next` queue must wait for before using the resource
+    /// and queue family ownership transfer that must be acquired.
+    /// Ownership is transferred only when families differ and content is preserved.
+    ///
+    /// # Safety
+    ///
+    /// `device` must be the same that was used to create this `Uploader`.
+    /// Resource in barrier must belong to the `device`.
+    ///
+    unsafe fn release<'a>(
+        &self,
+        device: &Device<B>,
+        last: QueueId,
+        next: QueueId,
+        stage: gfx_hal::pso::PipelineStage,
+        preserve: bool,
+        barrier: impl FnOnce(Range<QueueFamilyId>) -> gfx_hal::memory::Barrier<'a, B>,
+    ) -> Result<Acquire<B>, OutOfMemory> {
+        let families = if preserve && last.family != next.family {
+            Some(QueueFamilyId(last.family.index)..QueueFamilyId(next.family.index))
+        } else {
+            None
+        };
+
+        let mut family_uploads = self.family_uploads[last.family.index]
+            .as_ref()
+            .unwrap()
+            .lock();
+
+        let semaphore = family_uploads.semaphore(device)?;
+        let next_release = family_uploads.next_release(device, last.index)?;
+
+        if let Some(families) = families.clone() {
+            next_release.command_buffer.encoder().pipeline_barrier(
+                stage..gfx_hal::pso::PipelineStage::BOTTOM_OF_PIPE,
+                gfx_hal::memory::Dependencies::empty(),
+                once(barrier(families)),
+            );
+        }
+
+        next_release.signals.push(semaphore.clone());
+        Ok(Acquire {
+            semaphore,
+            families,
+        })
+    }
+
+    /// Check if batch of uploads and downloads is complete.
+    ///
+    /// # Safety
+    ///
+    /// `device` must be the same that was used to create this `Uploader`.
+    ///
+    pub(crate) unsafe fn is_complete(
+        &self,
+        device: &Device<B>,
+        queue: QueueId,
+        batch: u64,
+    ) -> bool {
+        let family_uploads = self.family_uploads[queue.family.index]
+            .as_ref()
+            .unwrap()
+            .lock();
+
+        if family_uploads.is_next(batch) {
+            return false;
+        }
+
+        match family_uploads.pending.iter().find(|p| p.batch == batch) {
+            Some(pending) => match device.get_fence_status(&pending.fence) {
+                Ok(complete) => complete,
+                Err(gfx_hal::device::DeviceLost) => {
+                    panic!("Device lost error is not handled yet");
+                }
+            },
+            None => true,
+        }
+    }
+
+    /// Wait for batch of uploads and downloads to complete.
+    ///
+    /// # Safety
+    ///
+    /// `device` must be the same that was used to create this `Uploader`.
+    ///
+    pub(crate) unsafe fn wait(
+        &self,
+        device: &Device<B>,
+        queue: QueueId,
+        batch: u64,
+    ) -> Result<(), OomOrDeviceLost> {
+        let family_uploads = self.family_uploads[queue.family.index]
+            .as_ref()
+            .unwrap()
+            .lock();
+
+        assert!(
+            !family_uploads.is_next(batch),
+            "Batch must be flushed before waiting"
+        );
+
+        if let Some(pending) = family_uploads.pending.iter().find(|p| p.batch == batch) {
+            let ready = device.wait_for_fence(&pending.fence, !0)?;
+            debug_assert!(ready);
+        }
+
+        Ok(())
+    }
+
     /// Cleanup pending updates.
     ///
     /// # Safety
@@ -389,6 +862,15 @@ where
     /// `families` must be the same that was used to create this `Uploader`.
     ///
     pub(crate) unsafe fn flush(&mut self, families: &mut Families<B>) {
+        // Releases are submitted first so that semaphores uploads wait for
+        // are signaled by commands already submitted.
+        for family in families.as_slice_mut() {
+            let uploader = self.family_uploads[family.id().index]
+                .as_mut()
+                .expect("Uploader must be initialized for all families");
+            uploader.get_mut().flush_releases(family);
+        }
+
         for family in families.as_slice_mut() {
             let uploader = self.family_uploads[family.id().index]
                 .as_mut()
@@ -416,8 +898,14 @@ pub(crate) struct FamilyUploads<B: gfx_hal::Backend> {
         Vec<[CommandBuffer<B, Transfer, InitialState, PrimaryLevel, IndividualReset>; 2]>,
     next: Vec<Option<NextUploads<B>>>,
     pending: VecDeque<PendingUploads<B>>,
+    release_buffers: Vec<CommandBuffer<B, Transfer, InitialState, PrimaryLevel, IndividualReset>>,
+    releases: Vec<Option<NextReleases<B>>>,
+    pending_releases: VecDeque<PendingReleases<B>>,
     fences: Vec<B::Fence>,
+    semaphores: Vec<B::Semaphore>,
     barriers: Barriers<B>,
+    download_barriers: Barriers<B>,
+    batches: u64,
 }
 
 #[derive(Debug)]
@@ -425,7 +913,10 @@ pub(crate) struct PendingUploads<B: gfx_hal::Backend> {
     barrier_buffer: CommandBuffer<B, Transfer, PendingOnceState, PrimaryLevel, IndividualReset>,
     command_buffer: CommandBuffer<B, Transfer, PendingOnceState, PrimaryLevel, IndividualReset>,
     staging_buffers: Vec<Escape<Buffer<B>>>,
+    download_buffers: Vec<Arc<parking_lot::Mutex<Escape<Buffer<B>>>>>,
+    waits: Vec<Arc<B::Semaphore>>,
     fence: B::Fence,
+    batch: u64,
 }
 
 #[derive(Debug)]
@@ -435,13 +926,88 @@ struct NextUploads<B: gfx_hal::Backend> {
     command_buffer:
         CommandBuffer<B, Transfer, RecordingState<OneShot>, PrimaryLevel, IndividualReset>,
     staging_buffers: Vec<Escape<Buffer<B>>>,
+    download_buffers: Vec<Arc<parking_lot::Mutex<Escape<Buffer<B>>>>>,
+    waits: Vec<Arc<B::Semaphore>>,
+    fence: B::Fence,
+    batch: u64,
+}
+
+impl<B> NextUploads<B>
+where
+    B: gfx_hal::Backend,
+{
+    /// Wait for resource release and acquire its ownership.
+    unsafe fn acquire<'a>(
+        &mut self,
+        acquire: Acquire<B>,
+        barrier: impl FnOnce(Range<QueueFamilyId>) -> gfx_hal::memory::Barrier<'a, B>,
+    ) {
+        if let Some(families) = acquire.families {
+            self.barrier_buffer.encoder().pipeline_barrier(
+                gfx_hal::pso::PipelineStage::TRANSFER..gfx_hal::pso::PipelineStage::TRANSFER,
+                gfx_hal::memory::Dependencies::empty(),
+                once(barrier(families)),
+            );
+        }
+        self.waits.push(acquire.semaphore);
+    }
+}
+
+/// Resources released by one queue to be used by another.
+#[derive(Debug)]
+struct NextReleases<B: gfx_hal::Backend> {
+    command_buffer:
+        CommandBuffer<B, Transfer, RecordingState<OneShot>, PrimaryLevel, IndividualReset>,
+    signals: Vec<Arc<B::Semaphore>>,
+    fence: B::Fence,
+}
+
+#[derive(Debug)]
+struct PendingReleases<B: gfx_hal::Backend> {
+    command_buffer: CommandBuffer<B, Transfer, PendingOnceState, PrimaryLevel, IndividualReset>,
+    signals: Vec<Arc<B::Semaphore>>,
     fence: B::Fence,
 }
 
+/// Semaphore signaled after resource release
+/// and queue family ownership transfer to acquire.
+#[derive(Debug)]
+struct Acquire<B: gfx_hal::Backend> {
+    semaphore: Arc<B::Semaphore>,
+    families: Option<Range<QueueFamilyId>>,
+}
+
 impl<B> FamilyUploads<B>
 where
     B: gfx_hal::Backend,
 {
+    unsafe fn flush_releases(&mut self, family: &mut Family<B>) {
+        for (queue, next) in self
+            .releases
+            .drain(..)
+            .enumerate()
+            .filter_map(|(i, x)| x.map(|x| (i, x)))
+        {
+            let (submit, command_buffer) = next.command_buffer.finish().submit_once();
+
+            family.queue_mut(queue).submit_raw_fence(
+                Some(
+                    Submission::new()
+                        .wait(std::iter::empty::<(&Arc<B::Semaphore>, _)>())
+                        .submits(once(submit))
+                        .signal(next.signals.iter()),
+                ),
+                Some(&next.fence),
+            );
+
+            self.pending_releases.push_back(PendingReleases {
+                command_buffer,
+                signals: next.signals,
+                fence: next.fence,
+            });
+        }
+    }
+
     unsafe fn flush(&mut self, family: &mut Family<B>) {
         for (queue, mut next) in self
             .next
@@ -453,13 +1019,24 @@ where
             let mut encoder = next.command_buffer.encoder();
 
             self.barriers.encode_before(&mut barriers_encoder);
+            self.download_barriers.encode_before(&mut barriers_encoder);
             self.barriers.encode_after(&mut encoder);
+            self.download_barriers.encode_after(&mut encoder);
 
             let (barriers_submit, barrier_buffer) = next.barrier_buffer.finish().submit_once();
             let (submit, command_buffer) = next.command_buffer.finish().submit_once();
 
             family.queue_mut(queue).submit_raw_fence(
-                Some(Submission::new().submits(once(barriers_submit).chain(once(submit)))),
+                Some(
+                    Submission::new()
+                        .wait(
+                            next.waits
+                                .iter()
+                                .map(|w| (w, gfx_hal::pso::PipelineStage::TRANSFER)),
+                        )
+                        .submits(once(barriers_submit).chain(once(submit)))
+                        .signal(std::iter::empty::<&Arc<B::Semaphore>>()),
+                ),
                 Some(&next.fence),
             );
 
@@ -467,7 +1044,10 @@ where
                 barrier_buffer,
                 command_buffer,
                 staging_buffers: next.staging_buffers,
+                download_buffers: next.download_buffers,
+                waits: next.waits,
                 fence: next.fence,
+                batch: next.batch,
             });
         }
     }
@@ -498,6 +1078,43 @@ where
                     barrier_buffer: buf_a.begin(OneShot, ()),
                     command_buffer: buf_b.begin(OneShot, ()),
                     staging_buffers: Vec::new(),
+                    download_buffers: Vec::new(),
+                    waits: Vec::new(),
+                    fence,
+                    batch: self.batches,
+                });
+                self.batches += 1;
+
+                Ok(slot.as_mut().unwrap())
+            }
+        }
+    }
+
+    unsafe fn next_release(
+        &mut self,
+        device: &Device<B>,
+        queue: usize,
+    ) -> Result<&mut NextReleases<B>, OutOfMemory> {
+        while self.releases.len() <= queue {
+            self.releases.push(None);
+        }
+
+        let pool = &mut self.pool;
+
+        match &mut self.releases[queue] {
+            Some(next) => Ok(next),
+            slot @ None => {
+                let buf = self
+                    .release_buffers
+                    .pop()
+                    .unwrap_or_else(|| pool.allocate_buffers(1).remove(0));
+                let fence = self
+                    .fences
+                    .pop()
+                    .map_or_else(|| device.create_fence(false), Ok)?;
+                *slot = Some(NextReleases {
+                    command_buffer: buf.begin(OneShot, ()),
+                    signals: Vec::new(),
                     fence,
                 });
 
@@ -506,6 +1123,27 @@ where
         }
     }
 
+    unsafe fn semaphore(&mut self, device: &Device<B>) -> Result<Arc<B::Semaphore>, OutOfMemory> {
+        let semaphore = self
+            .semaphores
+            .pop()
+            .map_or_else(|| device.create_semaphore(), Ok)?;
+        Ok(Arc::new(semaphore))
+    }
+
+    /// Take back semaphores no longer used by either side.
+    fn recycle_semaphores(&mut self, semaphores: Vec<Arc<B::Semaphore>>) {
+        self.semaphores.extend(
+            semaphores
+                .into_iter()
+                .filter_map(|s| Arc::try_unwrap(s).ok()),
+        );
+    }
+
+    fn is_next(&self, batch: u64) -> bool {
+        self.next.iter().flatten().any(|next| next.batch == batch)
+    }
+
     /// Cleanup pending updates.
     ///
     /// # Safety
@@ -513,6 +1151,27 @@ where
     /// `device` must be the same that was used with other methods of this instance.
     ///
     unsafe fn cleanup(&mut self, device: &Device<B>) {
+        while let Some(pending) = self.pending_releases.pop_front() {
+            match device.get_fence_status(&pending.fence) {
+                Ok(false) => {
+                    self.pending_releases.push_front(pending);
+                    break;
+                }
+                Err(gfx_hal::device::DeviceLost) => {
+                    panic!("Device lost error is not handled yet");
+                }
+                Ok(true) => {
+                    device
+                        .reset_fence(&pending.fence)
+                        .expect("Can always reset signalled fence");
+                    self.fences.push(pending.fence);
+                    self.release_buffers
+                        .push(pending.command_buffer.mark_complete().reset());
+                    self.recycle_semaphores(pending.signals);
+                }
+            }
+        }
+
         while let Some(pending) = self.pending.pop_front() {
             match device.get_fence_status(&pending.fence) {
                 Ok(false) => {
@@ -531,6 +1190,7 @@ where
                         pending.command_buffer.mark_complete().reset(),
                         pending.barrier_buffer.mark_complete().reset(),
                     ]);
+                    self.recycle_semaphores(pending.waits);
                 }
             }
         }
@@ -542,10 +1202,19 @@ where
     ///
     unsafe fn dispose(mut self, device: &Device<B>) {
         let pool = &mut self.pool;
+        let mut semaphores = Vec::new();
+
         self.pending.drain(..).for_each(|pending| {
             device.destroy_fence(pending.fence);
             pool.free_buffers(Some(pending.command_buffer.mark_complete()));
             pool.free_buffers(Some(pending.barrier_buffer.mark_complete()));
+            semaphores.extend(pending.waits);
+        });
+
+        self.pending_releases.drain(..).for_each(|pending| {
+            device.destroy_fence(pending.fence);
+            pool.free_buffers(Some(pending.command_buffer.mark_complete()));
+            semaphores.extend(pending.signals);
         });
 
         self.fences
@@ -556,12 +1225,130 @@ where
                 .drain(..)
                 .flat_map(|[a, b]| once(a).chain(once(b))),
         );
+        pool.free_buffers(self.release_buffers.drain(..));
 
         pool.free_buffers(self.next.drain(..).filter_map(|n| n).flat_map(|next| {
             device.destroy_fence(next.fence);
+            semaphores.extend(next.waits);
             once(next.command_buffer).chain(once(next.barrier_buffer))
         }));
+
+        pool.free_buffers(self.releases.drain(..).filter_map(|n| n).map(|next| {
+            device.destroy_fence(next.fence);
+            semaphores.extend(next.signals);
+            next.command_buffer
+        }));
+
+        // Semaphores shared with other families are destroyed by the last owner.
+        semaphores
+            .into_iter()
+            .filter_map(|s| Arc::try_unwrap(s).ok())
+            .chain(self.semaphores.drain(..))
+            .for_each(|semaphore| device.destroy_semaphore(semaphore));
+
         drop(pool);
         self.pool.dispose(device);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use gfx_hal::{
+        format::{Aspects, Format},
+        image::{Extent, Kind, Offset, SubresourceLayers},
+    };
+
+    fn layers(level: u8, layers: std::ops::Range<u16>) -> SubresourceLayers {
+        SubresourceLayers {
+            aspects: Aspects::COLOR,
+            level,
+            layers,
+        }
+    }
+
+    fn extent(width: u32, height: u32) -> Extent {
+        Extent {
+            width,
+            height,
+            depth: 1,
+        }
+    }
+
+    #[test]
+    fn test_content_size_multiple_of_element() {
+        assert_content_size(16, 4);
+        assert_content_size(0, 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "Content size must be multiple of element size")]
+    fn test_content_size_not_multiple_of_element() {
+        assert_content_size(10, 4);
+    }
+
+    #[test]
+    fn test_download_image_size() {
+        let kind = Kind::D2(16, 8, 4, 1);
+        assert_eq!(
+            download_image_size(
+                kind,
+                Format::Rgba8Unorm,
+                &layers(0, 1..3),
+                Offset::ZERO,
+                extent(3, 2)
+            ),
+            3 * 2 * 4 * 2
+        );
+        assert_eq!(
+            download_image_size(
+                kind,
+                Format::Rgba8Unorm,
+                &layers(1, 0..1),
+                Offset { x: 4, y: 2, z: 0 },
+                extent(4, 2)
+            ),
+            4 * 2 * 4
+        );
+    }
+
+    #[test]
+    fn test_download_image_size_partial_block() {
+        // Level 2 of 8x8 BC1 image is 2x2, smaller than a single 4x4 block.
+        assert_eq!(
+            download_image_size(
+                Kind::D2(8, 8, 1, 1),
+                Format::Bc1RgbUnorm,
+                &layers(2, 0..1),
+                Offset::ZERO,
+                extent(2, 2)
+            ),
+            8
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_download_image_empty_layers() {
+        download_image_size(
+            Kind::D2(16, 8, 4, 1),
+            Format::Rgba8Unorm,
+            &layers(0, 2..2),
+            Offset::ZERO,
+            extent(1, 1),
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_download_image_region_outside_level() {
+        // Level 1 is 8x4.
+        download_image_size(
+            Kind::D2(16, 8, 1, 1),
+            Format::Rgba8Unorm,
+            &layers(1, 0..1),
+            Offset { x: 4, y: 0, z: 0 },
+            extent(8, 4),
+        );
+    }
+}
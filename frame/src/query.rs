@@ -0,0 +1,98 @@
+//! Query pools for reading results of previous frames.
+
+use {
+    crate::{
+        factory::Factory,
+        resource::{QueryPool, QueryType},
+    },
+    gfx_hal::Backend,
+};
+
+/// Query pools bound to indices of command cirque values.
+///
+/// Commands recorded into command buffer with some index
+/// use query pool with the same index.
+/// When command cirque gives that index again, previous submission of the
+/// command buffer is complete as its frame's `Fences` are signaled.
+/// Thus results of that submission can be read without stalling.
+#[derive(derivative::Derivative)]
+#[derivative(Debug(bound = "Q: std::fmt::Debug"))]
+pub struct FrameQueries<B: Backend, Q> {
+    ty: Q,
+    count: gfx_hal::query::Id,
+    pools: Vec<Option<(QueryPool<B, Q>, bool)>>,
+}
+
+impl<B, Q> FrameQueries<B, Q>
+where
+    B: Backend,
+    Q: QueryType,
+{
+    /// Create new `FrameQueries` with `count` queries per index.
+    /// Query pools are created lazily.
+    pub fn new(ty: Q, count: gfx_hal::query::Id) -> Self {
+        FrameQueries {
+            ty,
+            count,
+            pools: Vec::new(),
+        }
+    }
+
+    /// Get query pool to record queries with command buffer of specified index.
+    /// Queries must be reset before use every time commands are submitted.
+    pub fn pool(
+        &mut self,
+        factory: &Factory<B>,
+        index: usize,
+    ) -> Result<&QueryPool<B, Q>, gfx_hal::query::CreationError> {
+        if self.pools.len() <= index {
+            self.pools.resize_with(index + 1, || None);
+        }
+
+        let slot = &mut self.pools[index];
+        if slot.is_none() {
+            *slot = Some((
+                factory.create_relevant_query_pool(self.ty, self.count)?,
+                false,
+            ));
+        }
+
+        let (pool, recorded) = slot.as_mut().unwrap();
+        *recorded = true;
+        Ok(pool)
+    }
+
+    /// Read results of queries recorded with command buffer of specified index
+    /// by its previous submission.
+    /// `data` must hold `result_len` values for each query.
+    ///
+    /// Returns `false` if no queries were recorded with this index
+    /// or results are not available.
+    ///
+    /// # Safety
+    ///
+    /// Command buffer with specified index must be acquired from command cirque
+    /// for the current frame and queries must be written by its previous submission.
+    pub unsafe fn read(
+        &self,
+        factory: &Factory<B>,
+        index: usize,
+        data: &mut [u64],
+    ) -> Result<bool, gfx_hal::device::OomOrDeviceLost> {
+        match self.pools.get(index) {
+            Some(Some((pool, true))) => pool.results(factory.device(), 0..self.count, data, false),
+            _ => Ok(false),
+        }
+    }
+
+    /// Dispose of the `FrameQueries`.
+    ///
+    /// # Safety
+    ///
+    /// Query pools must not be used by pending commands.
+    pub unsafe fn dispose(self, factory: &Factory<B>) {
+        for (pool, _) in self.pools.into_iter().flatten() {
+            factory.destroy_relevant_query_pool(pool);
+        }
+    }
+}
@@ -4,21 +4,32 @@ use {
         command::{Families, FamilyId, QueueId},
         factory::Factory,
         frame::{Fences, Frame, Frames},
-        memory::Data,
+        memory::{Data, MemoryBlock},
         node::{
             BufferBarrier, DynNode, ImageBarrier, NodeBuffer, NodeBuildError, NodeBuilder,
             NodeImage,
         },
         resource::{
-            Buffer, BufferCreationError, BufferInfo, Handle, Image, ImageCreationError, ImageInfo,
+            Buffer, BufferCreationError, BufferInfo, CreationError, Handle, Image,
+            ImageCreationError, ImageInfo,
         },
         util::{device_owned, DeviceId},
         BufferId, ImageId, NodeId,
     },
     gfx_hal::{queue::QueueFamilyId, Backend},
+    std::{
+        cmp::{max, min},
+        collections::{HashMap, HashSet},
+        ops::Range,
+    },
     thread_profiler::profile_scope,
 };
 
+mod timing;
+
+pub use self::timing::GpuTimings;
+use self::timing::NodeTimer;
+
 #[derive(Debug)]
 struct GraphNode<B: Backend, T: ?Sized> {
     node: Box<dyn DynNode<B, T>>,
@@ -36,6 +47,7 @@ pub struct Graph<B: Backend, T: ?Sized> {
     fences: Vec<Fences<B>>,
     inflight: u32,
     ctx: GraphContext<B>,
+    timer: Option<NodeTimer<B>>,
 }
 
 device_owned!(Graph<B, T: ?Sized>);
@@ -51,80 +63,454 @@ pub enum GraphBuildError {
     Semaphore(gfx_hal::device::OutOfMemory),
     /// Failed to build a node.
     Node(NodeBuildError),
+    /// Failed to create a query pool for GPU timing.
+    QueryPool(gfx_hal::query::CreationError),
+    /// Nodes access resources in a way that can't be scheduled.
+    Chain(chain::CollectError),
 }
 
 /// Graphics context contains all transient resources managed by graph.
+/// Resources used on the same queue in non-overlapping ranges of submissions share memory,
+/// unless they read content left from the previous frame.
 #[derive(Debug)]
 pub struct GraphContext<B: Backend> {
     buffers: Vec<Option<Handle<Buffer<B>>>>,
     images: Vec<Option<(Handle<Image<B>>, Option<gfx_hal::command::ClearValue>)>>,
+    aliased_buffers: HashMap<BufferId, (gfx_hal::buffer::Access, gfx_hal::pso::PipelineStage)>,
+    aliased_images: HashMap<ImageId, (gfx_hal::image::Access, gfx_hal::pso::PipelineStage)>,
+    memory: Vec<MemoryBlock<B>>,
     /// Number of potential frames in flight
     pub frames_in_flight: u32,
 }
 
+/// Transient resource that is yet to be bound to memory.
+#[derive(Debug)]
+struct Unbound<R> {
+    index: usize,
+    raw: R,
+    requirements: gfx_hal::memory::Requirements,
+    /// Range of submissions using the resource.
+    /// `None` if resource can't share memory with other resources.
+    lifetime: Option<(chain::QueueId, Range<usize>)>,
+}
+
+/// Set of transient resources sharing one memory block.
+#[derive(Debug)]
+struct AliasGroup<R> {
+    queue: Option<chain::QueueId>,
+    end: usize,
+    mask: u32,
+    size: u64,
+    align: u64,
+    resources: Vec<Unbound<R>>,
+}
+
+impl<R> AliasGroup<R> {
+    fn new(resource: Unbound<R>) -> Self {
+        AliasGroup {
+            queue: resource.lifetime.as_ref().map(|(qid, _)| *qid),
+            end: resource
+                .lifetime
+                .as_ref()
+                .map_or(0, |(_, lifetime)| lifetime.end),
+            mask: resource.requirements.type_mask as u32,
+            size: resource.requirements.size,
+            align: resource.requirements.alignment,
+            resources: vec![resource],
+        }
+    }
+
+    /// Check if resource can be placed into the group.
+    /// Lifetimes of all resources in the group must be
+    /// on the same queue and must not overlap.
+    fn fits(&self, resource: &Unbound<R>) -> bool {
+        match (&self.queue, &resource.lifetime) {
+            (Some(queue), Some((qid, lifetime))) => {
+                queue == qid
+                    && self.end <= lifetime.start
+                    && self.mask & resource.requirements.type_mask as u32 != 0
+            }
+            _ => false,
+        }
+    }
+
+    fn push(&mut self, resource: Unbound<R>) {
+        let (_, lifetime) = resource.lifetime.as_ref().unwrap();
+        self.end = lifetime.end;
+        self.mask &= resource.requirements.type_mask as u32;
+        self.size = max(self.size, resource.requirements.size);
+        self.align = max(self.align, resource.requirements.alignment);
+        self.resources.push(resource);
+    }
+}
+
+/// Find range of submissions in which resource is used.
+/// Returns `None` if resource is used on multiple queues,
+/// as aliasing would require additional semaphores.
+fn chain_lifetime<R: chain::Resource>(
+    chain: &chain::Chain<R>,
+    schedule: &chain::Schedule<chain::Unsynchronized>,
+) -> Option<(chain::QueueId, Range<usize>)> {
+    let mut queues = chain.links().iter().flat_map(|link| link.queues());
+    let (qid, queue) = queues.next()?;
+    let mut first = queue.first;
+    let mut last = queue.last;
+    for (other, queue) in queues {
+        if other != qid {
+            return None;
+        }
+        first = min(first, queue.first);
+        last = max(last, queue.last);
+    }
+
+    let submit_order = |index| schedule[chain::SubmissionId::new(qid, index)].submit_order();
+    Some((qid, submit_order(first)..submit_order(last) + 1))
+}
+
+/// Check if the first access to the buffer in the frame reads its content.
+/// Such buffer may read content written in the previous frame,
+/// so its memory can't be shared with other buffers.
+fn buffer_reads_previous_frame(chain: &chain::Chain<chain::Buffer>) -> bool {
+    let writes = gfx_hal::buffer::Access::SHADER_WRITE
+        | gfx_hal::buffer::Access::TRANSFER_WRITE
+        | gfx_hal::buffer::Access::HOST_WRITE
+        | gfx_hal::buffer::Access::MEMORY_WRITE;
+    chain
+        .links()
+        .first()
+        .map_or(false, |link| !(link.access() - writes).is_empty())
+}
+
+/// Check if the first access to the image in the frame reads its content.
+/// Such image may read content written in the previous frame,
+/// so its memory can't be shared with other images.
+///
+/// Attachment reads of the node for which `clears` returns `true` are ignored,
+/// as the node clears the image instead of loading it.
+fn image_reads_previous_frame(
+    chain: &chain::Chain<chain::Image>,
+    schedule: &chain::Schedule<chain::Unsynchronized>,
+    clears: impl Fn(usize) -> bool,
+) -> bool {
+    let writes = gfx_hal::image::Access::SHADER_WRITE
+        | gfx_hal::image::Access::COLOR_ATTACHMENT_WRITE
+        | gfx_hal::image::Access::DEPTH_STENCIL_ATTACHMENT_WRITE
+        | gfx_hal::image::Access::TRANSFER_WRITE
+        | gfx_hal::image::Access::HOST_WRITE
+        | gfx_hal::image::Access::MEMORY_WRITE;
+
+    let link = match chain.links().first() {
+        Some(link) => link,
+        None => return false,
+    };
+    let mut reads = link.access() - writes;
+
+    // Attachment writes are exclusive, so node writing the attachment is alone in the link.
+    let mut queues = link.queues();
+    if let (Some((qid, queue)), None) = (queues.next(), queues.next()) {
+        let sid = chain::SubmissionId::new(qid, queue.first);
+        if queue.first == queue.last && clears(schedule[sid].node()) {
+            reads -= gfx_hal::image::Access::COLOR_ATTACHMENT_READ
+                | gfx_hal::image::Access::DEPTH_STENCIL_ATTACHMENT_READ;
+        }
+    }
+
+    !reads.is_empty()
+}
+
+/// Find range of submissions in which image is used.
+/// Returns `None` if image can't share memory with other images.
+fn image_lifetime(
+    chain: &chain::Chain<chain::Image>,
+    schedule: &chain::Schedule<chain::Unsynchronized>,
+    clears: impl Fn(usize) -> bool,
+) -> Option<(chain::QueueId, Range<usize>)> {
+    if image_reads_previous_frame(chain, schedule, clears) {
+        None
+    } else {
+        chain_lifetime(chain, schedule)
+    }
+}
+
+/// Distribute resources into groups that can share memory.
+/// Resources are placed greedily in order of their first use.
+fn alias_groups<R>(mut resources: Vec<Unbound<R>>) -> Vec<AliasGroup<R>> {
+    resources.sort_by_key(|resource| {
+        resource
+            .lifetime
+            .as_ref()
+            .map_or(0, |(_, lifetime)| lifetime.start)
+    });
+
+    let mut groups: Vec<AliasGroup<R>> = Vec::new();
+    for resource in resources {
+        let best = groups
+            .iter_mut()
+            .filter(|group| group.fits(&resource))
+            .min_by_key(|group| {
+                let size = resource.requirements.size;
+                max(group.size, size) - min(group.size, size)
+            });
+
+        match best {
+            Some(group) => group.push(resource),
+            None => groups.push(AliasGroup::new(resource)),
+        }
+    }
+    groups
+}
+
 impl<B: Backend> GraphContext<B> {
+    /// Create transient resources and bind them to memory.
+    /// `first_use_clears` contains pairs of node and image index
+    /// where node clears the image if it is the first one to use it in the frame.
     fn alloc<'a>(
         factory: &Factory<B>,
         chains: &chain::Chains,
         buffers: impl IntoIterator<Item = &'a BufferInfo>,
         images: impl IntoIterator<Item = &'a (ImageInfo, Option<gfx_hal::command::ClearValue>)>,
+        first_use_clears: &HashSet<(usize, usize)>,
         frames_in_flight: u32,
     ) -> Result<Self, GraphBuildError> {
         profile_scope!("alloc");
 
+        let buffers: Vec<_> = buffers.into_iter().cloned().collect();
+        let images: Vec<_> = images.into_iter().cloned().collect();
+
+        let mut ctx = GraphContext {
+            buffers: buffers.iter().map(|_| None).collect(),
+            images: images.iter().map(|_| None).collect(),
+            aliased_buffers: HashMap::new(),
+            aliased_images: HashMap::new(),
+            memory: Vec::new(),
+            frames_in_flight,
+        };
+
+        let mut unbound_buffers = Vec::new();
+        let mut unbound_images = Vec::new();
+
+        let result = ctx
+            .alloc_buffers(factory, chains, &buffers, &mut unbound_buffers)
+            .and_then(|()| {
+                ctx.alloc_images(
+                    factory,
+                    chains,
+                    &images,
+                    first_use_clears,
+                    &mut unbound_images,
+                )
+            });
+
+        if let Err(err) = result {
+            unsafe {
+                // Resources were never used.
+                for unbound in unbound_buffers {
+                    factory.destroy_unbound_buffer(unbound.raw);
+                }
+                for unbound in unbound_images {
+                    factory.destroy_unbound_image(unbound.raw);
+                }
+                ctx.dispose(factory);
+            }
+            return Err(err);
+        }
+
+        Ok(ctx)
+    }
+
+    /// Create transient buffers.
+    /// Raw buffers that are not bound to memory are left in `unbound` on failure.
+    fn alloc_buffers(
+        &mut self,
+        factory: &Factory<B>,
+        chains: &chain::Chains,
+        buffers: &[BufferInfo],
+        unbound: &mut Vec<Unbound<B::Buffer>>,
+    ) -> Result<(), GraphBuildError> {
         log::trace!("Allocate buffers");
-        let buffers: Vec<Option<Handle<Buffer<B>>>> = buffers
-            .into_iter()
-            .enumerate()
-            .map(|(index, info)| {
-                chains
-                    .buffers
-                    .get(&chain::Id(index))
-                    .map(|buffer| {
-                        factory
-                            .create_buffer(
-                                BufferInfo {
-                                    usage: buffer.usage(),
-                                    ..info.clone()
-                                },
-                                Data,
-                            )
-                            .map(|buffer| Some(buffer.into()))
-                    })
-                    .unwrap_or(Ok(None))
-            })
-            .collect::<Result<_, _>>()
-            .map_err(GraphBuildError::Buffer)?;
+        for (index, &info) in buffers.iter().enumerate() {
+            if let Some(buffer) = chains.buffers.get(&chain::Id(index)) {
+                let info = BufferInfo {
+                    usage: buffer.usage(),
+                    ..info
+                };
+                let (raw, requirements) = unsafe { factory.create_unbound_buffer(info) }
+                    .map_err(GraphBuildError::Buffer)?;
+                unbound.push(Unbound {
+                    index,
+                    raw,
+                    requirements,
+                    lifetime: if buffer_reads_previous_frame(buffer) {
+                        None
+                    } else {
+                        chain_lifetime(buffer, &chains.schedule)
+                    },
+                });
+            }
+        }
+
+        let mut groups = alias_groups(unbound.drain(..).collect()).into_iter();
+        while let Some(group) = groups.next() {
+            log::trace!(
+                "Allocate memory for {} buffers: size: {}, align: {}",
+                group.resources.len(),
+                group.size,
+                group.align
+            );
+            let block = match factory.allocate_memory(group.mask, Data, group.size, group.align) {
+                Ok(block) => block,
+                Err(err) => {
+                    unbound.extend(group.resources);
+                    unbound.extend(groups.flat_map(|group| group.resources));
+                    return Err(GraphBuildError::Buffer(CreationError::Allocate(err)));
+                }
+            };
+            self.memory.push(block);
+            let block = self.memory.last().unwrap();
+
+            // Resource that used memory last before the current one.
+            // The first one follows the last one from previous frame.
+            let count = group.resources.len();
+            let previous: Vec<_> = group
+                .resources
+                .iter()
+                .map(|resource| {
+                    let state = chains.buffers[&chain::Id(resource.index)]
+                        .links()
+                        .last()
+                        .unwrap()
+                        .state();
+                    (state.access, state.stages)
+                })
+                .collect();
+
+            let mut resources = group.resources.into_iter().enumerate();
+            while let Some((i, Unbound { index, raw, .. })) = resources.next() {
+                let info = BufferInfo {
+                    usage: chains.buffers[&chain::Id(index)].usage(),
+                    ..buffers[index]
+                };
+                let buffer = match unsafe { factory.create_aliased_buffer(raw, info, block, 0) } {
+                    Ok(buffer) => buffer,
+                    Err(err) => {
+                        unbound.extend(resources.map(|(_, resource)| resource));
+                        unbound.extend(groups.flat_map(|group| group.resources));
+                        return Err(GraphBuildError::Buffer(err));
+                    }
+                };
+                self.buffers[index] = Some(buffer.into());
+                if count > 1 {
+                    self.aliased_buffers
+                        .insert(BufferId(index), previous[(i + count - 1) % count]);
+                }
+            }
+        }
 
+        Ok(())
+    }
+
+    /// Create transient images.
+    /// Raw images that are not bound to memory are left in `unbound` on failure.
+    fn alloc_images(
+        &mut self,
+        factory: &Factory<B>,
+        chains: &chain::Chains,
+        images: &[(ImageInfo, Option<gfx_hal::command::ClearValue>)],
+        first_use_clears: &HashSet<(usize, usize)>,
+        unbound: &mut Vec<Unbound<B::Image>>,
+    ) -> Result<(), GraphBuildError> {
         log::trace!("Allocate images");
-        let images: Vec<Option<(Handle<Image<B>>, _)>> = images
-            .into_iter()
-            .enumerate()
-            .map(|(index, (info, clear))| {
-                chains
-                    .images
-                    .get(&chain::Id(index))
-                    .map(|image| {
-                        factory
-                            .create_image(
-                                ImageInfo {
-                                    usage: image.usage(),
-                                    ..info.clone()
-                                },
-                                Data,
-                            )
-                            .map(|image| Some((image.into(), *clear)))
-                    })
-                    .unwrap_or(Ok(None))
-            })
-            .collect::<Result<_, _>>()
-            .map_err(GraphBuildError::Image)?;
+        for (index, &(info, _)) in images.iter().enumerate() {
+            if let Some(image) = chains.images.get(&chain::Id(index)) {
+                let info = ImageInfo {
+                    usage: image.usage(),
+                    ..info
+                };
+                let (raw, requirements) = unsafe { factory.create_unbound_image(info) }
+                    .map_err(GraphBuildError::Image)?;
+                unbound.push(Unbound {
+                    index,
+                    raw,
+                    requirements,
+                    lifetime: image_lifetime(image, &chains.schedule, |node| {
+                        first_use_clears.contains(&(node, index))
+                    }),
+                });
+            }
+        }
 
-        Ok(Self {
-            buffers,
-            images,
-            frames_in_flight,
-        })
+        let mut groups = alias_groups(unbound.drain(..).collect()).into_iter();
+        while let Some(group) = groups.next() {
+            log::trace!(
+                "Allocate memory for {} images: size: {}, align: {}",
+                group.resources.len(),
+                group.size,
+                group.align
+            );
+            let block = match factory.allocate_memory(group.mask, Data, group.size, group.align) {
+                Ok(block) => block,
+                Err(err) => {
+                    unbound.extend(group.resources);
+                    unbound.extend(groups.flat_map(|group| group.resources));
+                    return Err(GraphBuildError::Image(CreationError::Allocate(err)));
+                }
+            };
+            self.memory.push(block);
+            let block = self.memory.last().unwrap();
+
+            // Resource that used memory last before the current one.
+            // The first one follows the last one from previous frame.
+            let count = group.resources.len();
+            let previous: Vec<_> = group
+                .resources
+                .iter()
+                .map(|resource| {
+                    let state = chains.images[&chain::Id(resource.index)]
+                        .links()
+                        .last()
+                        .unwrap()
+                        .state();
+                    (state.access, state.stages)
+                })
+                .collect();
+
+            let mut resources = group.resources.into_iter().enumerate();
+            while let Some((i, Unbound { index, raw, .. })) = resources.next() {
+                let (info, clear) = images[index];
+                let info = ImageInfo {
+                    usage: chains.images[&chain::Id(index)].usage(),
+                    ..info
+                };
+                let image = match unsafe { factory.create_aliased_image(raw, info, block, 0) } {
+                    Ok(image) => image,
+                    Err(err) => {
+                        unbound.extend(resources.map(|(_, resource)| resource));
+                        unbound.extend(groups.flat_map(|group| group.resources));
+                        return Err(GraphBuildError::Image(err));
+                    }
+                };
+                self.images[index] = Some((image.into(), clear));
+                if count > 1 {
+                    self.aliased_images
+                        .insert(ImageId(index), previous[(i + count - 1) % count]);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Free memory shared by transient resources.
+    ///
+    /// # Safety
+    ///
+    /// Transient resources must not be used by pending commands.
+    unsafe fn dispose(self, factory: &Factory<B>) {
+        drop(self.buffers);
+        drop(self.images);
+        for block in self.memory {
+            factory.free_memory(block);
+        }
     }
 
     /// Get reference to transient image by id.
@@ -168,8 +554,17 @@ where
                 factory.reset_fences(&mut fences).unwrap();
                 self_fences.push(fences);
             });
+
+            if let Some(timer) = &mut self.timer {
+                unsafe {
+                    // Frame is complete.
+                    timer.collect(factory, wait.index());
+                }
+            }
         }
 
+        let frame = self.frames.next().index();
+
         let mut fences = self.fences.pop().unwrap_or_else(Fences::<B>::default);
         let mut fences_used = 0;
         let ref semaphores = self.semaphores;
@@ -179,6 +574,7 @@ where
             let sid = submission.id();
             let qid = sid.queue();
 
+            let index = submission.node();
             let GraphNode { node, queue } = self
                 .nodes
                 .get_mut(submission.node())
@@ -200,6 +596,12 @@ where
                 None
             };
 
+            let timed = match &self.timer {
+                Some(timer) => unsafe { timer.begin(families, *queue, frame, index) },
+                None => false,
+            };
+            let (fence, end_fence) = if timed { (None, fence) } else { (fence, None) };
+
             unsafe {
                 node.run(
                     &self.ctx,
@@ -236,12 +638,23 @@ where
                     fence,
                 )
             }
+
+            if let (true, Some(timer)) = (timed, &self.timer) {
+                unsafe { timer.end(families, *queue, frame, index, end_fence) };
+            }
         }
 
         fences.truncate(fences_used);
         self.frames.advance(fences);
     }
 
+    /// Get GPU timings of the last complete frame.
+    /// Returns `None` if GPU timing is not enabled for the graph
+    /// or no frames were complete yet.
+    pub fn gpu_timings(&self) -> Option<&GpuTimings> {
+        self.timer.as_ref().and_then(NodeTimer::report)
+    }
+
     /// Get queue that will exeute given node.
     pub fn node_queue(&self, node: NodeId) -> QueueId {
         let (f, i) = self.nodes[node.0].queue;
@@ -255,6 +668,9 @@ where
     }
 
     /// Dispose of the `Graph`.
+    /// Pipelines, their layouts and render passes stay cached in `Factory`,
+    /// so graph rebuilt afterwards reuses them.
+    /// Use `Factory::evict_unused_pipeline_caches` to release them.
     pub fn dispose(self, factory: &mut Factory<B>, data: &T) {
         profile_scope!("dispose");
 
@@ -272,12 +688,20 @@ where
             for semaphore in self.semaphores {
                 factory.destroy_semaphore(semaphore);
             }
+
+            if let Some(mut timer) = self.timer {
+                timer.dispose(factory);
+            }
         }
         drop(self.device);
         drop(self.schedule);
         drop(self.fences);
         drop(self.inflight);
-        drop(self.ctx);
+
+        unsafe {
+            // Device is idle.
+            self.ctx.dispose(factory);
+        }
     }
 }
 
@@ -289,6 +713,7 @@ pub struct GraphBuilder<B: Backend, T: ?Sized> {
     buffers: Vec<BufferInfo>,
     images: Vec<(ImageInfo, Option<gfx_hal::command::ClearValue>)>,
     frames_in_flight: u32,
+    gpu_timing: Option<f32>,
 }
 
 impl<B, T> GraphBuilder<B, T>
@@ -303,6 +728,7 @@ where
             buffers: Vec::new(),
             images: Vec::new(),
             frames_in_flight: 3,
+            gpu_timing: None,
         }
     }
 
@@ -358,6 +784,20 @@ where
         self
     }
 
+    /// Measure GPU time spent by each node.
+    /// Timings are available through `Graph::gpu_timings`
+    /// once the frame is complete.
+    ///
+    /// `timestamp_period` is number of nanoseconds per timestamp tick,
+    /// that is `timestampPeriod` limit of the physical device.
+    /// It is not reported by `gfx-hal` and has to be provided by the caller.
+    /// Nodes executed on transfer-only queues are not timed,
+    /// as such queues may not support timestamps.
+    pub fn with_gpu_timing(mut self, timestamp_period: f32) -> Self {
+        self.gpu_timing = Some(timestamp_period);
+        self
+    }
+
     /// Build `Graph`.
     ///
     /// # Parameters
@@ -377,6 +817,25 @@ where
     ) -> Result<Graph<B, T>, GraphBuildError> {
         profile_scope!("build");
 
+        // Render pass clears attachment instead of loading when it is the first to use it.
+        let images = &self.images;
+        let first_use_clears: HashSet<_> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .flat_map(|(node, builder)| {
+                builder
+                    .first_use_clears()
+                    .into_iter()
+                    .map(move |id| (node, id.0))
+            })
+            .filter(|&(_, image)| {
+                images
+                    .get(image)
+                    .map_or(false, |(_, clear)| clear.is_some())
+            })
+            .collect();
+
         log::trace!("Schedule nodes execution");
         let chain_nodes: Vec<chain::Node> = {
             profile_scope!("schedule_nodes");
@@ -389,7 +848,8 @@ where
 
         let chains = chain::collect(chain_nodes, |id| {
             families.family_by_index(id.0).as_slice().len()
-        });
+        })
+        .map_err(GraphBuildError::Chain)?;
         log::trace!("Scheduled nodes execution {:#?}", chains);
 
         let mut ctx = GraphContext::alloc(
@@ -397,6 +857,7 @@ where
             &chains,
             &self.buffers,
             &self.images,
+            &first_use_clears,
             self.frames_in_flight,
         )?;
 
@@ -449,22 +910,41 @@ where
             .collect::<Result<_, _>>()
             .map_err(GraphBuildError::Semaphore)?;
 
+        let nodes: Vec<_> = built_nodes
+            .into_iter()
+            .map(Option::unwrap)
+            .map(|(node, qid)| GraphNode {
+                node,
+                queue: (qid.family().0, qid.index()),
+            })
+            .collect();
+
+        let timer = match self.gpu_timing {
+            Some(period) => {
+                log::debug!("Create timestamp queries for {} nodes", nodes.len());
+                let node_families: Vec<_> = nodes.iter().map(|node| node.queue.0).collect();
+                NodeTimer::new(
+                    factory,
+                    families,
+                    &node_families,
+                    self.frames_in_flight,
+                    period,
+                )
+                .map_err(GraphBuildError::QueryPool)?
+            }
+            None => None,
+        };
+
         Ok(Graph {
             device: factory.device().id(),
             ctx,
-            nodes: built_nodes
-                .into_iter()
-                .map(Option::unwrap)
-                .map(|(node, qid)| GraphNode {
-                    node,
-                    queue: (qid.family().0, qid.index()),
-                })
-                .collect(),
+            nodes,
             schedule,
             semaphores,
             inflight: self.frames_in_flight,
             frames: Frames::new(),
             fences: Vec::new(),
+            timer,
         })
     }
 }
@@ -483,60 +963,71 @@ fn build_node<'a, B: Backend, T: ?Sized>(
     buffer_ids.sort();
     buffer_ids.dedup();
 
-    let buffers: Vec<_> = buffer_ids
-        .into_iter()
-        .map(|id| {
-            let chain_id = chain::Id(id.0);
-            let sync = submission.sync();
-            let buffer = ctx
-                .get_buffer(id)
-                .expect("Buffer referenced from at least one node must be instantiated");
-            NodeBuffer {
-                id,
-                range: 0..buffer.size(),
-                acquire: sync.acquire.buffers.get(&chain_id).map(
-                    |chain::Barrier { states, families }| BufferBarrier {
-                        states: states.start.0..states.end.0,
-                        stages: states.start.2..states.end.2,
-                        families: families.clone(),
-                    },
-                ),
-                release: sync.release.buffers.get(&chain_id).map(
+    let buffers: Vec<_> =
+        buffer_ids
+            .into_iter()
+            .map(|id| {
+                let chain_id = chain::Id(id.0);
+                let sync = submission.sync();
+                let buffer = ctx
+                    .get_buffer(id)
+                    .expect("Buffer referenced from at least one node must be instantiated");
+                let mut acquire = sync.acquire.buffers.get(&chain_id).map(
                     |chain::Barrier { states, families }| BufferBarrier {
                         states: states.start.0..states.end.0,
                         stages: states.start.2..states.end.2,
                         families: families.clone(),
                     },
-                ),
-            }
-        })
-        .collect();
+                );
+                if let Some(&(access, stages)) = ctx.aliased_buffers.get(&id) {
+                    let chain = &chains.buffers[&chain_id];
+                    if first_use(chain, submission.id()) {
+                        // Memory was used by another buffer since previous use.
+                        let state = chain.links()[0].submission_state(submission.id());
+                        acquire = Some(match acquire {
+                            Some(barrier) => BufferBarrier {
+                                states: barrier.states.start | access..barrier.states.end,
+                                stages: barrier.stages.start | stages..barrier.stages.end,
+                                families: barrier.families,
+                            },
+                            None => BufferBarrier {
+                                states: access..state.access,
+                                stages: stages..state.stages,
+                                families: None,
+                            },
+                        });
+                    }
+                }
+                NodeBuffer {
+                    id,
+                    range: 0..buffer.size(),
+                    acquire,
+                    release: sync.release.buffers.get(&chain_id).map(
+                        |chain::Barrier { states, families }| BufferBarrier {
+                            states: states.start.0..states.end.0,
+                            stages: states.start.2..states.end.2,
+                            families: families.clone(),
+                        },
+                    ),
+                }
+            })
+            .collect();
 
     let mut image_ids: Vec<_> = builder.images().into_iter().map(|(id, _)| id).collect();
     image_ids.sort();
     image_ids.dedup();
 
-    let images: Vec<_> = image_ids
-        .into_iter()
-        .map(|id| {
-            let chain_id = chain::Id(id.0);
-            let sync = submission.sync();
-            let link = submission.image_link_index(chain_id);
-            let (image, clear) = ctx
-                .get_image_with_clear(id)
-                .expect("Image referenced from at least one node must be instantiated");
-            NodeImage {
-                id,
-                range: gfx_hal::image::SubresourceRange {
-                    aspects: image.format().surface_desc().aspects,
-                    levels: 0..image.levels(),
-                    layers: 0..image.layers(),
-                },
-                layout: chains.images[&chain_id].links()[link]
-                    .submission_state(submission.id())
-                    .layout,
-                clear: if link == 0 { clear } else { None },
-                acquire: sync.acquire.images.get(&chain_id).map(
+    let images: Vec<_> =
+        image_ids
+            .into_iter()
+            .map(|id| {
+                let chain_id = chain::Id(id.0);
+                let sync = submission.sync();
+                let link = submission.image_link_index(chain_id);
+                let (image, clear) = ctx
+                    .get_image_with_clear(id)
+                    .expect("Image referenced from at least one node must be instantiated");
+                let mut acquire = sync.acquire.images.get(&chain_id).map(
                     |chain::Barrier { states, families }| ImageBarrier {
                         states: (
                             states.start.0,
@@ -549,20 +1040,65 @@ fn build_node<'a, B: Backend, T: ?Sized>(
                         stages: states.start.2..states.end.2,
                         families: families.clone(),
                     },
-                ),
-                release: sync.release.images.get(&chain_id).map(
-                    |chain::Barrier { states, families }| ImageBarrier {
-                        states: (states.start.0, states.start.1)..(states.end.0, states.end.1),
-                        stages: states.start.2..states.end.2,
-                        families: families.clone(),
+                );
+                if let Some(&(access, stages)) = ctx.aliased_images.get(&id) {
+                    let chain = &chains.images[&chain_id];
+                    if first_use(chain, submission.id()) {
+                        // Memory was used by another image since previous use.
+                        let state = chain.links()[0].submission_state(submission.id());
+                        acquire = Some(match acquire {
+                            // Content left by another image is undefined.
+                            Some(barrier) => ImageBarrier {
+                                states: (
+                                    barrier.states.start.0 | access,
+                                    gfx_hal::image::Layout::Undefined,
+                                )..barrier.states.end,
+                                stages: barrier.stages.start | stages..barrier.stages.end,
+                                families: barrier.families,
+                            },
+                            None => ImageBarrier {
+                                states: (access, gfx_hal::image::Layout::Undefined)
+                                    ..(state.access, state.layout),
+                                stages: stages..state.stages,
+                                families: None,
+                            },
+                        });
+                    }
+                }
+                NodeImage {
+                    id,
+                    range: gfx_hal::image::SubresourceRange {
+                        aspects: image.format().surface_desc().aspects,
+                        levels: 0..image.levels(),
+                        layers: 0..image.layers(),
                     },
-                ),
-            }
-        })
-        .collect();
+                    layout: chains.images[&chain_id].links()[link]
+                        .submission_state(submission.id())
+                        .layout,
+                    clear: if link == 0 { clear } else { None },
+                    acquire,
+                    release: sync.release.images.get(&chain_id).map(
+                        |chain::Barrier { states, families }| ImageBarrier {
+                            states: (states.start.0, states.start.1)..(states.end.0, states.end.1),
+                            stages: states.start.2..states.end.2,
+                            families: families.clone(),
+                        },
+                    ),
+                }
+            })
+            .collect();
     builder.build(ctx, factory, family, queue, aux, buffers, images)
 }
 
+/// Check if submission is the first one to use the resource in the frame.
+fn first_use<R: chain::Resource>(chain: &chain::Chain<R>, sid: chain::SubmissionId) -> bool {
+    let first = &chain.links()[0];
+    first.family() == sid.family()
+        && first
+            .queues()
+            .any(|(qid, queue)| qid == sid.queue() && queue.first == sid.index())
+}
+
 fn make_chain_node<B, T>(
     builder: &dyn NodeBuilder<B, T>,
     id: usize,
@@ -579,6 +1115,11 @@ where
         id,
         family: QueueFamilyId(builder.family(factory, families).unwrap().index),
         dependencies: builder.dependencies().into_iter().map(|id| id.0).collect(),
+        discards: builder
+            .discards()
+            .into_iter()
+            .map(|id| chain::Id(id.0))
+            .collect(),
         buffers: buffers
             .into_iter()
             .map(|(id, access)| {
@@ -609,3 +1150,135 @@ where
             .collect(),
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn unbound(index: usize, size: u64, lifetime: Option<Range<usize>>) -> Unbound<()> {
+        Unbound {
+            index,
+            raw: (),
+            requirements: gfx_hal::memory::Requirements {
+                size,
+                alignment: 256,
+                type_mask: 0b11,
+            },
+            lifetime: lifetime.map(|lifetime| (chain::QueueId::new(QueueFamilyId(0), 0), lifetime)),
+        }
+    }
+
+    fn indices(groups: &[AliasGroup<()>]) -> Vec<Vec<usize>> {
+        groups
+            .iter()
+            .map(|group| group.resources.iter().map(|r| r.index).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_alias_disjoint_lifetimes() {
+        let groups = alias_groups(vec![
+            unbound(0, 1024, Some(0..2)),
+            unbound(1, 2048, Some(2..4)),
+            unbound(2, 512, Some(1..3)),
+        ]);
+        assert_eq!(indices(&groups), vec![vec![0, 1], vec![2]]);
+        assert_eq!(groups[0].size, 2048);
+    }
+
+    #[test]
+    fn test_alias_overlapping_lifetimes() {
+        let groups = alias_groups(vec![
+            unbound(0, 1024, Some(0..3)),
+            unbound(1, 1024, Some(2..4)),
+        ]);
+        assert_eq!(indices(&groups), vec![vec![0], vec![1]]);
+    }
+
+    #[test]
+    fn test_alias_skips_resources_without_lifetime() {
+        // Resource that reads previous frame content or is used on multiple queues.
+        let groups = alias_groups(vec![
+            unbound(0, 1024, None),
+            unbound(1, 1024, Some(1..2)),
+            unbound(2, 1024, Some(2..3)),
+        ]);
+        assert_eq!(indices(&groups), vec![vec![0], vec![1, 2]]);
+    }
+
+    fn chain_node(id: usize, images: Vec<(usize, gfx_hal::image::Access)>) -> chain::Node {
+        chain::Node {
+            id,
+            family: QueueFamilyId(0),
+            dependencies: (0..id).collect(),
+            buffers: HashMap::new(),
+            images: images
+                .into_iter()
+                .map(|(image, access)| {
+                    let state = if access.contains(gfx_hal::image::Access::COLOR_ATTACHMENT_WRITE) {
+                        chain::ImageState {
+                            access,
+                            layout: gfx_hal::image::Layout::ColorAttachmentOptimal,
+                            stages: gfx_hal::pso::PipelineStage::COLOR_ATTACHMENT_OUTPUT,
+                            usage: gfx_hal::image::Usage::COLOR_ATTACHMENT,
+                        }
+                    } else {
+                        chain::ImageState {
+                            access,
+                            layout: gfx_hal::image::Layout::ShaderReadOnlyOptimal,
+                            stages: gfx_hal::pso::PipelineStage::FRAGMENT_SHADER,
+                            usage: gfx_hal::image::Usage::SAMPLED,
+                        }
+                    };
+                    (chain::Id(image), state)
+                })
+                .collect(),
+            discards: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_alias_cleared_render_targets() {
+        // Each node samples the previous target and renders to the next one.
+        let target = gfx_hal::image::Access::COLOR_ATTACHMENT_READ
+            | gfx_hal::image::Access::COLOR_ATTACHMENT_WRITE;
+        let sampled = gfx_hal::image::Access::SHADER_READ;
+        let chains = chain::collect(
+            vec![
+                chain_node(0, vec![(0, target)]),
+                chain_node(1, vec![(0, sampled), (1, target)]),
+                chain_node(2, vec![(1, sampled), (2, target)]),
+            ],
+            |_| 1,
+        )
+        .unwrap();
+
+        let groups = |clears: &HashSet<(usize, usize)>| {
+            let images = (0..3)
+                .map(|index| {
+                    let chain = &chains.images[&chain::Id(index)];
+                    let lifetime = image_lifetime(chain, &chains.schedule, |node| {
+                        clears.contains(&(node, index))
+                    });
+                    unbound(index, 1024, lifetime.map(|(_, lifetime)| lifetime))
+                })
+                .collect();
+            indices(&alias_groups(images))
+        };
+
+        // Targets are cleared by the nodes rendering to them.
+        let clears: HashSet<_> = (0..3).map(|index| (index, index)).collect();
+        assert_eq!(groups(&clears), vec![vec![0, 2], vec![1]]);
+
+        // Loaded targets may read content of the previous frame.
+        assert_eq!(groups(&HashSet::new()), vec![vec![0], vec![1], vec![2]]);
+    }
+
+    #[test]
+    fn test_alias_requires_common_memory_type() {
+        let mut other = unbound(1, 1024, Some(1..2));
+        other.requirements.type_mask = 0b100;
+        let groups = alias_groups(vec![unbound(0, 1024, Some(0..1)), other]);
+        assert_eq!(indices(&groups), vec![vec![0], vec![1]]);
+    }
+}
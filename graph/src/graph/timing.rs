@@ -0,0 +1,306 @@
+use {
+    crate::{
+        command::{
+            CommandBuffer, CommandPool, ExecutableState, Families, Fence, MultiShot,
+            NoIndividualReset, PendingState, PrimaryLevel, SimultaneousUse, Submission, Submit,
+        },
+        factory::Factory,
+        resource::{QueryPool, Timestamp},
+        NodeId,
+    },
+    gfx_hal::{queue::QueueType, Backend},
+    std::{collections::HashMap, time::Duration},
+};
+
+/// GPU time spent executing each node of one frame.
+///
+/// Time is measured between top-of-pipe timestamp written before node's submissions
+/// and bottom-of-pipe timestamp written after them.
+/// Thus it includes time the node waits for other queues.
+#[derive(Clone, Debug)]
+pub struct GpuTimings {
+    frame: u64,
+    nodes: HashMap<NodeId, Duration>,
+}
+
+impl GpuTimings {
+    /// Index of the frame these timings were measured for.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Get GPU time of the node.
+    pub fn get(&self, node: NodeId) -> Option<Duration> {
+        self.nodes.get(&node).cloned()
+    }
+
+    /// Iterate over GPU times of all nodes.
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, Duration)> + '_ {
+        self.nodes.iter().map(|(&node, &duration)| (node, duration))
+    }
+}
+
+type TimestampBuffer<B> = CommandBuffer<
+    B,
+    QueueType,
+    PendingState<ExecutableState<MultiShot<SimultaneousUse>>>,
+    PrimaryLevel,
+    NoIndividualReset,
+>;
+
+/// Timestamp submits for one frame in flight.
+#[derive(derivative::Derivative)]
+#[derivative(Debug(bound = ""))]
+struct TimerFrame<B: Backend> {
+    pool: QueryPool<B, Timestamp>,
+    submits: Vec<(Submit<B, SimultaneousUse>, Submit<B, SimultaneousUse>)>,
+}
+
+/// Writes timestamps around submissions of each node.
+#[derive(derivative::Derivative)]
+#[derivative(Debug(bound = ""))]
+pub(super) struct NodeTimer<B: Backend> {
+    period: f32,
+    slots: Vec<Option<u32>>,
+    frames: Vec<TimerFrame<B>>,
+    command_pools: Vec<(usize, CommandPool<B, QueueType>)>,
+    buffers: Vec<(usize, TimestampBuffer<B>)>,
+    report: Option<GpuTimings>,
+}
+
+impl<B> NodeTimer<B>
+where
+    B: Backend,
+{
+    /// Create timer for nodes executed on queues of specified families.
+    /// `period` is number of nanoseconds per timestamp tick.
+    ///
+    /// Nodes executed on transfer-only queues are not timed,
+    /// as such queues may not support timestamps.
+    /// Returns `None` if no node can be timed.
+    pub(super) fn new(
+        factory: &Factory<B>,
+        families: &Families<B>,
+        node_families: &[usize],
+        frames_in_flight: u32,
+        period: f32,
+    ) -> Result<Option<Self>, gfx_hal::query::CreationError> {
+        let mut count = 0;
+        let slots: Vec<_> = node_families
+            .iter()
+            .enumerate()
+            .map(|(node, &family)| {
+                if families.family_by_index(family).capability() == QueueType::Transfer {
+                    log::warn!(
+                        "Node {} is executed on transfer queue that may not support timestamps. It won't be timed",
+                        node
+                    );
+                    None
+                } else {
+                    count += 1;
+                    Some(count - 1)
+                }
+            })
+            .collect();
+
+        if count == 0 {
+            log::warn!("No node can be timed");
+            return Ok(None);
+        }
+
+        let mut timer = NodeTimer {
+            period,
+            slots: Vec::new(),
+            frames: Vec::new(),
+            command_pools: Vec::new(),
+            buffers: Vec::new(),
+            report: None,
+        };
+
+        for _ in 0..frames_in_flight {
+            let pool = match factory.create_relevant_query_pool(Timestamp, count as u32 * 2) {
+                Ok(pool) => pool,
+                Err(err) => {
+                    unsafe { timer.dispose(factory) };
+                    return Err(err);
+                }
+            };
+
+            timer.frames.push(TimerFrame {
+                pool,
+                submits: Vec::with_capacity(count as usize),
+            });
+
+            let timed = node_families
+                .iter()
+                .zip(&slots)
+                .filter_map(|(&family, &slot)| slot.map(|slot| (family, slot)));
+
+            for (family, index) in timed {
+                let position = timer.command_pools.iter().position(|&(f, _)| f == family);
+                let pool_index = match position {
+                    Some(pool_index) => pool_index,
+                    None => match factory.create_command_pool(families.family_by_index(family)) {
+                        Ok(command_pool) => {
+                            timer.command_pools.push((family, command_pool));
+                            timer.command_pools.len() - 1
+                        }
+                        Err(err) => {
+                            unsafe { timer.dispose(factory) };
+                            return Err(gfx_hal::query::CreationError::OutOfMemory(err));
+                        }
+                    },
+                };
+
+                let TimerFrame { pool, submits } = timer.frames.last_mut().unwrap();
+                let pool = &*pool;
+
+                let mut buffers = timer.command_pools[pool_index]
+                    .1
+                    .allocate_buffers::<PrimaryLevel>(2)
+                    .into_iter();
+
+                let mut begin = buffers
+                    .next()
+                    .unwrap()
+                    .begin(MultiShot(SimultaneousUse), ());
+                {
+                    let mut encoder = begin.encoder();
+                    unsafe {
+                        encoder.reset_query_pool(pool.raw(), index * 2..index * 2 + 2);
+                        encoder.write_timestamp(
+                            gfx_hal::pso::PipelineStage::TOP_OF_PIPE,
+                            pool.query(index * 2),
+                        );
+                    }
+                }
+                let (begin_submit, begin) = begin.finish().submit();
+
+                let mut end = buffers
+                    .next()
+                    .unwrap()
+                    .begin(MultiShot(SimultaneousUse), ());
+                unsafe {
+                    end.encoder().write_timestamp(
+                        gfx_hal::pso::PipelineStage::BOTTOM_OF_PIPE,
+                        pool.query(index * 2 + 1),
+                    );
+                }
+                let (end_submit, end) = end.finish().submit();
+
+                timer.buffers.push((pool_index, begin));
+                timer.buffers.push((pool_index, end));
+                submits.push((begin_submit, end_submit));
+            }
+        }
+
+        timer.slots = slots;
+        Ok(Some(timer))
+    }
+
+    fn frame(&self, frame: u64) -> &TimerFrame<B> {
+        &self.frames[(frame % self.frames.len() as u64) as usize]
+    }
+
+    /// Submit commands that write timestamp before node's submissions.
+    /// Returns `false` if the node is not timed.
+    pub(super) unsafe fn begin(
+        &self,
+        families: &mut Families<B>,
+        queue: (usize, usize),
+        frame: u64,
+        node: usize,
+    ) -> bool {
+        let slot = match self.slots[node] {
+            Some(slot) => slot,
+            None => return false,
+        };
+        let submit = &self.frame(frame).submits[slot as usize].0;
+        families
+            .family_by_index_mut(queue.0)
+            .queue_mut(queue.1)
+            .submit(Some(Submission::new().submits(Some(submit))), None);
+        true
+    }
+
+    /// Submit commands that write timestamp after node's submissions.
+    /// Node must be timed.
+    pub(super) unsafe fn end(
+        &self,
+        families: &mut Families<B>,
+        queue: (usize, usize),
+        frame: u64,
+        node: usize,
+        fence: Option<&mut Fence<B>>,
+    ) {
+        let slot = self.slots[node].expect("Node is not timed");
+        let submit = &self.frame(frame).submits[slot as usize].1;
+        families
+            .family_by_index_mut(queue.0)
+            .queue_mut(queue.1)
+            .submit(Some(Submission::new().submits(Some(submit))), fence);
+    }
+
+    /// Read timestamps written for the frame.
+    ///
+    /// # Safety
+    ///
+    /// Frame must be complete.
+    pub(super) unsafe fn collect(&mut self, factory: &Factory<B>, frame: u64) {
+        let timer_frame = self.frame(frame);
+        let count = timer_frame.submits.len();
+        let mut data = vec![0u64; count * 2];
+
+        let result =
+            timer_frame
+                .pool
+                .results(factory.device(), 0..count as u32 * 2, &mut data, true);
+
+        match result {
+            Ok(true) => {
+                let period = self.period as f64;
+                let nodes = self
+                    .slots
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(node, &slot)| {
+                        let slot = slot? as usize;
+                        let ticks = data[slot * 2 + 1].saturating_sub(data[slot * 2]);
+                        let nanos = (ticks as f64 * period) as u64;
+                        Some((NodeId(node), Duration::from_nanos(nanos)))
+                    })
+                    .collect();
+                self.report = Some(GpuTimings { frame, nodes });
+            }
+            Ok(false) => log::warn!("Timestamps for frame {} are not available", frame),
+            Err(err) => log::error!("Failed to read timestamps for frame {}: {:?}", frame, err),
+        }
+    }
+
+    /// Get timings of the last collected frame.
+    pub(super) fn report(&self) -> Option<&GpuTimings> {
+        self.report.as_ref()
+    }
+
+    /// Dispose of the timer.
+    ///
+    /// # Safety
+    ///
+    /// Timestamp commands must not be pending.
+    pub(super) unsafe fn dispose(&mut self, factory: &Factory<B>) {
+        for (pool_index, buffer) in self.buffers.drain(..) {
+            let executable = buffer.mark_complete();
+            self.command_pools[pool_index]
+                .1
+                .free_buffers(Some(executable));
+        }
+
+        for (_, pool) in self.command_pools.drain(..) {
+            factory.destroy_command_pool(pool);
+        }
+
+        for frame in self.frames.drain(..) {
+            factory.destroy_relevant_query_pool(frame.pool);
+        }
+    }
+}
@@ -0,0 +1,399 @@
+//! Defines simple compute node.
+
+use {
+    crate::{
+        command::{
+            CommandPool, Compute, Encoder, Family, IndividualReset, MultiShot, NoSimultaneousUse,
+            PrimaryLevel, QueueId, Submit,
+        },
+        factory::Factory,
+        frame::{
+            cirque::{CirqueRef, CommandCirque},
+            Frames,
+        },
+        graph::GraphContext,
+        node::{
+            gfx_acquire_barriers, gfx_release_barriers, is_metal, render::PrepareResult,
+            BufferAccess, DescBuilder, ImageAccess, Node, NodeBuffer, NodeBuildError, NodeDesc,
+            NodeImage, NodeSubmittable,
+        },
+        resource::{DescriptorSetLayout, Handle},
+        util::types::Layout,
+    },
+    gfx_hal::{device::Device as _, Backend},
+};
+
+/// Descriptor for simple compute pipeline implementation.
+pub trait SimpleComputePipelineDesc<B: Backend, T: ?Sized>: std::fmt::Debug {
+    /// Simple compute pipeline implementation
+    type Pipeline: SimpleComputePipeline<B, T>;
+
+    /// Make simple compute node builder.
+    fn builder(self) -> DescBuilder<B, T, SimpleComputeNodeDesc<Self>>
+    where
+        Self: Sized,
+    {
+        SimpleComputeNodeDesc { inner: self }.builder()
+    }
+
+    /// Get set or buffer resources the node uses.
+    fn buffers(&self) -> Vec<BufferAccess> {
+        Vec::new()
+    }
+
+    /// Get set or image resources the node uses.
+    fn images(&self) -> Vec<ImageAccess> {
+        Vec::new()
+    }
+
+    /// Layout for compute pipeline.
+    fn layout(&self) -> Layout {
+        Layout {
+            sets: Vec::new(),
+            push_constants: Vec::new(),
+        }
+    }
+
+    /// Load shader set.
+    /// Returned `ShaderSet` must contain compute shader.
+    ///
+    /// # Parameters
+    ///
+    /// `factory`   - factory to create shader modules.
+    ///
+    /// `aux`       - auxiliary data container. May be anything the implementation desires.
+    ///
+    fn load_shader_set(&self, factory: &mut Factory<B>, aux: &T) -> rendy_shader::ShaderSet<B>;
+
+    /// Build pipeline instance.
+    fn build<'a>(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        queue: QueueId,
+        aux: &T,
+        buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+        set_layouts: &[Handle<DescriptorSetLayout<B>>],
+    ) -> Result<Self::Pipeline, gfx_hal::pso::CreationError>;
+}
+
+/// Simple compute pipeline.
+pub trait SimpleComputePipeline<B: Backend, T: ?Sized>:
+    std::fmt::Debug + Sized + Send + Sync + 'static
+{
+    /// This pipeline descriptor.
+    type Desc: SimpleComputePipelineDesc<B, T, Pipeline = Self>;
+
+    /// Make simple compute node builder.
+    fn builder() -> DescBuilder<B, T, SimpleComputeNodeDesc<Self::Desc>>
+    where
+        Self::Desc: Default,
+    {
+        Self::Desc::default().builder()
+    }
+
+    /// Prepare to record dispatch commands.
+    ///
+    /// Should return true if commands must be re-recorded.
+    fn prepare(
+        &mut self,
+        _factory: &Factory<B>,
+        _queue: QueueId,
+        _set_layouts: &[Handle<DescriptorSetLayout<B>>],
+        _index: usize,
+        _aux: &T,
+    ) -> PrepareResult {
+        PrepareResult::DrawRecord
+    }
+
+    /// Record dispatch commands to the command buffer provided.
+    /// Pipeline is already bound and barriers for node resources are inserted around the commands.
+    fn dispatch(
+        &mut self,
+        layout: &B::PipelineLayout,
+        encoder: &mut Encoder<'_, B, Compute, PrimaryLevel>,
+        index: usize,
+        aux: &T,
+    );
+
+    /// Free all resources and destroy pipeline instance.
+    fn dispose(self, factory: &mut Factory<B>, aux: &T);
+}
+
+/// Node that consist of simple compute pipeline.
+#[derive(derivative::Derivative)]
+#[derivative(Debug(bound = "P: std::fmt::Debug"))]
+pub struct SimpleComputeNode<B: Backend, P> {
+    set_layouts: Vec<Handle<DescriptorSetLayout<B>>>,
+    pipeline_layout: B::PipelineLayout,
+    compute_pipeline: B::ComputePipeline,
+    pipeline: P,
+
+    buffers: Vec<NodeBuffer>,
+    images: Vec<NodeImage>,
+
+    queue: QueueId,
+    command_pool: CommandPool<B, Compute, IndividualReset>,
+    command_cirque: CommandCirque<B, Compute>,
+}
+
+/// Descriptor for simple compute node.
+#[derive(Debug)]
+pub struct SimpleComputeNodeDesc<P: std::fmt::Debug> {
+    inner: P,
+}
+
+impl<'a, B, P> NodeSubmittable<'a, B> for SimpleComputeNode<B, P>
+where
+    B: Backend,
+{
+    type Submittable = Submit<B, NoSimultaneousUse>;
+    type Submittables = Option<Submit<B, NoSimultaneousUse>>;
+}
+
+impl<B, T, P> NodeDesc<B, T> for SimpleComputeNodeDesc<P>
+where
+    B: Backend,
+    T: ?Sized,
+    P: SimpleComputePipelineDesc<B, T> + 'static,
+{
+    type Node = SimpleComputeNode<B, P::Pipeline>;
+
+    fn buffers(&self) -> Vec<BufferAccess> {
+        self.inner.buffers()
+    }
+
+    fn images(&self) -> Vec<ImageAccess> {
+        self.inner.images()
+    }
+
+    fn build<'a>(
+        self,
+        ctx: &GraphContext<B>,
+        factory: &mut Factory<B>,
+        family: &mut Family<B>,
+        queue: usize,
+        aux: &T,
+        buffers: Vec<NodeBuffer>,
+        images: Vec<NodeImage>,
+    ) -> Result<Self::Node, NodeBuildError> {
+        log::trace!("Load shader set for {:#?}", self.inner);
+
+        let mut shader_set = self.inner.load_shader_set(factory, aux);
+
+        let layout = self.inner.layout();
+
+        let set_layouts = layout
+            .sets
+            .into_iter()
+            .map(|set| {
+                factory
+                    .create_descriptor_set_layout(set.bindings)
+                    .map(Handle::from)
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| {
+                shader_set.dispose(factory);
+                NodeBuildError::OutOfMemory(e)
+            })?;
+
+        let pipeline_layout = unsafe {
+            factory
+                .device()
+                .create_pipeline_layout(set_layouts.iter().map(|l| l.raw()), layout.push_constants)
+        }
+        .map_err(|e| {
+            shader_set.dispose(factory);
+            NodeBuildError::OutOfMemory(e)
+        })?;
+
+        let shader = match shader_set.raw_compute() {
+            Err(e) => {
+                unsafe { factory.device().destroy_pipeline_layout(pipeline_layout) };
+                shader_set.dispose(factory);
+                return Err(NodeBuildError::Shader(e));
+            }
+            Ok(s) => s,
+        };
+
+        let compute_pipeline = unsafe {
+            factory.device().create_compute_pipeline(
+                &gfx_hal::pso::ComputePipelineDesc {
+                    shader,
+                    layout: &pipeline_layout,
+                    flags: gfx_hal::pso::PipelineCreationFlags::empty(),
+                    parent: gfx_hal::pso::BasePipeline::None,
+                },
+                Some(factory.pipeline_cache()),
+            )
+        };
+
+        // Shader modules are not needed once the pipeline is created.
+        shader_set.dispose(factory);
+
+        let compute_pipeline = match compute_pipeline {
+            Err(e) => {
+                unsafe { factory.device().destroy_pipeline_layout(pipeline_layout) };
+                return Err(NodeBuildError::Pipeline(e));
+            }
+            Ok(compute_pipeline) => compute_pipeline,
+        };
+
+        let command_pool = match factory.create_command_pool(family) {
+            Err(e) => {
+                unsafe {
+                    factory.device().destroy_compute_pipeline(compute_pipeline);
+                    factory.device().destroy_pipeline_layout(pipeline_layout);
+                }
+                return Err(NodeBuildError::OutOfMemory(e));
+            }
+            Ok(command_pool) => command_pool
+                .with_capability()
+                .expect("Graph must specify family that supports `Compute`"),
+        };
+
+        let queue = QueueId {
+            family: family.id(),
+            index: queue,
+        };
+
+        let pipeline = match self.inner.build(
+            ctx,
+            factory,
+            queue,
+            aux,
+            buffers.clone(),
+            images.clone(),
+            &set_layouts,
+        ) {
+            Err(e) => {
+                unsafe {
+                    factory.destroy_command_pool(command_pool);
+                    factory.device().destroy_compute_pipeline(compute_pipeline);
+                    factory.device().destroy_pipeline_layout(pipeline_layout);
+                }
+                return Err(NodeBuildError::Pipeline(e));
+            }
+            Ok(pipeline) => pipeline,
+        };
+
+        Ok(SimpleComputeNode {
+            set_layouts,
+            pipeline_layout,
+            compute_pipeline,
+            pipeline,
+            buffers,
+            images,
+            queue,
+            command_pool,
+            command_cirque: CommandCirque::new(),
+        })
+    }
+}
+
+impl<B, T, P> Node<B, T> for SimpleComputeNode<B, P>
+where
+    B: Backend,
+    T: ?Sized,
+    P: SimpleComputePipeline<B, T>,
+{
+    type Capability = Compute;
+
+    fn run<'a>(
+        &'a mut self,
+        ctx: &GraphContext<B>,
+        factory: &Factory<B>,
+        aux: &T,
+        frames: &'a Frames<B>,
+    ) -> Option<Submit<B, NoSimultaneousUse>> {
+        let SimpleComputeNode {
+            set_layouts,
+            pipeline_layout,
+            compute_pipeline,
+            pipeline,
+            buffers,
+            images,
+            queue,
+            command_pool,
+            command_cirque,
+        } = self;
+
+        let submit = command_cirque.encode(frames, command_pool, |mut cbuf| {
+            let index = cbuf.index();
+
+            let force_record = pipeline
+                .prepare(factory, *queue, set_layouts, index, aux)
+                .force_record();
+
+            if force_record {
+                cbuf = CirqueRef::Initial(cbuf.or_reset(|cbuf| cbuf.reset()));
+            }
+
+            cbuf.or_init(|cbuf| {
+                let mut cbuf = cbuf.begin(MultiShot(NoSimultaneousUse), ());
+                let mut encoder = cbuf.encoder();
+
+                if !is_metal::<B>() {
+                    let (stages, barriers) = gfx_acquire_barriers(ctx, &*buffers, &*images);
+                    if !barriers.is_empty() {
+                        log::trace!("Acquire {:?} : {:#?}", stages, barriers);
+                        unsafe {
+                            encoder.pipeline_barrier(
+                                stages,
+                                gfx_hal::memory::Dependencies::empty(),
+                                barriers,
+                            );
+                        }
+                    }
+                }
+
+                encoder.bind_compute_pipeline(compute_pipeline);
+                pipeline.dispatch(pipeline_layout, &mut encoder, index, aux);
+
+                if !is_metal::<B>() {
+                    let (stages, barriers) = gfx_release_barriers(ctx, &*buffers, &*images);
+                    if !barriers.is_empty() {
+                        log::trace!("Release {:?} : {:#?}", stages, barriers);
+                        unsafe {
+                            encoder.pipeline_barrier(
+                                stages,
+                                gfx_hal::memory::Dependencies::empty(),
+                                barriers,
+                            );
+                        }
+                    }
+                }
+
+                cbuf.finish()
+            })
+        });
+
+        Some(submit)
+    }
+
+    unsafe fn dispose(self, factory: &mut Factory<B>, aux: &T) {
+        self.pipeline.dispose(factory, aux);
+
+        let mut pool = self.command_pool;
+        self.command_cirque.dispose(|buffer| {
+            buffer.either_with(
+                &mut pool,
+                |pool, executable| pool.free_buffers(Some(executable)),
+                |pool, pending| {
+                    let executable = pending.mark_complete();
+                    pool.free_buffers(Some(executable))
+                },
+            );
+        });
+        factory.destroy_command_pool(pool);
+
+        factory
+            .device()
+            .destroy_compute_pipeline(self.compute_pipeline);
+        factory
+            .device()
+            .destroy_pipeline_layout(self.pipeline_layout);
+        drop(self.set_layouts);
+    }
+}
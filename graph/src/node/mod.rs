@@ -1,6 +1,7 @@
 //! Defines node - building block for framegraph.
 //!
 
+pub mod compute;
 pub mod present;
 pub mod render;
 
@@ -290,6 +291,32 @@ pub enum NodeBuildError {
     Swapchain(SwapchainError),
     /// Ran out of memory when creating something.
     OutOfMemory(gfx_hal::device::OutOfMemory),
+    /// Shader set lacks shader required by the node.
+    Shader(rendy_shader::ShaderError),
+    /// Pipeline could not be derived from shader reflection.
+    #[cfg(feature = "spirv-reflection")]
+    Reflect(rendy_shader::ReflectError),
+    /// Subpass has resolve attachments, but not one for each color attachment.
+    ResolveCountMismatch {
+        /// Index of the subpass.
+        subpass: usize,
+        /// Number of color attachments.
+        colors: usize,
+        /// Number of resolve attachments.
+        resolves: usize,
+    },
+    /// Render group renders with sample count different from subpass attachments.
+    SampleCountMismatch {
+        /// Index of the subpass.
+        subpass: usize,
+        /// Sample count of the subpass attachments.
+        attachments: gfx_hal::image::NumSamples,
+        /// Sample count of the render group.
+        group: gfx_hal::image::NumSamples,
+    },
+    /// Attachment load operation is `Clear`, but no clear value was provided for it.
+    /// Contains id of the image, or `None` for the surface.
+    ClearValueMissing(Option<ImageId>),
 }
 
 /// Dynamic node builder that emits `DynNode`.
@@ -303,6 +330,21 @@ pub trait NodeBuilder<B: Backend, T: ?Sized>: std::fmt::Debug {
     /// Get images accessed by the node.
     fn images(&self) -> Vec<(ImageId, ImageAccess)>;
 
+    /// Get images which content is discarded by the node.
+    /// Discarded images have no readers after this node.
+    /// Empty by default.
+    fn discards(&self) -> Vec<ImageId> {
+        Vec::new()
+    }
+
+    /// Get images the node clears instead of loading
+    /// if it is the first one to use them in the frame and they have clear value.
+    /// Attachment reads declared for such images don't read content left from previous frame.
+    /// Empty by default.
+    fn first_use_clears(&self) -> Vec<ImageId> {
+        Vec::new()
+    }
+
     /// Indices of nodes this one dependes on.
     fn dependencies(&self) -> Vec<NodeId>;
 
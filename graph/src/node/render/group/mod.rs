@@ -4,12 +4,12 @@ pub use self::simple::*;
 
 use {
     crate::{
-        command::{QueueId, RenderPassEncoder},
+        command::{Encoder, Graphics, PrimaryLevel, QueueId, RenderPassEncoder},
         factory::Factory,
         graph::GraphContext,
         node::{
             render::{pass::SubpassBuilder, PrepareResult},
-            BufferAccess, DescBuilder, ImageAccess, NodeBuffer, NodeImage,
+            BufferAccess, DescBuilder, ImageAccess, NodeBuffer, NodeBuildError, NodeImage,
         },
         BufferId, ImageId, NodeId,
     },
@@ -52,6 +52,13 @@ pub trait RenderGroupDesc<B: Backend, T: ?Sized>: std::fmt::Debug {
         true
     }
 
+    /// Sample count the group renders with.
+    /// Checked against sample count of subpass attachments.
+    /// `None` by default, that is not checked.
+    fn samples(&self) -> Option<gfx_hal::image::NumSamples> {
+        None
+    }
+
     /// Build render group.
     fn build<'a>(
         self,
@@ -64,7 +71,7 @@ pub trait RenderGroupDesc<B: Backend, T: ?Sized>: std::fmt::Debug {
         subpass: gfx_hal::pass::Subpass<'_, B>,
         buffers: Vec<NodeBuffer>,
         images: Vec<NodeImage>,
-    ) -> Result<Box<dyn RenderGroup<B, T>>, gfx_hal::pso::CreationError>;
+    ) -> Result<Box<dyn RenderGroup<B, T>>, NodeBuildError>;
 }
 
 /// One or more graphics pipelines to be called in subpass.
@@ -79,6 +86,18 @@ pub trait RenderGroup<B: Backend, T: ?Sized>: std::fmt::Debug + Send + Sync {
         aux: &T,
     ) -> PrepareResult;
 
+    /// Record commands outside of the render pass before it begins.
+    /// Can be used to reset queries that are used in `draw_inline`.
+    /// Does nothing by default.
+    fn record_before_pass(
+        &mut self,
+        _encoder: &mut Encoder<'_, B, Graphics, PrimaryLevel>,
+        _index: usize,
+        _subpass: gfx_hal::pass::Subpass<'_, B>,
+        _aux: &T,
+    ) {
+    }
+
     /// Record commands.
     fn draw_inline(
         &mut self,
@@ -108,6 +127,11 @@ pub trait RenderGroupBuilder<B: Backend, T: ?Sized>: std::fmt::Debug {
     /// Is depth image used.
     fn depth(&self) -> bool;
 
+    /// Sample count the group renders with, if known.
+    fn samples(&self) -> Option<gfx_hal::image::NumSamples> {
+        None
+    }
+
     /// Get buffers used by the group
     fn buffers(&self) -> Vec<(BufferId, BufferAccess)>;
 
@@ -129,7 +153,7 @@ pub trait RenderGroupBuilder<B: Backend, T: ?Sized>: std::fmt::Debug {
         subpass: gfx_hal::pass::Subpass<'_, B>,
         buffers: Vec<NodeBuffer>,
         images: Vec<NodeImage>,
-    ) -> Result<Box<dyn RenderGroup<B, T>>, gfx_hal::pso::CreationError>;
+    ) -> Result<Box<dyn RenderGroup<B, T>>, NodeBuildError>;
 }
 
 impl<B, T, D> RenderGroupBuilder<B, T> for DescBuilder<B, T, D>
@@ -146,6 +170,10 @@ where
         self.desc.depth()
     }
 
+    fn samples(&self) -> Option<gfx_hal::image::NumSamples> {
+        self.desc.samples()
+    }
+
     fn buffers(&self) -> Vec<(BufferId, BufferAccess)> {
         self.buffers
             .iter()
@@ -177,7 +205,7 @@ where
         subpass: gfx_hal::pass::Subpass<'_, B>,
         buffers: Vec<NodeBuffer>,
         images: Vec<NodeImage>,
-    ) -> Result<Box<dyn RenderGroup<B, T>>, gfx_hal::pso::CreationError> {
+    ) -> Result<Box<dyn RenderGroup<B, T>>, NodeBuildError> {
         self.desc.build(
             ctx,
             factory,
@@ -5,15 +5,19 @@ use {
         factory::Factory,
         graph::GraphContext,
         node::{
-            render::PrepareResult, BufferAccess, DescBuilder, ImageAccess, NodeBuffer, NodeImage,
+            render::PrepareResult, BufferAccess, DescBuilder, ImageAccess, NodeBuffer,
+            NodeBuildError, NodeImage,
         },
-        resource::{DescriptorSetLayout, Handle},
+        resource::{CacheKey, DescriptorSetLayout, GraphicsPipeline, Handle, PipelineLayout},
     },
-    gfx_hal::{device::Device as _, Backend},
+    gfx_hal::Backend,
 };
 
 pub use crate::util::types::{Layout, SetLayout};
 
+#[cfg(feature = "spirv-reflection")]
+use crate::util::types::vertex::VertexFormat;
+
 /// Pipeline info
 #[derive(Clone, Debug)]
 pub struct Pipeline {
@@ -35,6 +39,107 @@ pub struct Pipeline {
 
     /// Primitive to use in the input assembler.
     pub input_assembler_desc: gfx_hal::pso::InputAssemblerDesc,
+
+    /// Rasterizer state for pipeline.
+    pub rasterizer: gfx_hal::pso::Rasterizer,
+
+    /// Multisampling state for pipeline.
+    pub multisampling: Option<gfx_hal::pso::Multisampling>,
+
+    /// Baked states for pipeline.
+    /// States set to `None` are dynamic.
+    pub baked_states: gfx_hal::pso::BakedStates,
+}
+
+/// Hashes every field that affects created pipeline.
+/// Floating point values are hashed by their bits.
+impl std::hash::Hash for Pipeline {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        use gfx_hal::pso::{PolygonMode, State};
+
+        fn bits(value: &State<f32>) -> Option<u32> {
+            match *value {
+                State::Static(value) => Some(value.to_bits()),
+                State::Dynamic => None,
+            }
+        }
+
+        fn rect_key(rect: &gfx_hal::pso::Rect) -> (i16, i16, i16, i16) {
+            (rect.x, rect.y, rect.w, rect.h)
+        }
+
+        self.layout.hash(state);
+        self.vertices.hash(state);
+        self.colors.hash(state);
+        self.depth_stencil.hash(state);
+        self.input_assembler_desc.primitive.hash(state);
+        self.input_assembler_desc.primitive_restart.hash(state);
+
+        let rasterizer = &self.rasterizer;
+        match &rasterizer.polygon_mode {
+            PolygonMode::Point => 0u8.hash(state),
+            PolygonMode::Line(width) => (1u8, bits(width)).hash(state),
+            PolygonMode::Fill => 2u8.hash(state),
+        }
+        rasterizer.cull_face.hash(state);
+        rasterizer.front_face.hash(state);
+        rasterizer.depth_clamping.hash(state);
+        rasterizer
+            .depth_bias
+            .map(|bias| match bias {
+                State::Static(bias) => Some((
+                    bias.const_factor.to_bits(),
+                    bias.clamp.to_bits(),
+                    bias.slope_factor.to_bits(),
+                )),
+                State::Dynamic => None,
+            })
+            .hash(state);
+        rasterizer.conservative.hash(state);
+
+        self.multisampling
+            .as_ref()
+            .map(|multisampling| {
+                (
+                    multisampling.rasterization_samples,
+                    multisampling.sample_shading.map(f32::to_bits),
+                    multisampling.sample_mask,
+                    multisampling.alpha_coverage,
+                    multisampling.alpha_to_one,
+                )
+            })
+            .hash(state);
+
+        let baked = &self.baked_states;
+        baked
+            .viewport
+            .as_ref()
+            .map(|viewport| {
+                (
+                    rect_key(&viewport.rect),
+                    viewport.depth.start.to_bits(),
+                    viewport.depth.end.to_bits(),
+                )
+            })
+            .hash(state);
+        baked.scissor.as_ref().map(rect_key).hash(state);
+        baked
+            .blend_color
+            .map(|color| {
+                [
+                    color[0].to_bits(),
+                    color[1].to_bits(),
+                    color[2].to_bits(),
+                    color[3].to_bits(),
+                ]
+            })
+            .hash(state);
+        baked
+            .depth_bounds
+            .as_ref()
+            .map(|bounds| (bounds.start.to_bits(), bounds.end.to_bits()))
+            .hash(state);
+    }
 }
 
 /// Descriptor for simple graphics pipeline implementation.
@@ -91,6 +196,24 @@ pub trait SimpleGraphicsPipelineDesc<B: Backend, T: ?Sized>: std::fmt::Debug {
         Vec::new()
     }
 
+    /// Shader reflection to derive pipeline layout and vertex input from.
+    ///
+    /// Returning `Some` opts into reflection-driven pipeline:
+    /// layout with push constants is taken from the reflection
+    /// and vertex input attributes are matched by name to `vertex_formats`.
+    /// `layout` and `vertices` are not used then.
+    #[cfg(feature = "spirv-reflection")]
+    fn reflection(&self) -> Option<&rendy_shader::SpirvReflection> {
+        None
+    }
+
+    /// Formats of the vertex buffers with their input rates.
+    /// Used to match vertex shader input attributes when `reflection` returns `Some`.
+    #[cfg(feature = "spirv-reflection")]
+    fn vertex_formats(&self) -> Vec<(VertexFormat, gfx_hal::pso::VertexInputRate)> {
+        Vec::new()
+    }
+
     /// Layout for graphics pipeline
     /// Default implementation for `pipeline` will use this.
     fn layout(&self) -> Layout {
@@ -108,6 +231,37 @@ pub trait SimpleGraphicsPipelineDesc<B: Backend, T: ?Sized>: std::fmt::Debug {
         }
     }
 
+    /// Rasterizer state.
+    /// Defaults to filled polygons without culling.
+    /// Can be overriden to change cull mode, polygon mode, depth bias etc.
+    fn rasterizer(&self) -> gfx_hal::pso::Rasterizer {
+        gfx_hal::pso::Rasterizer::FILL
+    }
+
+    /// Multisampling state.
+    /// Defaults to `None` that is single sample without alpha-to-coverage.
+    fn multisampling(&self) -> Option<gfx_hal::pso::Multisampling> {
+        None
+    }
+
+    /// Baked states.
+    /// States left `None` are dynamic and must be set by `SimpleGraphicsPipeline::draw`.
+    ///
+    /// Defaults to dynamic viewport, scissor, blend color and depth bounds.
+    /// Dynamic viewport and scissor are set to cover whole framebuffer
+    /// before `SimpleGraphicsPipeline::draw` is called.
+    ///
+    /// Dynamic stencil state is declared with `gfx_hal::pso::State::Dynamic`
+    /// in stencil test returned by `depth_stencil`.
+    fn baked_states(&self) -> gfx_hal::pso::BakedStates {
+        gfx_hal::pso::BakedStates {
+            viewport: None,
+            scissor: None,
+            blend_color: None,
+            depth_bounds: None,
+        }
+    }
+
     /// Graphics pipelines
     fn pipeline(&self) -> Pipeline {
         Pipeline {
@@ -118,19 +272,46 @@ pub trait SimpleGraphicsPipelineDesc<B: Backend, T: ?Sized>: std::fmt::Debug {
                 .depth_stencil()
                 .unwrap_or(gfx_hal::pso::DepthStencilDesc::default()),
             input_assembler_desc: self.input_assembler(),
+            rasterizer: self.rasterizer(),
+            multisampling: self.multisampling(),
+            baked_states: self.baked_states(),
         }
     }
 
     /// Load shader set.
     /// This function should utilize the provided `ShaderSetBuilder` reflection class and return the compiled `ShaderSet`.
     ///
+    /// Defaults to building the shaders returned by `reloadable_shaders`,
+    /// so it must be implemented if that function returns `None`.
+    ///
     /// # Parameters
     ///
     /// `factory`   - factory to create shader modules.
     ///
     /// `aux`       - auxiliary data container. May be anything the implementation desires.
     ///
-    fn load_shader_set(&self, factory: &mut Factory<B>, aux: &T) -> rendy_shader::ShaderSet<B>;
+    fn load_shader_set(&self, factory: &mut Factory<B>, _aux: &T) -> rendy_shader::ShaderSet<B> {
+        let shaders = self.reloadable_shaders().expect(
+            "`load_shader_set` must be implemented unless `reloadable_shaders` returns `Some`",
+        );
+        shaders
+            .builder()
+            .build(factory, shaders.spec_constants().clone())
+            .expect("Failed to build reloadable shaders")
+    }
+
+    /// Shaders to reload when their sources change.
+    ///
+    /// Returning `Some` enables hot-reload: shader sources are polled
+    /// on every `Graph::run` and the graphics pipeline is rebuilt from recompiled shaders.
+    /// Compile and pipeline creation errors are logged and the last good pipeline is kept.
+    /// Pipeline layout and vertex input are not changed by reload.
+    ///
+    /// Shaders are loaded from these sources initially
+    /// unless `load_shader_set` is implemented.
+    fn reloadable_shaders(&self) -> Option<rendy_shader::ReloadableShaderSet> {
+        None
+    }
 
     /// Build pass instance.
     fn build<'a>(
@@ -191,8 +372,15 @@ pub trait SimpleGraphicsPipeline<B: Backend, T: ?Sized>:
 #[derive(Debug)]
 pub struct SimpleRenderGroup<B: Backend, P> {
     set_layouts: Vec<Handle<DescriptorSetLayout<B>>>,
-    pipeline_layout: B::PipelineLayout,
-    graphics_pipeline: B::GraphicsPipeline,
+    graphics_pipeline: Handle<GraphicsPipeline<B>>,
+    framebuffer_width: u32,
+    framebuffer_height: u32,
+    dynamic_viewport: bool,
+    dynamic_scissor: bool,
+    desc: Pipeline,
+    shaders: Option<rendy_shader::ReloadableShaderSet>,
+    generation: u64,
+    recorded: Vec<u64>,
     pipeline: P,
 }
 
@@ -224,6 +412,15 @@ where
         self.inner.depth_stencil().is_some()
     }
 
+    fn samples(&self) -> Option<gfx_hal::image::NumSamples> {
+        Some(
+            self.inner
+                .pipeline()
+                .multisampling
+                .map_or(1, |multisampling| multisampling.rasterization_samples),
+        )
+    }
+
     fn build<'a>(
         self,
         ctx: &GraphContext<B>,
@@ -235,116 +432,87 @@ where
         subpass: gfx_hal::pass::Subpass<'_, B>,
         buffers: Vec<NodeBuffer>,
         images: Vec<NodeImage>,
-    ) -> Result<Box<dyn RenderGroup<B, T>>, gfx_hal::pso::CreationError> {
+    ) -> Result<Box<dyn RenderGroup<B, T>>, NodeBuildError> {
         log::trace!("Load shader sets for");
 
         let mut shader_set = self.inner.load_shader_set(factory, aux);
-
-        let pipeline = self.inner.pipeline();
+        let shaders = self.inner.reloadable_shaders();
+
+        #[allow(unused_mut)]
+        let mut pipeline = self.inner.pipeline();
+
+        #[cfg(feature = "spirv-reflection")]
+        {
+            if let Some(reflection) = self.inner.reflection() {
+                let reflected = reflection.layout().and_then(|layout| {
+                    let vertices = reflection.vertex_input(&self.inner.vertex_formats())?;
+                    Ok((layout, vertices))
+                });
+
+                match reflected {
+                    Ok((layout, vertices)) => {
+                        pipeline.layout = layout;
+                        pipeline.vertices = vertices;
+                    }
+                    Err(e) => {
+                        shader_set.dispose(factory);
+                        return Err(NodeBuildError::Reflect(e));
+                    }
+                }
+            }
+        }
 
         let set_layouts = pipeline
             .layout
             .sets
-            .into_iter()
-            .map(|set| {
-                factory
-                    .create_descriptor_set_layout(set.bindings)
-                    .map(Handle::from)
-            })
+            .iter()
+            .map(|set| factory.get_descriptor_set_layout(set.bindings.clone()))
             .collect::<Result<Vec<_>, _>>()
             .map_err(|e| {
                 shader_set.dispose(factory);
-                e
+                NodeBuildError::OutOfMemory(e)
             })?;
 
-        let pipeline_layout = unsafe {
-            factory.device().create_pipeline_layout(
-                set_layouts.iter().map(|l| l.raw()),
-                pipeline.layout.push_constants,
-            )
-        }
-        .map_err(|e| {
-            shader_set.dispose(factory);
-            gfx_hal::pso::CreationError::OutOfMemory(e)
-        })?;
+        let pipeline_layout = factory
+            .get_pipeline_layout(set_layouts, pipeline.layout.push_constants.clone())
+            .map_err(|e| {
+                shader_set.dispose(factory);
+                NodeBuildError::OutOfMemory(e)
+            })?;
 
         assert_eq!(pipeline.colors.len(), self.inner.colors().len());
 
-        let mut vertex_buffers = Vec::new();
-        let mut attributes = Vec::new();
-
-        for &(ref elemets, stride, rate) in &pipeline.vertices {
-            push_vertex_desc(elemets, stride, rate, &mut vertex_buffers, &mut attributes);
-        }
-
-        let rect = gfx_hal::pso::Rect {
-            x: 0,
-            y: 0,
-            w: framebuffer_width as i16,
-            h: framebuffer_height as i16,
-        };
-
-        let shaders = match shader_set.raw() {
-            Err(e) => {
-                shader_set.dispose(factory);
-                log::warn!("Shader error {:?}", e);
-                return Err(gfx_hal::pso::CreationError::Other);
-            }
-            Ok(s) => s,
-        };
+        let graphics_pipeline =
+            create_graphics_pipeline(factory, &shader_set, &pipeline, pipeline_layout, subpass)
+                .map_err(|e| {
+                    shader_set.dispose(factory);
+                    NodeBuildError::Pipeline(e)
+                })?;
 
-        let graphics_pipeline = unsafe {
-            factory.device().create_graphics_pipelines(
-                Some(gfx_hal::pso::GraphicsPipelineDesc {
-                    shaders,
-                    rasterizer: gfx_hal::pso::Rasterizer::FILL,
-                    vertex_buffers,
-                    attributes,
-                    input_assembler: pipeline.input_assembler_desc,
-                    blender: gfx_hal::pso::BlendDesc {
-                        logic_op: None,
-                        targets: pipeline.colors.clone(),
-                    },
-                    depth_stencil: pipeline.depth_stencil,
-                    multisampling: None,
-                    baked_states: gfx_hal::pso::BakedStates {
-                        viewport: Some(gfx_hal::pso::Viewport {
-                            rect,
-                            depth: 0.0..1.0,
-                        }),
-                        scissor: Some(rect),
-                        blend_color: None,
-                        depth_bounds: None,
-                    },
-                    layout: &pipeline_layout,
-                    subpass,
-                    flags: gfx_hal::pso::PipelineCreationFlags::empty(),
-                    parent: gfx_hal::pso::BasePipeline::None,
-                }),
-                None,
-            )
-        }
-        .remove(0)
-        .map_err(|e| {
-            shader_set.dispose(factory);
-            e
-        })?;
+        let set_layouts = graphics_pipeline.layout().sets().to_vec();
 
-        let pipeline = self
+        let graphics = self
             .inner
             .build(ctx, factory, queue, aux, buffers, images, &set_layouts)
             .map_err(|e| {
                 shader_set.dispose(factory);
-                e
+                NodeBuildError::Pipeline(e)
             })?;
 
         shader_set.dispose(factory);
 
         Ok(Box::new(SimpleRenderGroup::<B, _> {
             set_layouts,
-            pipeline_layout,
             graphics_pipeline,
-            pipeline,
+            framebuffer_width,
+            framebuffer_height,
+            dynamic_viewport: pipeline.baked_states.viewport.is_none(),
+            dynamic_scissor: pipeline.baked_states.scissor.is_none(),
+            desc: pipeline,
+            shaders,
+            generation: 0,
+            recorded: Vec::new(),
+            pipeline: graphics,
         }))
     }
 }
@@ -360,11 +528,28 @@ where
         factory: &Factory<B>,
         queue: QueueId,
         index: usize,
-        _subpass: gfx_hal::pass::Subpass<'_, B>,
+        subpass: gfx_hal::pass::Subpass<'_, B>,
         aux: &T,
     ) -> PrepareResult {
-        self.pipeline
+        if self.reload(factory, subpass) {
+            self.generation += 1;
+        }
+
+        // Command buffers of every frame index must be re-recorded with rebuilt pipeline,
+        // not only the one for the frame in which reload happened.
+        if self.recorded.len() <= index {
+            self.recorded.resize(index + 1, 0);
+        }
+        let outdated = self.recorded[index] != self.generation;
+        self.recorded[index] = self.generation;
+
+        match self
+            .pipeline
             .prepare(factory, queue, &self.set_layouts, index, aux)
+        {
+            PrepareResult::DrawReuse if outdated => PrepareResult::DrawRecord,
+            result => result,
+        }
     }
 
     fn draw_inline(
@@ -374,26 +559,146 @@ where
         _subpass: gfx_hal::pass::Subpass<'_, B>,
         aux: &T,
     ) {
-        encoder.bind_graphics_pipeline(&self.graphics_pipeline);
+        let rect = gfx_hal::pso::Rect {
+            x: 0,
+            y: 0,
+            w: self.framebuffer_width as i16,
+            h: self.framebuffer_height as i16,
+        };
+
+        encoder.bind_graphics_pipeline(self.graphics_pipeline.raw());
+        unsafe {
+            if self.dynamic_viewport {
+                encoder.set_viewports(
+                    0,
+                    Some(&gfx_hal::pso::Viewport {
+                        rect,
+                        depth: 0.0..1.0,
+                    }),
+                );
+            }
+            if self.dynamic_scissor {
+                encoder.set_scissors(0, Some(&rect));
+            }
+        }
         self.pipeline
-            .draw(&self.pipeline_layout, encoder, index, aux);
+            .draw(self.graphics_pipeline.layout().raw(), encoder, index, aux);
     }
 
     fn dispose(self: Box<Self>, factory: &mut Factory<B>, aux: &T) {
         self.pipeline.dispose(factory, aux);
+        drop(self.graphics_pipeline);
+        drop(self.set_layouts);
+    }
+}
 
-        unsafe {
-            factory
-                .device()
-                .destroy_graphics_pipeline(self.graphics_pipeline);
-            factory
-                .device()
-                .destroy_pipeline_layout(self.pipeline_layout);
-            drop(self.set_layouts);
+impl<B, P> SimpleRenderGroup<B, P>
+where
+    B: Backend,
+{
+    /// Rebuild graphics pipeline if shader sources changed.
+    /// Returns `true` if pipeline was rebuilt.
+    fn reload(&mut self, factory: &Factory<B>, subpass: gfx_hal::pass::Subpass<'_, B>) -> bool {
+        let shaders = match &mut self.shaders {
+            Some(shaders) => shaders,
+            None => return false,
+        };
+
+        match shaders.reload() {
+            Ok(true) => {}
+            Ok(false) => return false,
+            Err(e) => {
+                log::error!("Keep last good pipeline: {}", e);
+                return false;
+            }
+        }
+
+        log::info!("Rebuild pipeline with reloaded shaders");
+
+        let mut shader_set = match shaders
+            .builder()
+            .build(factory, shaders.spec_constants().clone())
+        {
+            Ok(shader_set) => shader_set,
+            Err(e) => {
+                log::error!("Keep last good pipeline: {:?}", e);
+                return false;
+            }
+        };
+
+        let result = create_graphics_pipeline(
+            factory,
+            &shader_set,
+            &self.desc,
+            self.graphics_pipeline.layout().clone(),
+            subpass,
+        );
+
+        shader_set.dispose(factory);
+
+        match result {
+            Ok(graphics_pipeline) => {
+                // Old pipeline is destroyed by `Factory` once frames in flight are complete.
+                let old = std::mem::replace(&mut self.graphics_pipeline, graphics_pipeline);
+                factory.release_graphics_pipeline(old);
+                true
+            }
+            Err(e) => {
+                log::error!("Keep last good pipeline: {:?}", e);
+                false
+            }
         }
     }
 }
 
+fn create_graphics_pipeline<B: Backend>(
+    factory: &Factory<B>,
+    shader_set: &rendy_shader::ShaderSet<B>,
+    pipeline: &Pipeline,
+    layout: Handle<PipelineLayout<B>>,
+    subpass: gfx_hal::pass::Subpass<'_, B>,
+) -> Result<Handle<GraphicsPipeline<B>>, gfx_hal::pso::CreationError> {
+    let key = CacheKey::new(shader_set).combine(CacheKey::new(pipeline));
+
+    let mut vertex_buffers = Vec::new();
+    let mut attributes = Vec::new();
+
+    for &(ref elemets, stride, rate) in &pipeline.vertices {
+        push_vertex_desc(elemets, stride, rate, &mut vertex_buffers, &mut attributes);
+    }
+
+    let shaders = match shader_set.raw() {
+        Err(e) => {
+            log::warn!("Shader error {:?}", e);
+            return Err(gfx_hal::pso::CreationError::Other);
+        }
+        Ok(s) => s,
+    };
+
+    factory.get_graphics_pipeline(
+        key,
+        &gfx_hal::pso::GraphicsPipelineDesc {
+            shaders,
+            rasterizer: pipeline.rasterizer,
+            vertex_buffers,
+            attributes,
+            input_assembler: pipeline.input_assembler_desc.clone(),
+            blender: gfx_hal::pso::BlendDesc {
+                logic_op: None,
+                targets: pipeline.colors.clone(),
+            },
+            depth_stencil: pipeline.depth_stencil,
+            multisampling: pipeline.multisampling.clone(),
+            baked_states: pipeline.baked_states.clone(),
+            layout: layout.raw(),
+            subpass,
+            flags: gfx_hal::pso::PipelineCreationFlags::empty(),
+            parent: gfx_hal::pso::BasePipeline::None,
+        },
+        layout.clone(),
+    )
+}
+
 fn push_vertex_desc(
     elements: &[gfx_hal::pso::Element<gfx_hal::format::Format>],
     stride: gfx_hal::pso::ElemStride,
@@ -1,9 +1,9 @@
 use {
     crate::{
         command::{
-            CommandBuffer, CommandPool, ExecutableState, Families, Family, FamilyId, Fence,
-            Graphics, IndividualReset, MultiShot, NoSimultaneousUse, PendingState, Queue, QueueId,
-            SecondaryLevel, SimultaneousUse, Submission, Submit,
+            CommandBuffer, CommandPool, Encoder, ExecutableState, Families, Family, FamilyId,
+            Fence, Graphics, IndividualReset, MultiShot, NoSimultaneousUse, PendingState,
+            PrimaryLevel, Queue, QueueId, SecondaryLevel, SimultaneousUse, Submission, Submit,
         },
         factory::Factory,
         frame::{
@@ -16,6 +16,7 @@ use {
             render::group::{RenderGroup, RenderGroupBuilder},
             BufferAccess, DynNode, ImageAccess, NodeBuffer, NodeBuildError, NodeBuilder, NodeImage,
         },
+        resource::{Handle, RenderPass},
         wsi::{Surface, Target},
         BufferId, ImageId, NodeId,
     },
@@ -36,7 +37,10 @@ pub struct SubpassBuilder<B: Backend, T: ?Sized> {
     groups: Vec<Box<dyn RenderGroupBuilder<B, T>>>,
     inputs: Vec<Attachment>,
     colors: Vec<Attachment>,
+    resolves: Vec<Attachment>,
     depth_stencil: Option<Attachment>,
+    loads: HashMap<Attachment, gfx_hal::pass::AttachmentLoadOp>,
+    stores: HashMap<Attachment, gfx_hal::pass::AttachmentStoreOp>,
     dependencies: Vec<NodeId>,
 }
 
@@ -116,6 +120,36 @@ where
         self
     }
 
+    /// Add resolve attachment to the subpass.
+    /// Multisampled color attachment with the same index is resolved into it.
+    /// Render pass fails to build unless every color attachment gets a resolve attachment.
+    pub fn add_resolve(&mut self, resolve: ImageId) -> &mut Self {
+        self.resolves.push(Either::Left(resolve));
+        self
+    }
+
+    /// Add resolve attachment to the subpass.
+    /// See [`add_resolve`](#method.add_resolve).
+    pub fn with_resolve(mut self, resolve: ImageId) -> Self {
+        self.add_resolve(resolve);
+        self
+    }
+
+    /// Add surface as resolve attachment to the subpass.
+    /// Allows rendering multisampled image and presenting single-sampled result.
+    /// Like [`add_resolve`](#method.add_resolve) it is matched with color attachment by index.
+    pub fn add_resolve_surface(&mut self) -> &mut Self {
+        self.resolves.push(Either::Right(RenderPassSurface));
+        self
+    }
+
+    /// Add surface as resolve attachment to the subpass.
+    /// See [`add_resolve_surface`](#method.add_resolve_surface).
+    pub fn with_resolve_surface(mut self) -> Self {
+        self.add_resolve_surface();
+        self
+    }
+
     /// Set depth-stencil attachment to the subpass.
     pub fn set_depth_stencil(&mut self, depth_stencil: ImageId) -> &mut Self {
         self.depth_stencil = Some(Either::Left(depth_stencil));
@@ -140,6 +174,88 @@ where
         self
     }
 
+    /// Set operation performed on the attachment content when render pass begins.
+    /// If not set for any subpass attachment is cleared if clear value was provided
+    /// to `GraphBuilder::create_image` and loaded otherwise.
+    ///
+    /// `AttachmentLoadOp::Clear` requires clear value for the image.
+    pub fn set_load_op(
+        &mut self,
+        image: ImageId,
+        op: gfx_hal::pass::AttachmentLoadOp,
+    ) -> &mut Self {
+        self.loads.insert(Either::Left(image), op);
+        self
+    }
+
+    /// Set operation performed on the attachment content when render pass begins.
+    /// If not set for any subpass attachment is cleared if clear value was provided
+    /// to `GraphBuilder::create_image` and loaded otherwise.
+    ///
+    /// `AttachmentLoadOp::Clear` requires clear value for the image.
+    pub fn with_load_op(mut self, image: ImageId, op: gfx_hal::pass::AttachmentLoadOp) -> Self {
+        self.set_load_op(image, op);
+        self
+    }
+
+    /// Set operation performed on the surface content when render pass begins.
+    /// If not set for any subpass surface is cleared if clear value was provided
+    /// with the surface and loaded otherwise.
+    ///
+    /// `AttachmentLoadOp::Clear` requires clear value for the surface.
+    pub fn set_surface_load_op(&mut self, op: gfx_hal::pass::AttachmentLoadOp) -> &mut Self {
+        self.loads.insert(Either::Right(RenderPassSurface), op);
+        self
+    }
+
+    /// Set operation performed on the surface content when render pass begins.
+    /// If not set for any subpass surface is cleared if clear value was provided
+    /// with the surface and loaded otherwise.
+    ///
+    /// `AttachmentLoadOp::Clear` requires clear value for the surface.
+    pub fn with_surface_load_op(mut self, op: gfx_hal::pass::AttachmentLoadOp) -> Self {
+        self.set_surface_load_op(op);
+        self
+    }
+
+    /// Set operation performed on the attachment content when render pass ends.
+    /// Attachment content is stored by default.
+    ///
+    /// Content of the image discarded with `AttachmentStoreOp::DontCare`
+    /// can't be read by nodes that are executed after this one.
+    pub fn set_store_op(
+        &mut self,
+        image: ImageId,
+        op: gfx_hal::pass::AttachmentStoreOp,
+    ) -> &mut Self {
+        self.stores.insert(Either::Left(image), op);
+        self
+    }
+
+    /// Set operation performed on the attachment content when render pass ends.
+    /// Attachment content is stored by default.
+    ///
+    /// Content of the image discarded with `AttachmentStoreOp::DontCare`
+    /// can't be read by nodes that are executed after this one.
+    pub fn with_store_op(mut self, image: ImageId, op: gfx_hal::pass::AttachmentStoreOp) -> Self {
+        self.set_store_op(image, op);
+        self
+    }
+
+    /// Set operation performed on the surface content when render pass ends.
+    /// Surface content is stored by default.
+    pub fn set_surface_store_op(&mut self, op: gfx_hal::pass::AttachmentStoreOp) -> &mut Self {
+        self.stores.insert(Either::Right(RenderPassSurface), op);
+        self
+    }
+
+    /// Set operation performed on the surface content when render pass ends.
+    /// Surface content is stored by default.
+    pub fn with_surface_store_op(mut self, op: gfx_hal::pass::AttachmentStoreOp) -> Self {
+        self.set_surface_store_op(op);
+        self
+    }
+
     /// Add dependency.
     /// `RenderPassNode` will be placed after its dependencies.
     pub fn add_dependency(&mut self, dependency: NodeId) -> &mut Self {
@@ -158,6 +274,60 @@ where
     pub fn into_pass(self) -> RenderPassNodeBuilder<B, T> {
         RenderPassNodeBuilder::new().with_subpass(self)
     }
+
+    /// Iterate over all attachments used by this subpass.
+    fn attachments(&self) -> impl Iterator<Item = Attachment> + '_ {
+        self.inputs
+            .iter()
+            .chain(self.colors.iter())
+            .chain(self.resolves.iter())
+            .chain(self.depth_stencil.iter())
+            .cloned()
+    }
+
+    /// Layout the attachment must be in during this subpass.
+    /// Attachment used in more than one way by the same subpass has to be in `General` layout.
+    fn attachment_layout(&self, attachment: Attachment, layout: Layout) -> Layout {
+        if self.attachments().filter(|&a| a == attachment).count() > 1 {
+            Layout::General
+        } else {
+            layout
+        }
+    }
+
+    /// Get access and stages at which this subpass accesses the attachment.
+    fn attachment_access(
+        &self,
+        attachment: Attachment,
+    ) -> (gfx_hal::image::Access, gfx_hal::pso::PipelineStage) {
+        let mut access = gfx_hal::image::Access::empty();
+        let mut stages = gfx_hal::pso::PipelineStage::empty();
+
+        if self.inputs.contains(&attachment) {
+            access |= gfx_hal::image::Access::INPUT_ATTACHMENT_READ;
+            stages |= gfx_hal::pso::PipelineStage::FRAGMENT_SHADER;
+        }
+
+        if self.colors.contains(&attachment) {
+            access |= gfx_hal::image::Access::COLOR_ATTACHMENT_READ
+                | gfx_hal::image::Access::COLOR_ATTACHMENT_WRITE;
+            stages |= gfx_hal::pso::PipelineStage::COLOR_ATTACHMENT_OUTPUT;
+        }
+
+        if self.resolves.contains(&attachment) {
+            access |= gfx_hal::image::Access::COLOR_ATTACHMENT_WRITE;
+            stages |= gfx_hal::pso::PipelineStage::COLOR_ATTACHMENT_OUTPUT;
+        }
+
+        if self.depth_stencil == Some(attachment) {
+            access |= gfx_hal::image::Access::DEPTH_STENCIL_ATTACHMENT_READ
+                | gfx_hal::image::Access::DEPTH_STENCIL_ATTACHMENT_WRITE;
+            stages |= gfx_hal::pso::PipelineStage::EARLY_FRAGMENT_TESTS
+                | gfx_hal::pso::PipelineStage::LATE_FRAGMENT_TESTS;
+        }
+
+        (access, stages)
+    }
 }
 
 /// Builder for render-pass node.
@@ -256,35 +426,49 @@ where
         let mut attachments = HashMap::new();
         let mut images = HashMap::new();
 
+        // Attachment content left by previous nodes is read only if it is loaded.
+        // Attachment without load operation is loaded unless it's cleared on first use,
+        // see `first_use_clears`.
+        let loaded = |id| {
+            attachment_load_op(&self.subpasses, Either::Left(id))
+                .map_or(true, |op| op == gfx_hal::pass::AttachmentLoadOp::Load)
+        };
+
         for subpass in &self.subpasses {
             for &id in subpass.inputs.iter().filter_map(|e| e.as_ref().left()) {
-                let entry = attachments.entry(id).or_insert(ImageAccess {
-                    layout: Layout::ShaderReadOnlyOptimal,
-                    ..empty
-                });
+                let entry = attachments.entry(id).or_insert(empty);
+                entry.layout = common_layout(entry.layout, Layout::ShaderReadOnlyOptimal);
                 entry.access |= gfx_hal::image::Access::INPUT_ATTACHMENT_READ;
                 entry.usage |= gfx_hal::image::Usage::INPUT_ATTACHMENT;
                 entry.stages |= gfx_hal::pso::PipelineStage::FRAGMENT_SHADER;
             }
 
             for &id in subpass.colors.iter().filter_map(|e| e.as_ref().left()) {
-                let entry = attachments.entry(id).or_insert(ImageAccess {
-                    layout: Layout::ColorAttachmentOptimal,
-                    ..empty
-                });
-                entry.access |= gfx_hal::image::Access::COLOR_ATTACHMENT_READ
-                    | gfx_hal::image::Access::COLOR_ATTACHMENT_WRITE;
+                let entry = attachments.entry(id).or_insert(empty);
+                entry.layout = common_layout(entry.layout, Layout::ColorAttachmentOptimal);
+                entry.access |= gfx_hal::image::Access::COLOR_ATTACHMENT_WRITE;
+                if loaded(id) {
+                    entry.access |= gfx_hal::image::Access::COLOR_ATTACHMENT_READ;
+                }
+                entry.usage |= gfx_hal::image::Usage::COLOR_ATTACHMENT;
+                entry.stages |= gfx_hal::pso::PipelineStage::COLOR_ATTACHMENT_OUTPUT;
+            }
+
+            for &id in subpass.resolves.iter().filter_map(|e| e.as_ref().left()) {
+                let entry = attachments.entry(id).or_insert(empty);
+                entry.layout = common_layout(entry.layout, Layout::ColorAttachmentOptimal);
+                entry.access |= gfx_hal::image::Access::COLOR_ATTACHMENT_WRITE;
                 entry.usage |= gfx_hal::image::Usage::COLOR_ATTACHMENT;
                 entry.stages |= gfx_hal::pso::PipelineStage::COLOR_ATTACHMENT_OUTPUT;
             }
 
             if let Some(id) = subpass.depth_stencil.and_then(Either::left) {
-                let entry = attachments.entry(id).or_insert(ImageAccess {
-                    layout: Layout::DepthStencilAttachmentOptimal,
-                    ..empty
-                });
-                entry.access |= gfx_hal::image::Access::DEPTH_STENCIL_ATTACHMENT_READ
-                    | gfx_hal::image::Access::DEPTH_STENCIL_ATTACHMENT_WRITE;
+                let entry = attachments.entry(id).or_insert(empty);
+                entry.layout = common_layout(entry.layout, Layout::DepthStencilAttachmentOptimal);
+                entry.access |= gfx_hal::image::Access::DEPTH_STENCIL_ATTACHMENT_WRITE;
+                if loaded(id) {
+                    entry.access |= gfx_hal::image::Access::DEPTH_STENCIL_ATTACHMENT_READ;
+                }
                 entry.usage |= gfx_hal::image::Usage::DEPTH_STENCIL_ATTACHMENT;
                 entry.stages |= gfx_hal::pso::PipelineStage::EARLY_FRAGMENT_TESTS
                     | gfx_hal::pso::PipelineStage::LATE_FRAGMENT_TESTS;
@@ -308,6 +492,34 @@ where
         attachments.into_iter().chain(images.into_iter()).collect()
     }
 
+    fn discards(&self) -> Vec<ImageId> {
+        let mut discards: Vec<_> = self
+            .subpasses
+            .iter()
+            .flat_map(|subpass| subpass.attachments().filter_map(Either::left))
+            .filter(|&id| {
+                attachment_store_op(&self.subpasses, Either::Left(id))
+                    == gfx_hal::pass::AttachmentStoreOp::DontCare
+            })
+            .collect();
+        discards.sort();
+        discards.dedup();
+        discards
+    }
+
+    fn first_use_clears(&self) -> Vec<ImageId> {
+        // Same default as in `build`.
+        let mut clears: Vec<_> = self
+            .subpasses
+            .iter()
+            .flat_map(|subpass| subpass.attachments().filter_map(Either::left))
+            .filter(|&id| attachment_load_op(&self.subpasses, Either::Left(id)).is_none())
+            .collect();
+        clears.sort();
+        clears.dedup();
+        clears
+    }
+
     fn dependencies(&self) -> Vec<NodeId> {
         let mut dependencies: Vec<_> = self
             .subpasses
@@ -335,6 +547,65 @@ where
         buffers: Vec<NodeBuffer>,
         images: Vec<NodeImage>,
     ) -> Result<Box<dyn DynNode<B, T>>, NodeBuildError> {
+        for (index, subpass) in self.subpasses.iter().enumerate() {
+            if !subpass.resolves.is_empty() && subpass.resolves.len() != subpass.colors.len() {
+                return Err(NodeBuildError::ResolveCountMismatch {
+                    subpass: index,
+                    colors: subpass.colors.len(),
+                    resolves: subpass.resolves.len(),
+                });
+            }
+
+            // Surface images are single sampled.
+            let attachments_samples = subpass
+                .colors
+                .iter()
+                .chain(subpass.depth_stencil.as_ref())
+                .next()
+                .map(|&attachment| match attachment {
+                    Either::Left(image_id) => ctx
+                        .get_image(image_id)
+                        .expect("Image does not exist")
+                        .kind()
+                        .num_samples(),
+                    Either::Right(RenderPassSurface) => 1,
+                });
+
+            if let Some(attachments_samples) = attachments_samples {
+                for group in &subpass.groups {
+                    match group.samples() {
+                        Some(samples) if samples != attachments_samples => {
+                            return Err(NodeBuildError::SampleCountMismatch {
+                                subpass: index,
+                                attachments: attachments_samples,
+                                group: samples,
+                            });
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        for attachment in self.subpasses.iter().flat_map(|s| s.attachments()) {
+            if attachment_load_op(&self.subpasses, attachment)
+                != Some(gfx_hal::pass::AttachmentLoadOp::Clear)
+            {
+                continue;
+            }
+            let clear = match attachment {
+                Either::Left(image_id) => ctx
+                    .get_image_with_clear(image_id)
+                    .and_then(|(_, clear)| clear),
+                Either::Right(RenderPassSurface) => {
+                    self.surface.as_ref().and_then(|&(_, clear)| clear)
+                }
+            };
+            if clear.is_none() {
+                return Err(NodeBuildError::ClearValueMissing(attachment.left()));
+            }
+        }
+
         let mut surface_color_usage = false;
         let mut surface_depth_usage = false;
 
@@ -354,6 +625,9 @@ where
                     .chain(subpass.colors.iter().inspect(|a| {
                         surface_color_usage = surface_color_usage || a.is_right();
                     }))
+                    .chain(subpass.resolves.iter().inspect(|a| {
+                        surface_color_usage = surface_color_usage || a.is_right();
+                    }))
                     .chain(subpass.depth_stencil.as_ref().into_iter().inspect(|a| {
                         surface_depth_usage = surface_depth_usage || a.is_right();
                     }))
@@ -500,19 +774,57 @@ where
             }).collect::<Result<Vec<_>, _>>()?
             .into_iter().flatten().collect();
 
+        log::trace!("Configure attachment operations");
+
+        let subpass_builders = &self.subpasses;
+        let attachment_ops: Vec<_> = attachments
+            .iter()
+            .map(|&attachment| {
+                let (clear, first_use_clear) = match attachment {
+                    Either::Left(image_id) => (
+                        ctx.get_image_with_clear(image_id)
+                            .expect("Image does not exist")
+                            .1,
+                        find_attachment_node_image(image_id).clear,
+                    ),
+                    Either::Right(RenderPassSurface) => (surface_clear, surface_clear),
+                };
+
+                let load = attachment_load_op(subpass_builders, attachment).unwrap_or(
+                    if first_use_clear.is_some() {
+                        gfx_hal::pass::AttachmentLoadOp::Clear
+                    } else {
+                        gfx_hal::pass::AttachmentLoadOp::Load
+                    },
+                );
+
+                // Clear value of explicitly cleared attachment is checked above.
+                let clear = match load {
+                    gfx_hal::pass::AttachmentLoadOp::Clear => clear,
+                    _ => None,
+                };
+
+                (
+                    load,
+                    attachment_store_op(subpass_builders, attachment),
+                    clear,
+                )
+            })
+            .collect();
+
         log::trace!("Configure render pass instance");
 
-        let render_pass: B::RenderPass = {
+        let render_pass: Handle<RenderPass<B>> = {
             let pass_attachments: Vec<_> = attachments
                 .iter()
-                .map(|&attachment| {
-                    let (format, clear, layout, samples) = match attachment {
+                .zip(&attachment_ops)
+                .map(|(&attachment, &(load, store, _))| {
+                    let (format, layout, samples) = match attachment {
                         Either::Left(image_id) => {
                             let node_image = find_attachment_node_image(image_id);
                             let image = ctx.get_image(image_id).expect("Image does not exist");
                             (
                                 image.format(),
-                                node_image.clear,
                                 node_image.layout,
                                 image.kind().num_samples(),
                             )
@@ -523,7 +835,6 @@ where
                                 .expect("Expect target created")
                                 .backbuffer()[0]
                                 .format(),
-                            surface_clear,
                             gfx_hal::image::Layout::Present,
                             1,
                         ),
@@ -531,19 +842,11 @@ where
 
                     gfx_hal::pass::Attachment {
                         format: Some(format),
-                        ops: gfx_hal::pass::AttachmentOps {
-                            load: if clear.is_some() {
-                                gfx_hal::pass::AttachmentLoadOp::Clear
-                            } else {
-                                gfx_hal::pass::AttachmentLoadOp::Load
-                            },
-                            store: gfx_hal::pass::AttachmentStoreOp::Store,
-                        },
+                        ops: gfx_hal::pass::AttachmentOps { load, store },
                         stencil_ops: gfx_hal::pass::AttachmentOps::DONT_CARE,
-                        layouts: if clear.is_some() {
-                            gfx_hal::image::Layout::Undefined..layout
-                        } else {
-                            layout..layout
+                        layouts: match load {
+                            gfx_hal::pass::AttachmentLoadOp::Load => layout..layout,
+                            _ => gfx_hal::image::Layout::Undefined..layout,
                         },
                         samples,
                     }
@@ -556,6 +859,7 @@ where
             struct OwningSubpassDesc {
                 inputs: Vec<(usize, Layout)>,
                 colors: Vec<(usize, Layout)>,
+                resolves: Vec<(usize, Layout)>,
                 depth_stencil: Option<(usize, Layout)>,
             }
 
@@ -569,14 +873,7 @@ where
                         .map(|&i| {
                             (
                                 attachments.iter().position(|&a| a == i).unwrap(),
-                                match i {
-                                    Either::Left(image_id) => {
-                                        find_attachment_node_image(image_id).layout
-                                    }
-                                    Either::Right(RenderPassSurface) => {
-                                        gfx_hal::image::Layout::ShaderReadOnlyOptimal
-                                    }
-                                },
+                                subpass.attachment_layout(i, Layout::ShaderReadOnlyOptimal),
                             )
                         })
                         .collect(),
@@ -586,28 +883,24 @@ where
                         .map(|&c| {
                             (
                                 attachments.iter().position(|&a| a == c).unwrap(),
-                                match c {
-                                    Either::Left(image_id) => {
-                                        find_attachment_node_image(image_id).layout
-                                    }
-                                    Either::Right(RenderPassSurface) => {
-                                        gfx_hal::image::Layout::ColorAttachmentOptimal
-                                    }
-                                },
+                                subpass.attachment_layout(c, Layout::ColorAttachmentOptimal),
+                            )
+                        })
+                        .collect(),
+                    resolves: subpass
+                        .resolves
+                        .iter()
+                        .map(|&r| {
+                            (
+                                attachments.iter().position(|&a| a == r).unwrap(),
+                                subpass.attachment_layout(r, Layout::ColorAttachmentOptimal),
                             )
                         })
                         .collect(),
                     depth_stencil: subpass.depth_stencil.map(|ds| {
                         (
                             attachments.iter().position(|&a| a == ds).unwrap(),
-                            match ds {
-                                Either::Left(image_id) => {
-                                    find_attachment_node_image(image_id).layout
-                                }
-                                Either::Right(RenderPassSurface) => {
-                                    gfx_hal::image::Layout::DepthStencilAttachmentOptimal
-                                }
-                            },
+                            subpass.attachment_layout(ds, Layout::DepthStencilAttachmentOptimal),
                         )
                     }),
                 })
@@ -621,24 +914,23 @@ where
                     inputs: &subpass.inputs[..],
                     colors: &subpass.colors[..],
                     depth_stencil: subpass.depth_stencil.as_ref(),
-                    resolves: &[],
+                    resolves: &subpass.resolves[..],
                     preserves: &[],
                 })
                 .collect();
 
-            let result = unsafe {
-                factory
-                    .device()
-                    .create_render_pass(pass_attachments, subpasses, {
-                        assert_eq!(
-                            self.subpasses.len(),
-                            1,
-                            "TODO: Implement subpass dependencies to allow more than one subpass"
-                        );
-                        std::iter::empty::<gfx_hal::pass::SubpassDependency>()
-                    })
-            }
-            .unwrap();
+            let dependencies: Vec<_> = (0..subpass_builders.len())
+                .flat_map(|dst| (0..dst).map(move |src| (src, dst)))
+                .filter_map(|(src, dst)| {
+                    subpass_dependency(src, &subpass_builders[src], dst, &subpass_builders[dst])
+                })
+                .collect();
+
+            log::debug!("Subpass dependencies {:#?}", dependencies);
+
+            let result = factory
+                .get_render_pass(pass_attachments, subpasses, dependencies)
+                .unwrap();
 
             log::trace!("RenderPass instance created");
             result
@@ -661,7 +953,7 @@ where
                 factory
                     .device()
                     .create_framebuffer(
-                        &render_pass,
+                        render_pass.raw(),
                         views[..attachments.len() - 1].iter().chain(Some(&views[i])),
                         gfx_hal::image::Extent {
                             width: framebuffer_width,
@@ -675,13 +967,15 @@ where
 
         log::trace!("Collect clears for render pass");
 
-        let clears: Vec<_> = attachments
+        // Clear values are indexed by attachment index.
+        // Values for attachments that are not cleared are ignored.
+        let clears: Vec<_> = attachment_ops
             .iter()
-            .filter_map(|&a| match a {
-                Either::Left(image_id) => find_attachment_node_image(image_id).clear,
-                Either::Right(RenderPassSurface) => surface_clear,
+            .map(|&(_, _, clear)| {
+                clear.unwrap_or(gfx_hal::command::ClearValue {
+                    color: gfx_hal::command::ClearColor { float32: [0.0; 4] },
+                })
             })
-            .map(Into::into)
             .collect();
 
         let mut command_pool = factory
@@ -794,7 +1088,7 @@ where
                             framebuffer_height,
                             gfx_hal::pass::Subpass {
                                 index,
-                                main_pass: &render_pass,
+                                main_pass: render_pass.raw(),
                             },
                             buffers,
                             images,
@@ -803,8 +1097,7 @@ where
                     .collect::<Result<Vec<_>, _>>()
                     .map(|groups| SubpassNode { groups })
             })
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(NodeBuildError::Pipeline)?;
+            .collect::<Result<Vec<_>, _>>()?;
 
         let node: Box<dyn DynNode<B, T>> = match node_target {
             Some(target) => {
@@ -907,7 +1200,7 @@ struct RenderPassNodeCommon<B: Backend, T: ?Sized> {
     framebuffer_height: u32,
     _framebuffer_layers: u16,
 
-    render_pass: B::RenderPass,
+    render_pass: Handle<RenderPass<B>>,
     views: Vec<B::ImageView>,
     clears: Vec<gfx_hal::command::ClearValue>,
 
@@ -958,7 +1251,7 @@ where
         for view in self.views {
             factory.device().destroy_image_view(view);
         }
-        factory.device().destroy_render_pass(self.render_pass);
+        drop(self.render_pass);
     }
 }
 
@@ -1050,7 +1343,7 @@ where
                                         index,
                                         gfx_hal::pass::Subpass {
                                             index: subpass_index,
-                                            main_pass: &render_pass,
+                                            main_pass: render_pass.raw(),
                                         },
                                         aux,
                                     )
@@ -1077,6 +1370,8 @@ where
                 if let Some(next) = &next {
                     let ref mut for_image = per_image[next[0] as usize];
 
+                    record_before_pass(subpasses, &mut encoder, index, render_pass.raw(), aux);
+
                     let area = gfx_hal::pso::Rect {
                         x: 0,
                         y: 0,
@@ -1085,28 +1380,29 @@ where
                     };
 
                     let mut pass_encoder = encoder.begin_render_pass_inline(
-                        &render_pass,
+                        render_pass.raw(),
                         &for_image.framebuffer,
                         area,
                         &clears,
                     );
 
-                    subpasses
-                        .iter_mut()
-                        .enumerate()
-                        .for_each(|(subpass_index, subpass)| {
-                            subpass.groups.iter_mut().for_each(|group| {
-                                group.draw_inline(
-                                    pass_encoder.reborrow(),
-                                    index,
-                                    gfx_hal::pass::Subpass {
-                                        index: subpass_index,
-                                        main_pass: &render_pass,
-                                    },
-                                    aux,
-                                )
-                            })
+                    for (subpass_index, subpass) in subpasses.iter_mut().enumerate() {
+                        if subpass_index > 0 {
+                            pass_encoder = pass_encoder.next_subpass_inline();
+                        }
+
+                        subpass.groups.iter_mut().for_each(|group| {
+                            group.draw_inline(
+                                pass_encoder.reborrow(),
+                                index,
+                                gfx_hal::pass::Subpass {
+                                    index: subpass_index,
+                                    main_pass: render_pass.raw(),
+                                },
+                                aux,
+                            )
                         });
+                    }
 
                     drop(pass_encoder);
                 }
@@ -1221,7 +1517,7 @@ where
                                     index,
                                     gfx_hal::pass::Subpass {
                                         index: subpass_index,
-                                        main_pass: &render_pass,
+                                        main_pass: render_pass.raw(),
                                     },
                                     aux,
                                 )
@@ -1243,6 +1539,8 @@ where
                     encoder.execute_commands(std::iter::once(&barriers.submit));
                 }
 
+                record_before_pass(subpasses, &mut encoder, index, render_pass.raw(), aux);
+
                 let area = gfx_hal::pso::Rect {
                     x: 0,
                     y: 0,
@@ -1251,24 +1549,25 @@ where
                 };
 
                 let mut pass_encoder =
-                    encoder.begin_render_pass_inline(&render_pass, framebuffer, area, &clears);
+                    encoder.begin_render_pass_inline(render_pass.raw(), framebuffer, area, &clears);
 
-                subpasses
-                    .iter_mut()
-                    .enumerate()
-                    .for_each(|(subpass_index, subpass)| {
-                        subpass.groups.iter_mut().for_each(|group| {
-                            group.draw_inline(
-                                pass_encoder.reborrow(),
-                                index,
-                                gfx_hal::pass::Subpass {
-                                    index: subpass_index,
-                                    main_pass: &render_pass,
-                                },
-                                aux,
-                            )
-                        })
+                for (subpass_index, subpass) in subpasses.iter_mut().enumerate() {
+                    if subpass_index > 0 {
+                        pass_encoder = pass_encoder.next_subpass_inline();
+                    }
+
+                    subpass.groups.iter_mut().for_each(|group| {
+                        group.draw_inline(
+                            pass_encoder.reborrow(),
+                            index,
+                            gfx_hal::pass::Subpass {
+                                index: subpass_index,
+                                main_pass: render_pass.raw(),
+                            },
+                            aux,
+                        )
                     });
+                }
 
                 drop(pass_encoder);
 
@@ -1296,6 +1595,99 @@ where
     }
 }
 
+/// Let groups of all subpasses record commands before render pass begins.
+fn record_before_pass<B: Backend, T: ?Sized>(
+    subpasses: &mut [SubpassNode<B, T>],
+    encoder: &mut Encoder<'_, B, Graphics, PrimaryLevel>,
+    index: usize,
+    render_pass: &B::RenderPass,
+    aux: &T,
+) {
+    for (subpass_index, subpass) in subpasses.iter_mut().enumerate() {
+        for group in &mut subpass.groups {
+            group.record_before_pass(
+                encoder,
+                index,
+                gfx_hal::pass::Subpass {
+                    index: subpass_index,
+                    main_pass: render_pass,
+                },
+                aux,
+            );
+        }
+    }
+}
+
+/// Get load operation for the attachment.
+/// First subpass that specifies load operation for the attachment defines it.
+fn attachment_load_op<B: Backend, T: ?Sized>(
+    subpasses: &[SubpassBuilder<B, T>],
+    attachment: Attachment,
+) -> Option<gfx_hal::pass::AttachmentLoadOp> {
+    subpasses
+        .iter()
+        .find_map(|subpass| subpass.loads.get(&attachment).cloned())
+}
+
+/// Get store operation for the attachment.
+/// Last subpass that specifies store operation for the attachment defines it.
+fn attachment_store_op<B: Backend, T: ?Sized>(
+    subpasses: &[SubpassBuilder<B, T>],
+    attachment: Attachment,
+) -> gfx_hal::pass::AttachmentStoreOp {
+    subpasses
+        .iter()
+        .rev()
+        .find_map(|subpass| subpass.stores.get(&attachment).cloned())
+        .unwrap_or(gfx_hal::pass::AttachmentStoreOp::Store)
+}
+
+/// Derive dependency between two subpasses from attachments they access.
+/// Returns `None` if subpasses can be executed in any order.
+fn subpass_dependency<B: Backend, T: ?Sized>(
+    src_index: usize,
+    src: &SubpassBuilder<B, T>,
+    dst_index: usize,
+    dst: &SubpassBuilder<B, T>,
+) -> Option<gfx_hal::pass::SubpassDependency> {
+    let writes = gfx_hal::image::Access::COLOR_ATTACHMENT_WRITE
+        | gfx_hal::image::Access::DEPTH_STENCIL_ATTACHMENT_WRITE;
+
+    let mut src_access = gfx_hal::image::Access::empty();
+    let mut src_stages = gfx_hal::pso::PipelineStage::empty();
+    let mut dst_access = gfx_hal::image::Access::empty();
+    let mut dst_stages = gfx_hal::pso::PipelineStage::empty();
+
+    for attachment in dst.attachments() {
+        let (src_attachment_access, src_attachment_stages) = src.attachment_access(attachment);
+        if src_attachment_access.is_empty() {
+            continue;
+        }
+
+        let (dst_attachment_access, dst_attachment_stages) = dst.attachment_access(attachment);
+        if !(src_attachment_access | dst_attachment_access).intersects(writes) {
+            // Read after read doesn't require synchronization.
+            continue;
+        }
+
+        src_access |= src_attachment_access;
+        src_stages |= src_attachment_stages;
+        dst_access |= dst_attachment_access;
+        dst_stages |= dst_attachment_stages;
+    }
+
+    if src_stages.is_empty() {
+        None
+    } else {
+        Some(gfx_hal::pass::SubpassDependency {
+            passes: gfx_hal::pass::SubpassRef::Pass(src_index)
+                ..gfx_hal::pass::SubpassRef::Pass(dst_index),
+            stages: src_stages..dst_stages,
+            accesses: src_access..dst_access,
+        })
+    }
+}
+
 fn common_layout(acc: Layout, layout: Layout) -> Layout {
     match (acc, layout) {
         (Layout::Undefined, layout) => layout,
@@ -1309,3 +1701,62 @@ fn common_layout(acc: Layout, layout: Layout) -> Layout {
         (_, _) => Layout::General,
     }
 }
+
+#[cfg(test)]
+mod test {
+    rendy_util::rendy_with_empty_backend! {
+        use super::*;
+        use gfx_hal::{image::Access, pso::PipelineStage};
+
+        type Subpass = SubpassBuilder<rendy_util::empty::Backend, ()>;
+
+        #[test]
+        fn test_dependency_color_to_input() {
+            let src = Subpass::new().with_color(ImageId(0));
+            let dst = Subpass::new().with_input(ImageId(0)).with_color(ImageId(1));
+
+            let dependency = subpass_dependency(0, &src, 1, &dst).unwrap();
+            assert_eq!(
+                dependency.passes,
+                gfx_hal::pass::SubpassRef::Pass(0)..gfx_hal::pass::SubpassRef::Pass(1)
+            );
+            assert_eq!(
+                dependency.stages,
+                PipelineStage::COLOR_ATTACHMENT_OUTPUT..PipelineStage::FRAGMENT_SHADER
+            );
+            assert_eq!(
+                dependency.accesses,
+                Access::COLOR_ATTACHMENT_READ | Access::COLOR_ATTACHMENT_WRITE
+                    ..Access::INPUT_ATTACHMENT_READ
+            );
+        }
+
+        #[test]
+        fn test_dependency_depth_to_depth() {
+            let src = Subpass::new().with_depth_stencil(ImageId(0));
+            let dst = Subpass::new().with_depth_stencil(ImageId(0));
+
+            let tests = PipelineStage::EARLY_FRAGMENT_TESTS | PipelineStage::LATE_FRAGMENT_TESTS;
+            let access =
+                Access::DEPTH_STENCIL_ATTACHMENT_READ | Access::DEPTH_STENCIL_ATTACHMENT_WRITE;
+
+            let dependency = subpass_dependency(0, &src, 1, &dst).unwrap();
+            assert_eq!(dependency.stages, tests..tests);
+            assert_eq!(dependency.accesses, access..access);
+        }
+
+        #[test]
+        fn test_no_dependency_read_after_read() {
+            let src = Subpass::new().with_input(ImageId(0)).with_color(ImageId(1));
+            let dst = Subpass::new().with_input(ImageId(0)).with_color(ImageId(2));
+            assert!(subpass_dependency(0, &src, 1, &dst).is_none());
+        }
+
+        #[test]
+        fn test_no_dependency_disjoint_attachments() {
+            let src = Subpass::new().with_color(ImageId(0));
+            let dst = Subpass::new().with_color(ImageId(1));
+            assert!(subpass_dependency(0, &src, 1, &dst).is_none());
+        }
+    }
+}
@@ -489,7 +489,7 @@ where
                         flags: hal::pso::PipelineCreationFlags::empty(),
                         parent: hal::pso::BasePipeline::None,
                     },
-                    None,
+                    Some(factory.pipeline_cache()),
                 )
                 .map_err(NodeBuildError::Pipeline)?
         };
@@ -31,7 +31,7 @@ pub struct BufferInfo {
 pub struct Buffer<B: Backend> {
     device: DeviceId,
     raw: B::Buffer,
-    block: MemoryBlock<B>,
+    block: Option<MemoryBlock<B>>,
     info: BufferInfo,
     relevant: Relevant,
 }
@@ -83,18 +83,75 @@ where
         Ok(Buffer {
             device: device.id(),
             raw: buf,
-            block,
+            block: Some(block),
+            info,
+            relevant: Relevant,
+        })
+    }
+
+    /// Create buffer without binding memory to it.
+    /// Returns raw buffer along with its memory requirements.
+    ///
+    /// # Safety
+    ///
+    /// Raw buffer must be either bound with [`bind_aliased`] or destroyed.
+    ///
+    /// [`bind_aliased`]: #method.bind_aliased
+    pub unsafe fn create_unbound(
+        device: &Device<B>,
+        info: BufferInfo,
+    ) -> Result<(B::Buffer, gfx_hal::memory::Requirements), BufferCreationError> {
+        log::trace!("{:#?}", info);
+        assert_ne!(info.size, 0);
+
+        let buf = device
+            .create_buffer(info.size, info.usage)
+            .map_err(CreationError::Create)?;
+        let reqs = device.get_buffer_requirements(&buf);
+        Ok((buf, reqs))
+    }
+
+    /// Bind buffer created with [`create_unbound`] to memory range owned by another block.
+    /// Buffer doesn't take ownership of the memory so multiple resources can alias it.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be created from `device` with `info` and not bound yet.
+    /// `offset` must satisfy buffer's memory requirements.
+    /// Memory must not be freed while buffer is in use.
+    /// Accesses to aliasing resources must be properly synchronized.
+    /// `raw` is destroyed if binding fails.
+    ///
+    /// [`create_unbound`]: #method.create_unbound
+    pub unsafe fn bind_aliased(
+        device: &Device<B>,
+        mut raw: B::Buffer,
+        info: BufferInfo,
+        memory: &B::Memory,
+        offset: u64,
+    ) -> Result<Self, BufferCreationError> {
+        if let Err(err) = device.bind_buffer_memory(memory, offset, &mut raw) {
+            device.destroy_buffer(raw);
+            return Err(CreationError::Bind(err));
+        }
+
+        Ok(Buffer {
+            device: device.id(),
+            raw,
+            block: None,
             info,
             relevant: Relevant,
         })
     }
 
     /// Dispose of buffer resource.
-    /// Deallocate memory block.
+    /// Deallocate memory block if owned.
     pub unsafe fn dispose(self, device: &Device<B>, heaps: &mut Heaps<B>) {
         self.assert_device_owner(device);
         device.destroy_buffer(self.raw);
-        heaps.free(device, self.block);
+        if let Some(block) = self.block {
+            heaps.free(device, block);
+        }
         self.relevant.dispose();
     }
 
@@ -109,13 +166,15 @@ where
     }
 
     /// Get reference to memory block occupied by buffer.
-    pub fn block(&self) -> &MemoryBlock<B> {
-        &self.block
+    /// Returns `None` if buffer is bound to memory it doesn't own.
+    pub fn block(&self) -> Option<&MemoryBlock<B>> {
+        self.block.as_ref()
     }
 
     /// Get mutable reference to memory block occupied by buffer.
-    pub unsafe fn block_mut(&mut self) -> &mut MemoryBlock<B> {
-        &mut self.block
+    /// Returns `None` if buffer is bound to memory it doesn't own.
+    pub unsafe fn block_mut(&mut self) -> Option<&mut MemoryBlock<B>> {
+        self.block.as_mut()
     }
 
     /// Get buffer info.
@@ -129,9 +188,11 @@ where
     /// [`map`]: #method.map
     /// [`InvalidAccess`]: https://docs.rs/gfx-hal/0.1/gfx_hal/mapping/enum.Error.html#InvalidAccess
     pub fn visible(&self) -> bool {
-        self.block
-            .properties()
-            .contains(gfx_hal::memory::Properties::CPU_VISIBLE)
+        self.block.as_ref().map_or(false, |block| {
+            block
+                .properties()
+                .contains(gfx_hal::memory::Properties::CPU_VISIBLE)
+        })
     }
 
     /// Map range of the buffer to the CPU accessible memory.
@@ -140,7 +201,10 @@ where
         device: &Device<B>,
         range: std::ops::Range<u64>,
     ) -> Result<MappedRange<'a, B>, gfx_hal::device::MapError> {
-        self.block.map(device, range)
+        self.block
+            .as_mut()
+            .ok_or(gfx_hal::device::MapError::MappingFailed)?
+            .map(device, range)
     }
 
     /// Get buffer info.
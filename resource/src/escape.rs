@@ -167,6 +167,18 @@ impl<T> From<Escape<T>> for Handle<T> {
     }
 }
 
+impl<T> Handle<T> {
+    /// Check if this is the only handle of the resource.
+    pub fn is_unique(&self) -> bool {
+        Arc::strong_count(&self.inner) == 1
+    }
+
+    /// Check if two handles point to the same resource.
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&this.inner, &other.inner)
+    }
+}
+
 impl<T> Deref for Handle<T> {
     type Target = T;
 
@@ -116,6 +116,75 @@ where
         })
     }
 
+    /// Create image without binding memory to it.
+    /// Returns raw image along with its memory requirements.
+    ///
+    /// # Safety
+    ///
+    /// Raw image must be either bound with [`bind_aliased`] or destroyed.
+    ///
+    /// [`bind_aliased`]: #method.bind_aliased
+    pub unsafe fn create_unbound(
+        device: &Device<B>,
+        info: ImageInfo,
+    ) -> Result<(B::Image, gfx_hal::memory::Requirements), ImageCreationError> {
+        assert!(
+            info.levels <= info.kind.num_levels(),
+            "Number of mip leves ({}) cannot be greater than {} for given kind {:?}",
+            info.levels,
+            info.kind.num_levels(),
+            info.kind,
+        );
+
+        log::trace!("{:#?}", info);
+
+        let img = device
+            .create_image(
+                info.kind,
+                info.levels,
+                info.format,
+                info.tiling,
+                info.usage,
+                info.view_caps,
+            )
+            .map_err(CreationError::Create)?;
+        let reqs = device.get_image_requirements(&img);
+        Ok((img, reqs))
+    }
+
+    /// Bind image created with [`create_unbound`] to memory range owned by another block.
+    /// Image doesn't take ownership of the memory so multiple resources can alias it.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be created from `device` with `info` and not bound yet.
+    /// `offset` must satisfy image's memory requirements.
+    /// Memory must not be freed while image is in use.
+    /// Accesses to aliasing resources must be properly synchronized.
+    /// `raw` is destroyed if binding fails.
+    ///
+    /// [`create_unbound`]: #method.create_unbound
+    pub unsafe fn bind_aliased(
+        device: &Device<B>,
+        mut raw: B::Image,
+        info: ImageInfo,
+        memory: &B::Memory,
+        offset: u64,
+    ) -> Result<Self, ImageCreationError> {
+        if let Err(err) = device.bind_image_memory(memory, offset, &mut raw) {
+            device.destroy_image(raw);
+            return Err(CreationError::Bind(err));
+        }
+
+        Ok(Image {
+            device: device.id(),
+            raw,
+            block: None,
+            info,
+            relevant: Relevant,
+        })
+    }
+
     /// Create image handler for swapchain image.
     pub unsafe fn create_from_swapchain(device: DeviceId, info: ImageInfo, raw: B::Image) -> Self {
         Image {
@@ -131,7 +200,9 @@ where
     pub unsafe fn dispose(self, device: &Device<B>, heaps: &mut Heaps<B>) {
         self.assert_device_owner(device);
         device.destroy_image(self.raw);
-        self.block.map(|block| heaps.free(device, block));
+        if let Some(block) = self.block {
+            heaps.free(device, block);
+        }
         self.relevant.dispose();
     }
 
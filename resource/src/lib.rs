@@ -18,12 +18,16 @@ use rendy_util as util;
 mod buffer;
 mod escape;
 mod image;
+mod pipeline;
+mod query;
 mod set;
 
 mod resources;
 mod sampler;
 
-pub use crate::{buffer::*, escape::*, image::*, resources::*, sampler::*, set::*};
+pub use crate::{
+    buffer::*, escape::*, image::*, pipeline::*, query::*, resources::*, sampler::*, set::*,
+};
 
 /// Error creating a resource.
 #[derive(Debug)]
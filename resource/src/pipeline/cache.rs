@@ -0,0 +1,224 @@
+//! A cache to store and retrieve pipelines and layouts
+
+use {
+    crate::escape::Handle,
+    std::{
+        collections::hash_map::{DefaultHasher, Entry, HashMap},
+        fmt::Debug,
+        hash::{Hash, Hasher},
+        ops::{Deref, DerefMut},
+        sync::Arc,
+    },
+};
+
+/// Key of the cached resource.
+/// Holds full description of the resource,
+/// so keys of different descriptions never compare equal even if their hashes collide.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CacheKey {
+    hash: u64,
+    description: Arc<[u8]>,
+}
+
+impl CacheKey {
+    /// Make key from hashable value.
+    /// Every byte fed to the hasher by `value` is stored in the key.
+    pub fn new<T: Hash + ?Sized>(value: &T) -> Self {
+        let mut writer = DescriptionWriter(Vec::new());
+        value.hash(&mut writer);
+        CacheKey {
+            hash: writer.finish(),
+            description: writer.0.into(),
+        }
+    }
+
+    /// Combine two keys into one.
+    pub fn combine(self, other: CacheKey) -> Self {
+        CacheKey::new(&(&*self.description, &*other.description))
+    }
+
+    /// Get hash of the description.
+    pub fn value(&self) -> u64 {
+        self.hash
+    }
+}
+
+impl Hash for CacheKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.hash.hash(state);
+    }
+}
+
+impl Debug for CacheKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CacheKey({:016x})", self.hash)
+    }
+}
+
+/// Hasher that records bytes written into it.
+struct DescriptionWriter(Vec<u8>);
+
+impl Hasher for DescriptionWriter {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hasher.write(&self.0);
+        hasher.finish()
+    }
+}
+
+/// Resource cache holds handlers to created resources
+/// keyed by description they were created from.
+#[derive(Debug, derivative::Derivative)]
+#[derivative(Default(bound = ""))]
+pub struct ResourceCache<T> {
+    resources: HashMap<CacheKey, Handle<T>>,
+}
+
+impl<T> ResourceCache<T> {
+    /// Get resource with specified key.
+    /// Create new one using closure provided.
+    pub fn get<E>(
+        &mut self,
+        key: CacheKey,
+        create: impl FnOnce() -> Result<Handle<T>, E>,
+    ) -> Result<Handle<T>, E> {
+        Ok(match self.resources.entry(key) {
+            Entry::Occupied(occupied) => occupied.get().clone(),
+            Entry::Vacant(vacant) => {
+                let resource = create()?;
+                vacant.insert(resource).clone()
+            }
+        })
+    }
+
+    /// Get resource with specified key.
+    /// Create new one using closure provided.
+    /// Does not lock for writing if resource exists.
+    pub fn get_with_upgradable_lock<R, W, U, E>(
+        read: R,
+        upgrade: U,
+        key: CacheKey,
+        create: impl FnOnce() -> Result<Handle<T>, E>,
+    ) -> Result<Handle<T>, E>
+    where
+        R: Deref<Target = Self>,
+        W: DerefMut<Target = Self>,
+        U: FnOnce(R) -> W,
+    {
+        if let Some(resource) = read.resources.get(&key) {
+            return Ok(resource.clone());
+        }
+        let resource = create()?;
+        {
+            upgrade(read).resources.insert(key, resource.clone());
+        }
+        Ok(resource)
+    }
+
+    /// Iterate over cached resources.
+    pub fn iter(&self) -> impl Iterator<Item = &Handle<T>> {
+        self.resources.values()
+    }
+
+    /// Number of cached resources.
+    pub fn len(&self) -> usize {
+        self.resources.len()
+    }
+
+    /// Check if cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+
+    /// Drop all cached handlers.
+    /// Resources will be destroyed once all other handlers are dropped.
+    pub fn clear(&mut self) {
+        self.resources.clear();
+    }
+
+    /// Drop the handle and evict the resource from the cache
+    /// if it is not used anywhere else.
+    pub fn release(&mut self, handle: Handle<T>) {
+        let key = self
+            .resources
+            .iter()
+            .find(|(_, resource)| Handle::ptr_eq(resource, &handle))
+            .map(|(key, _)| key.clone());
+        drop(handle);
+
+        if let Some(key) = key {
+            if self.resources[&key].is_unique() {
+                self.resources.remove(&key);
+            }
+        }
+    }
+
+    /// Drop cached handlers of resources that are not used anywhere else.
+    pub fn evict_unused(&mut self) {
+        self.resources.retain(|_, resource| !resource.is_unique());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::escape::Terminal;
+
+    #[test]
+    fn test_cache_key_equality() {
+        assert_eq!(CacheKey::new(&(1u32, "a")), CacheKey::new(&(1u32, "a")));
+        assert_ne!(CacheKey::new(&(1u32, "a")), CacheKey::new(&(2u32, "a")));
+        assert_ne!(
+            CacheKey::new(&1u32).combine(CacheKey::new(&2u32)),
+            CacheKey::new(&2u32).combine(CacheKey::new(&1u32))
+        );
+    }
+
+    #[test]
+    fn test_resource_cache_keeps_unused_until_evicted() {
+        let mut terminal = Terminal::new();
+        let mut cache = ResourceCache::default();
+        let key = CacheKey::new("resource");
+
+        let first = cache
+            .get(key.clone(), || Ok::<_, ()>(terminal.escape(1u32).into()))
+            .unwrap();
+
+        // Handle dropped by the user, e.g. when graph is disposed.
+        // Rebuilding gets the same resource.
+        drop(first.clone());
+        let second = cache
+            .get(key.clone(), || -> Result<_, ()> {
+                panic!("Resource is cached")
+            })
+            .unwrap();
+        assert!(Handle::ptr_eq(&first, &second));
+
+        // Only resources without handles outside of the cache are evicted.
+        cache.evict_unused();
+        assert_eq!(cache.len(), 1);
+
+        drop(first);
+        drop(second);
+        cache.evict_unused();
+        assert!(cache.is_empty());
+
+        let third = cache
+            .get(key, || Ok::<_, ()>(terminal.escape(2u32).into()))
+            .unwrap();
+        assert_eq!(*third, 2);
+
+        // Released resource stays cached while used elsewhere.
+        let fourth = third.clone();
+        cache.release(third);
+        assert_eq!(cache.len(), 1);
+        cache.release(fourth);
+        assert!(cache.is_empty());
+
+        assert_eq!(terminal.drain().collect::<Vec<_>>(), vec![1, 2]);
+    }
+}
@@ -0,0 +1,263 @@
+//! Render pass, pipeline layout and pipeline wrappers.
+
+mod cache;
+
+use {
+    crate::{
+        escape::Handle,
+        set::DescriptorSetLayout,
+        util::{device_owned, Device, DeviceId},
+    },
+    gfx_hal::{device::Device as _, Backend},
+    relevant::Relevant,
+};
+
+pub use crate::pipeline::cache::{CacheKey, ResourceCache};
+
+/// Generic render pass resource wrapper.
+#[derive(Debug)]
+pub struct RenderPass<B: Backend> {
+    device: DeviceId,
+    raw: B::RenderPass,
+    key: CacheKey,
+    compatibility: CacheKey,
+    relevant: Relevant,
+}
+
+device_owned!(RenderPass<B>);
+
+impl<B> RenderPass<B>
+where
+    B: Backend,
+{
+    /// Create new render pass.
+    pub unsafe fn create<'a>(
+        device: &Device<B>,
+        attachments: Vec<gfx_hal::pass::Attachment>,
+        subpasses: Vec<gfx_hal::pass::SubpassDesc<'a>>,
+        dependencies: Vec<gfx_hal::pass::SubpassDependency>,
+    ) -> Result<Self, gfx_hal::device::OutOfMemory> {
+        let key = Self::key(&attachments, &subpasses, &dependencies);
+        let compatibility = Self::compatibility(&attachments, &subpasses, &dependencies);
+        let raw = device.create_render_pass(attachments, subpasses, dependencies)?;
+
+        Ok(RenderPass {
+            device: device.id(),
+            raw,
+            key,
+            compatibility,
+            relevant: Relevant,
+        })
+    }
+
+    /// Key that identifies render pass created from specified description.
+    pub fn key(
+        attachments: &[gfx_hal::pass::Attachment],
+        subpasses: &[gfx_hal::pass::SubpassDesc<'_>],
+        dependencies: &[gfx_hal::pass::SubpassDependency],
+    ) -> CacheKey {
+        let subpasses: Vec<_> = subpasses
+            .iter()
+            .map(|subpass| {
+                (
+                    subpass.colors,
+                    subpass.depth_stencil,
+                    subpass.inputs,
+                    subpass.resolves,
+                    subpass.preserves,
+                )
+            })
+            .collect();
+
+        CacheKey::new(&(attachments, subpasses, dependencies))
+    }
+
+    /// Key that is equal for all compatible render passes.
+    /// Render passes are compatible if they differ only in
+    /// attachment load and store operations and image layouts.
+    ///
+    /// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/html/vkspec.html#renderpass-compatibility
+    pub fn compatibility(
+        attachments: &[gfx_hal::pass::Attachment],
+        subpasses: &[gfx_hal::pass::SubpassDesc<'_>],
+        dependencies: &[gfx_hal::pass::SubpassDependency],
+    ) -> CacheKey {
+        let ids = |refs: &[gfx_hal::pass::AttachmentRef]| -> Vec<_> {
+            refs.iter().map(|&(id, _)| id).collect()
+        };
+
+        let attachments: Vec<_> = attachments
+            .iter()
+            .map(|attachment| (attachment.format, attachment.samples))
+            .collect();
+
+        let subpasses: Vec<_> = subpasses
+            .iter()
+            .map(|subpass| {
+                (
+                    ids(subpass.colors),
+                    subpass.depth_stencil.map(|&(id, _)| id),
+                    ids(subpass.inputs),
+                    ids(subpass.resolves),
+                    subpass.preserves,
+                )
+            })
+            .collect();
+
+        CacheKey::new(&(attachments, subpasses, dependencies))
+    }
+
+    /// Destroy render pass resource.
+    pub unsafe fn dispose(self, device: &Device<B>) {
+        self.assert_device_owner(device);
+        device.destroy_render_pass(self.raw);
+        self.relevant.dispose();
+    }
+
+    /// Get reference to raw render pass resource.
+    pub fn raw(&self) -> &B::RenderPass {
+        &self.raw
+    }
+
+    /// Get mutable reference to raw render pass resource.
+    pub unsafe fn raw_mut(&mut self) -> &mut B::RenderPass {
+        &mut self.raw
+    }
+
+    /// Get key of the render pass description.
+    pub fn cache_key(&self) -> CacheKey {
+        self.key.clone()
+    }
+
+    /// Get render pass compatibility key.
+    pub fn compatibility_key(&self) -> CacheKey {
+        self.compatibility.clone()
+    }
+}
+
+/// Generic pipeline layout resource wrapper.
+#[derive(Debug)]
+pub struct PipelineLayout<B: Backend> {
+    device: DeviceId,
+    raw: B::PipelineLayout,
+    sets: Vec<Handle<DescriptorSetLayout<B>>>,
+    push_constants: Vec<(gfx_hal::pso::ShaderStageFlags, std::ops::Range<u32>)>,
+    relevant: Relevant,
+}
+
+device_owned!(PipelineLayout<B>);
+
+impl<B> PipelineLayout<B>
+where
+    B: Backend,
+{
+    /// Create new pipeline layout.
+    /// Pipeline layout keeps descriptor set layouts alive.
+    pub unsafe fn create(
+        device: &Device<B>,
+        sets: Vec<Handle<DescriptorSetLayout<B>>>,
+        push_constants: Vec<(gfx_hal::pso::ShaderStageFlags, std::ops::Range<u32>)>,
+    ) -> Result<Self, gfx_hal::device::OutOfMemory> {
+        let raw = device
+            .create_pipeline_layout(sets.iter().map(|set| set.raw()), push_constants.iter())?;
+
+        Ok(PipelineLayout {
+            device: device.id(),
+            raw,
+            sets,
+            push_constants,
+            relevant: Relevant,
+        })
+    }
+
+    /// Destroy pipeline layout resource.
+    pub unsafe fn dispose(self, device: &Device<B>) {
+        self.assert_device_owner(device);
+        device.destroy_pipeline_layout(self.raw);
+        drop(self.sets);
+        self.relevant.dispose();
+    }
+
+    /// Get reference to raw pipeline layout resource.
+    pub fn raw(&self) -> &B::PipelineLayout {
+        &self.raw
+    }
+
+    /// Get mutable reference to raw pipeline layout resource.
+    pub unsafe fn raw_mut(&mut self) -> &mut B::PipelineLayout {
+        &mut self.raw
+    }
+
+    /// Get descriptor set layouts of the pipeline layout.
+    pub fn sets(&self) -> &[Handle<DescriptorSetLayout<B>>] {
+        &self.sets
+    }
+
+    /// Get push constant ranges of the pipeline layout.
+    pub fn push_constants(&self) -> &[(gfx_hal::pso::ShaderStageFlags, std::ops::Range<u32>)] {
+        &self.push_constants
+    }
+}
+
+/// Generic graphics pipeline resource wrapper.
+#[derive(Debug)]
+pub struct GraphicsPipeline<B: Backend> {
+    device: DeviceId,
+    raw: B::GraphicsPipeline,
+    layout: Handle<PipelineLayout<B>>,
+    relevant: Relevant,
+}
+
+device_owned!(GraphicsPipeline<B>);
+
+impl<B> GraphicsPipeline<B>
+where
+    B: Backend,
+{
+    /// Create new graphics pipeline.
+    /// `desc.layout` must be raw pipeline layout of the `layout`.
+    /// Graphics pipeline keeps its pipeline layout alive.
+    pub unsafe fn create(
+        device: &Device<B>,
+        desc: &gfx_hal::pso::GraphicsPipelineDesc<'_, B>,
+        layout: Handle<PipelineLayout<B>>,
+        cache: Option<&B::PipelineCache>,
+    ) -> Result<Self, gfx_hal::pso::CreationError> {
+        assert!(
+            std::ptr::eq(desc.layout, layout.raw()),
+            "Pipeline must be created with specified layout"
+        );
+
+        let raw = device.create_graphics_pipeline(desc, cache)?;
+
+        Ok(GraphicsPipeline {
+            device: device.id(),
+            raw,
+            layout,
+            relevant: Relevant,
+        })
+    }
+
+    /// Destroy graphics pipeline resource.
+    pub unsafe fn dispose(self, device: &Device<B>) {
+        self.assert_device_owner(device);
+        device.destroy_graphics_pipeline(self.raw);
+        drop(self.layout);
+        self.relevant.dispose();
+    }
+
+    /// Get reference to raw graphics pipeline resource.
+    pub fn raw(&self) -> &B::GraphicsPipeline {
+        &self.raw
+    }
+
+    /// Get mutable reference to raw graphics pipeline resource.
+    pub unsafe fn raw_mut(&mut self) -> &mut B::GraphicsPipeline {
+        &mut self.raw
+    }
+
+    /// Get pipeline layout of the graphics pipeline.
+    pub fn layout(&self) -> &Handle<PipelineLayout<B>> {
+        &self.layout
+    }
+}
@@ -0,0 +1,167 @@
+//! Typed query pool wrappers.
+
+use {
+    crate::util::{device_owned, Device, DeviceId},
+    gfx_hal::{device::Device as _, Backend},
+    relevant::Relevant,
+};
+
+/// Type of queries in the pool.
+pub trait QueryType: Copy + std::fmt::Debug + Send + Sync + 'static {
+    /// Get raw query type.
+    fn raw(&self) -> gfx_hal::query::Type;
+
+    /// Number of 64-bit values in result of one query.
+    fn result_len(&self) -> usize {
+        1
+    }
+}
+
+/// Occlusion queries count samples that pass depth and stencil tests.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Occlusion;
+
+impl QueryType for Occlusion {
+    fn raw(&self) -> gfx_hal::query::Type {
+        gfx_hal::query::Type::Occlusion
+    }
+}
+
+/// Pipeline statistics queries count operations performed by pipeline stages.
+/// Result of the query contains one value per enabled statistic.
+#[derive(Clone, Copy, Debug)]
+pub struct PipelineStatistics(pub gfx_hal::query::PipelineStatistic);
+
+impl QueryType for PipelineStatistics {
+    fn raw(&self) -> gfx_hal::query::Type {
+        gfx_hal::query::Type::PipelineStatistics(self.0)
+    }
+
+    fn result_len(&self) -> usize {
+        self.0.bits().count_ones() as usize
+    }
+}
+
+/// Timestamp queries record time when commands reach pipeline stage.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Timestamp;
+
+impl QueryType for Timestamp {
+    fn raw(&self) -> gfx_hal::query::Type {
+        gfx_hal::query::Type::Timestamp
+    }
+}
+
+/// Query pool wrapper.
+#[derive(derivative::Derivative)]
+#[derivative(Debug(bound = "Q: std::fmt::Debug"))]
+pub struct QueryPool<B: Backend, Q> {
+    device: DeviceId,
+    #[derivative(Debug = "ignore")]
+    raw: B::QueryPool,
+    ty: Q,
+    count: gfx_hal::query::Id,
+    relevant: Relevant,
+}
+
+device_owned!(QueryPool<B, Q>);
+
+impl<B, Q> QueryPool<B, Q>
+where
+    B: Backend,
+    Q: QueryType,
+{
+    /// Create query pool with `count` queries.
+    pub fn create(
+        device: &Device<B>,
+        ty: Q,
+        count: gfx_hal::query::Id,
+    ) -> Result<Self, gfx_hal::query::CreationError> {
+        let raw = unsafe { device.create_query_pool(ty.raw(), count) }?;
+        Ok(QueryPool {
+            device: device.id(),
+            raw,
+            ty,
+            count,
+            relevant: Relevant,
+        })
+    }
+
+    /// Destroy query pool.
+    ///
+    /// # Safety
+    ///
+    /// Query pool must not be used by pending commands.
+    pub unsafe fn dispose(self, device: &Device<B>) {
+        self.assert_device_owner(device);
+        device.destroy_query_pool(self.raw);
+        self.relevant.dispose();
+    }
+
+    /// Get reference to raw query pool.
+    pub fn raw(&self) -> &B::QueryPool {
+        &self.raw
+    }
+
+    /// Get type of queries in the pool.
+    pub fn ty(&self) -> Q {
+        self.ty
+    }
+
+    /// Get number of queries in the pool.
+    pub fn count(&self) -> gfx_hal::query::Id {
+        self.count
+    }
+
+    /// Get query with specified index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is out of bounds.
+    pub fn query(&self, id: gfx_hal::query::Id) -> gfx_hal::query::Query<'_, B> {
+        assert!(id < self.count, "Query index is out of bounds");
+        gfx_hal::query::Query {
+            pool: &self.raw,
+            id,
+        }
+    }
+
+    /// Read results of the range of queries into `data`.
+    /// `data` must hold `result_len` values for each query in range.
+    /// Returns `false` if results are not available yet and `wait` is `false`.
+    ///
+    /// # Safety
+    ///
+    /// Queries in range must be written by submitted commands.
+    /// Otherwise this function will never return if `wait` is `true`.
+    pub unsafe fn results(
+        &self,
+        device: &Device<B>,
+        queries: std::ops::Range<gfx_hal::query::Id>,
+        data: &mut [u64],
+        wait: bool,
+    ) -> Result<bool, gfx_hal::device::OomOrDeviceLost> {
+        self.assert_device_owner(device);
+        assert!(queries.end <= self.count, "Query range is out of bounds");
+
+        let len = self.ty.result_len();
+        assert_eq!(
+            data.len(),
+            (queries.end - queries.start) as usize * len,
+            "Query results don't fit"
+        );
+
+        let mut flags = gfx_hal::query::ResultFlags::BITS_64;
+        if wait {
+            flags |= gfx_hal::query::ResultFlags::WAIT;
+        }
+
+        device.get_query_pool_results(
+            &self.raw,
+            queries,
+            std::slice::from_raw_parts_mut(data.as_mut_ptr() as *mut u8, data.len() * 8),
+            len as u64 * 8,
+            flags,
+        )
+    }
+}
@@ -2,11 +2,12 @@ use {
     crate::{
         descriptor,
         escape::Handle,
-        util::{device_owned, Device, DeviceId},
+        util::{device_owned, types::hash_bindings, Device, DeviceId},
     },
     gfx_hal::{device::Device as _, pso::DescriptorSetLayoutBinding, Backend},
     relevant::Relevant,
     smallvec::SmallVec,
+    std::hash::{Hash, Hasher},
 };
 
 /// Descriptor set layout info.
@@ -16,6 +17,12 @@ pub struct DescriptorSetInfo {
     pub bindings: Vec<DescriptorSetLayoutBinding>,
 }
 
+impl Hash for DescriptorSetInfo {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_bindings(&self.bindings, state);
+    }
+}
+
 impl DescriptorSetInfo {
     /// Get descriptor ranges of the layout.
     pub fn ranges(&self) -> descriptor::DescriptorRanges {
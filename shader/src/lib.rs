@@ -14,27 +14,58 @@
 #[cfg(feature = "shader-compiler")]
 mod shaderc;
 
+#[cfg(feature = "shader-compiler")]
+mod permutation;
+
+#[cfg(feature = "shader-compiler")]
+mod precompile;
+
 #[cfg(feature = "spirv-reflection")]
 #[allow(dead_code)]
 mod reflect;
 
+mod reload;
+
 #[cfg(feature = "shader-compiler")]
-pub use self::shaderc::*;
+pub use self::{
+    permutation::{PermutationKeys, ShaderPermutations},
+    precompile::ShaderPrecompiler,
+    shaderc::*,
+};
 
 #[cfg(feature = "spirv-reflection")]
-pub use self::reflect::{ReflectError, ReflectTypeError, RetrievalKind, SpirvReflection};
+pub use self::reflect::{
+    BlockKind, BlockLayout, BlockMember, BlockMemberType, ReflectError, ReflectTypeError,
+    RetrievalKind, SpecConstant, SpecConstantSetBuilder, SpecConstantType, SpecConstantValue,
+    SpirvReflection,
+};
+
+pub use self::reload::{ReloadError, ReloadableShaderSet, ShaderWatcher};
 
 use gfx_hal::{pso::ShaderStageFlags, Backend};
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    hash::{Hash, Hasher},
+};
 
 /// Error type returned by this module.
 #[derive(Copy, Clone, Debug)]
-pub enum ShaderError {}
+pub enum ShaderError {
+    /// Shader set doesn't contain shader for the required stage.
+    MissingStage(ShaderStageFlags),
+    /// Shader module for the stage is not loaded.
+    NotLoaded(ShaderStageFlags),
+}
 
 impl std::error::Error for ShaderError {}
 impl std::fmt::Display for ShaderError {
-    fn fmt(&self, _: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match *self {}
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            ShaderError::MissingStage(stage) => {
+                write!(f, "ShaderSet doesn't contain {:?} shader", stage)
+            }
+            ShaderError::NotLoaded(stage) => write!(f, "{:?} shader module is not loaded", stage),
+        }
     }
 }
 
@@ -127,6 +158,22 @@ impl SpirvShader {
             entrypoint,
         ))
     }
+
+    /// Create Spir-V shader from bytecode embedded by code generated with `ShaderPrecompiler`.
+    /// Stage is passed as raw `ShaderStageFlags` bits so that generated code
+    /// doesn't depend on `gfx-hal`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if bytecode length is not a multiple of 4.
+    pub fn from_precompiled(spirv: &[u8], stage: u32, entrypoint: &str) -> Self {
+        Self::from_bytes(
+            spirv,
+            ShaderStageFlags::from_bits_truncate(stage),
+            entrypoint,
+        )
+        .expect("Precompiled shader bytecode is valid")
+    }
 }
 
 impl Shader for SpirvShader {
@@ -166,14 +213,15 @@ impl<B: Backend> ShaderSet<B> {
     }
 
     /// Returns the `GraphicsShaderSet` structure to provide all the runtime information needed to use the shaders in this set in gfx_hal.
+    /// Fails with `ShaderError::MissingStage` if the set doesn't contain vertex shader.
     pub fn raw<'a>(&'a self) -> Result<(gfx_hal::pso::GraphicsShaderSet<'a, B>), ShaderError> {
         Ok(gfx_hal::pso::GraphicsShaderSet {
             vertex: self
                 .shaders
                 .get(&ShaderStageFlags::VERTEX)
-                .expect("ShaderSet doesn't contain vertex shader")
+                .ok_or(ShaderError::MissingStage(ShaderStageFlags::VERTEX))?
                 .get_entry_point()?
-                .unwrap(),
+                .ok_or(ShaderError::MissingStage(ShaderStageFlags::VERTEX))?,
             fragment: match self.shaders.get(&ShaderStageFlags::FRAGMENT) {
                 Some(fragment) => fragment.get_entry_point()?,
                 None => None,
@@ -193,6 +241,16 @@ impl<B: Backend> ShaderSet<B> {
         })
     }
 
+    /// Returns the compute `EntryPoint` to provide the runtime information needed to use the compute shader in this set in gfx_hal.
+    /// Fails with `ShaderError::MissingStage` if the set doesn't contain compute shader.
+    pub fn raw_compute<'a>(&'a self) -> Result<gfx_hal::pso::EntryPoint<'a, B>, ShaderError> {
+        self.shaders
+            .get(&ShaderStageFlags::COMPUTE)
+            .ok_or(ShaderError::MissingStage(ShaderStageFlags::COMPUTE))?
+            .get_entry_point()?
+            .ok_or(ShaderError::MissingStage(ShaderStageFlags::COMPUTE))
+    }
+
     /// Must be called to perform a drop of the Backend ShaderModule object otherwise the shader will never be destroyed in memory.
     pub fn dispose(&mut self, factory: &rendy_factory::Factory<B>) {
         for (_, shader) in self.shaders.iter_mut() {
@@ -201,7 +259,35 @@ impl<B: Backend> ShaderSet<B> {
     }
 }
 
+/// Hashes stages with their SPIR-V code, entry points and specialization.
+/// Shader sets with equal hashes produce equal pipelines.
+impl<B: Backend> Hash for ShaderSet<B> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut shaders: Vec<_> = self.shaders.values().collect();
+        shaders.sort_by_key(|shader| shader.stage.bits());
+
+        for shader in shaders {
+            shader.stage.bits().hash(state);
+            shader.spirv.hash(state);
+            shader.entrypoint.hash(state);
+            match &shader.specialization {
+                None => 0u8.hash(state),
+                Some(specialization) => {
+                    1u8.hash(state);
+                    specialization.constants.len().hash(state);
+                    for constant in specialization.constants.iter() {
+                        constant.id.hash(state);
+                        constant.range.hash(state);
+                    }
+                    specialization.data.hash(state);
+                }
+            }
+        }
+    }
+}
+
 /// A set of Specialization constants for a certain shader set.
+/// With `spirv-reflection` feature it can be built from reflected constants with `SpecConstantSetBuilder`.
 #[derive(Debug, Default, Clone)]
 #[allow(missing_copy_implementations)]
 pub struct SpecConstantSet {
@@ -344,6 +430,27 @@ impl ShaderSetBuilder {
         Ok(set)
     }
 
+    /// Add Spir-V shader to this shader set at the shader's stage.
+    /// Fails if the stage is not a single graphics or compute stage.
+    pub(crate) fn with_spirv(mut self, shader: &SpirvShader) -> Result<Self, ReloadError> {
+        let data = Some((shader.spirv.clone(), shader.entry.clone()));
+        match shader.stage {
+            ShaderStageFlags::VERTEX => self.vertex = data,
+            ShaderStageFlags::FRAGMENT => self.fragment = data,
+            ShaderStageFlags::GEOMETRY => self.geometry = data,
+            ShaderStageFlags::HULL => self.hull = data,
+            ShaderStageFlags::DOMAIN => self.domain = data,
+            ShaderStageFlags::COMPUTE => self.compute = data,
+            stage => {
+                return Err(ReloadError {
+                    stage,
+                    message: "unsupported shader stage".to_string(),
+                })
+            }
+        }
+        Ok(self)
+    }
+
     /// Add a vertex shader to this shader set
     #[inline(always)]
     pub fn with_vertex<S: Shader>(mut self, shader: &S) -> Result<Self, S::Error> {
@@ -442,7 +549,10 @@ impl<B: Backend> ShaderStorage<B> {
     ) -> Result<Option<gfx_hal::pso::EntryPoint<'a, B>>, ShaderError> {
         Ok(Some(gfx_hal::pso::EntryPoint {
             entry: &self.entrypoint,
-            module: self.module.as_ref().unwrap(),
+            module: self
+                .module
+                .as_ref()
+                .ok_or(ShaderError::NotLoaded(self.stage))?,
             specialization: self
                 .specialization
                 .clone()
@@ -0,0 +1,462 @@
+// This module is gated under "shader-compiler" feature
+use {
+    crate::{
+        shaderc::{CompileOptions, ShaderCError, ShaderKind, SourceCodeShaderInfo, SourceLanguage},
+        SpirvShader,
+    },
+    std::{
+        collections::{BTreeMap, HashMap, HashSet},
+        hash::{Hash, Hasher},
+        path::{Path, PathBuf},
+    },
+};
+
+/// Preprocessor keys selecting shader permutation.
+/// Boolean keys are defined without value when set and not defined otherwise.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PermutationKeys {
+    defines: BTreeMap<String, Option<String>>,
+}
+
+impl PermutationKeys {
+    /// Empty key set that selects base permutation.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set boolean key.
+    pub fn with_flag(mut self, name: impl Into<String>, enabled: bool) -> Self {
+        self.set_flag(name, enabled);
+        self
+    }
+
+    /// Set boolean key.
+    pub fn set_flag(&mut self, name: impl Into<String>, enabled: bool) {
+        let name = name.into();
+        if enabled {
+            self.defines.insert(name, None);
+        } else {
+            self.defines.remove(&name);
+        }
+    }
+
+    /// Set valued key.
+    pub fn with_value(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.set_value(name, value);
+        self
+    }
+
+    /// Set valued key.
+    pub fn set_value(&mut self, name: impl Into<String>, value: impl ToString) {
+        self.defines.insert(name.into(), Some(value.to_string()));
+    }
+
+    /// Iterate over preprocessor definitions of the keys.
+    pub fn defines(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.defines
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_ref().map(String::as_str)))
+    }
+}
+
+/// Shader source compiled into permutations on demand.
+///
+/// Compiled permutations are memoized per key set.
+/// With cache directory set, Spir-V bytecode of compiled permutations is stored on disk
+/// keyed by hash of the source, included files, compile options, keys and compiler,
+/// so warm starts load shaders without compiling them.
+#[derive(Clone, Debug)]
+pub struct ShaderPermutations {
+    source: String,
+    path: PathBuf,
+    kind: ShaderKind,
+    lang: SourceLanguage,
+    entry: String,
+    options: CompileOptions,
+    cache_dir: Option<PathBuf>,
+    compiled: HashMap<PermutationKeys, SpirvShader>,
+}
+
+impl ShaderPermutations {
+    /// Create permutations of the shader source.
+    /// `path` is used to resolve relative includes and in error messages.
+    pub fn new(
+        source: impl Into<String>,
+        path: impl Into<PathBuf>,
+        kind: ShaderKind,
+        lang: SourceLanguage,
+        entry: impl Into<String>,
+    ) -> Self {
+        ShaderPermutations {
+            source: source.into(),
+            path: path.into(),
+            kind,
+            lang,
+            entry: entry.into(),
+            options: CompileOptions::default(),
+            cache_dir: None,
+            compiled: HashMap::new(),
+        }
+    }
+
+    /// Create permutations of the shader source loaded from file.
+    pub fn from_file(
+        path: impl Into<PathBuf>,
+        kind: ShaderKind,
+        lang: SourceLanguage,
+        entry: impl Into<String>,
+    ) -> std::io::Result<Self> {
+        let path = path.into();
+        let source = std::fs::read_to_string(&path)?;
+        Ok(Self::new(source, path, kind, lang, entry))
+    }
+
+    /// Set base options to compile permutations with.
+    /// Permutation keys are added to the defines of the options.
+    pub fn with_options(mut self, options: CompileOptions) -> Self {
+        self.options = options;
+        self.compiled.clear();
+        self
+    }
+
+    /// Set directory to store compiled permutations in.
+    pub fn with_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Get permutation for specified keys.
+    /// Permutation is loaded from the disk cache or compiled if not yet available.
+    pub fn get(&mut self, keys: &PermutationKeys) -> Result<&SpirvShader, ShaderCError> {
+        if !self.compiled.contains_key(keys) {
+            let shader = self.load_or_compile(keys)?;
+            self.compiled.insert(keys.clone(), shader);
+        }
+        Ok(&self.compiled[keys])
+    }
+
+    /// Forget memoized permutations.
+    /// Disk cache is kept.
+    pub fn clear(&mut self) {
+        self.compiled.clear();
+    }
+
+    fn load_or_compile(&self, keys: &PermutationKeys) -> Result<SpirvShader, ShaderCError> {
+        let stage = crate::shaderc::stage_from_kind(&self.kind);
+
+        let cache_file = self
+            .cache_dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{:016x}.spv", self.hash(keys))));
+
+        if let Some(cache_file) = &cache_file {
+            if let Ok(bytes) = std::fs::read(cache_file) {
+                match SpirvShader::from_bytes(&bytes, stage, &self.entry) {
+                    Ok(shader) => {
+                        log::trace!("Load shader permutation from {:?}", cache_file);
+                        return Ok(shader);
+                    }
+                    Err(e) => log::warn!("Invalid cached shader {:?}: {}", cache_file, e),
+                }
+            }
+        }
+
+        let mut options = self.options.clone();
+        for (name, value) in keys.defines() {
+            options.add_define(name, value);
+        }
+
+        log::trace!("Compile shader {:?} permutation {:?}", self.path, keys);
+        let shader =
+            SourceCodeShaderInfo::new(&self.source, &self.path, self.kind, self.lang, &self.entry)
+                .with_options(options)
+                .precompile()?;
+
+        if let Some(cache_file) = &cache_file {
+            if let Err(e) = store(cache_file, &shader) {
+                log::warn!("Failed to cache shader in {:?}: {}", cache_file, e);
+            }
+        }
+
+        Ok(shader)
+    }
+
+    /// Hash of everything that affects compiled permutation.
+    /// Uses FNV-1a with platform independent integer encoding,
+    /// so keys don't change between runs and processes.
+    /// Builds with another Rust version may hash differently, which only causes recompilation.
+    fn hash(&self, keys: &PermutationKeys) -> u64 {
+        let mut hasher = FnvHasher::default();
+        self.source.hash(&mut hasher);
+        self.entry.hash(&mut hasher);
+        (self.kind as u32).hash(&mut hasher);
+        (self.lang as u32).hash(&mut hasher);
+        self.options.hash_into(&mut hasher);
+        keys.hash(&mut hasher);
+
+        // Defines and includes may be passed to shaderc differently by another version of this crate.
+        env!("CARGO_PKG_VERSION").hash(&mut hasher);
+        compiler_id().hash(&mut hasher);
+
+        let mut visited = HashSet::new();
+        self.hash_includes(&self.source, &self.path, &mut visited, &mut hasher);
+
+        hasher.finish()
+    }
+
+    /// Hash content of files included by the source, recursively.
+    /// Includes are collected regardless of preprocessor conditions.
+    /// Includes that can't be resolved are hashed by name only.
+    fn hash_includes(
+        &self,
+        source: &str,
+        path: &Path,
+        visited: &mut HashSet<PathBuf>,
+        hasher: &mut FnvHasher,
+    ) {
+        for line in source.lines() {
+            let line = line.trim_start();
+            if !line.starts_with('#') || !line[1..].trim_start().starts_with("include") {
+                continue;
+            }
+            let rest = line[1..].trim_start()["include".len()..].trim();
+
+            let (requested, include_type) = match rest.chars().next() {
+                Some('"') => (rest[1..].split('"').next(), shaderc::IncludeType::Relative),
+                Some('<') => (rest[1..].split('>').next(), shaderc::IncludeType::Standard),
+                _ => continue,
+            };
+            let requested = match requested {
+                Some(requested) => requested,
+                None => continue,
+            };
+
+            requested.hash(hasher);
+            let resolved =
+                self.options
+                    .resolve_include(requested, include_type, &path.to_string_lossy());
+            if let Ok(resolved) = resolved {
+                let resolved_path = PathBuf::from(&resolved.resolved_name);
+                if visited.insert(resolved_path.clone()) {
+                    resolved.content.hash(hasher);
+                    self.hash_includes(&resolved.content, &resolved_path, visited, hasher);
+                }
+            }
+        }
+    }
+}
+
+/// Hash of bytecode compiled from fixed probe shader.
+///
+/// shaderc doesn't report its version, while it changes with system library
+/// or shaderc build. Probe bytecode changes with the compiler as Spir-V header
+/// records generator version and code generation differences show up in the output.
+/// Probe is compiled once per thread.
+fn compiler_id() -> Option<u64> {
+    thread_local! {
+        static COMPILER_ID: std::cell::Cell<Option<Option<u64>>> = std::cell::Cell::new(None);
+    }
+
+    COMPILER_ID.with(|id| {
+        if id.get().is_none() {
+            let probe = SourceCodeShaderInfo::new(
+                "#version 450\nlayout(location = 0) out vec4 color;\nvoid main() { color = vec4(1.0); }\n",
+                "probe.frag",
+                ShaderKind::Fragment,
+                SourceLanguage::GLSL,
+                "main",
+            )
+            .precompile();
+
+            id.set(Some(match probe {
+                Ok(probe) => {
+                    let mut hasher = FnvHasher::default();
+                    probe.spirv.hash(&mut hasher);
+                    Some(hasher.finish())
+                }
+                Err(e) => {
+                    log::warn!("Failed to compile probe shader: {}", e);
+                    None
+                }
+            }));
+        }
+        id.get().unwrap()
+    })
+}
+
+fn store(cache_file: &Path, shader: &SpirvShader) -> std::io::Result<()> {
+    if let Some(dir) = cache_file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let spirv = shader
+        .spirv
+        .iter()
+        .flat_map(|word| word.to_le_bytes().to_vec());
+    let bytes: Vec<u8> = spirv.collect();
+
+    // Write to temporary file first so that readers never see partially written shader.
+    let temp = cache_file.with_extension("spv.tmp");
+    std::fs::write(&temp, bytes)?;
+    std::fs::rename(&temp, cache_file)
+}
+
+/// 64-bit FNV-1a hasher.
+#[derive(Clone, Copy, Debug)]
+struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        FnvHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    // Integers are hashed as little-endian and lengths as 64-bit
+    // so that hash doesn't depend on the platform.
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn permutations(dir: &Path, source: &str) -> ShaderPermutations {
+        ShaderPermutations::new(
+            source,
+            dir.join("shader.frag"),
+            ShaderKind::Fragment,
+            SourceLanguage::GLSL,
+            "main",
+        )
+    }
+
+    #[test]
+    fn test_hash_includes() {
+        let dir = std::env::temp_dir().join(format!("rendy-permutation-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("nested.glsl"), "float nested;").unwrap();
+        std::fs::write(dir.join("common.glsl"), "#include \"nested.glsl\"").unwrap();
+
+        let source = "#version 450\n#include \"common.glsl\"\nvoid main() {}\n";
+        let keys = PermutationKeys::new();
+        let hash = permutations(&dir, source).hash(&keys);
+        assert_eq!(hash, permutations(&dir, source).hash(&keys));
+
+        // Change of nested include content must change the hash.
+        std::fs::write(dir.join("nested.glsl"), "float changed;").unwrap();
+        let changed = permutations(&dir, source).hash(&keys);
+
+        // Include cycles must not recurse forever.
+        std::fs::write(dir.join("nested.glsl"), "#include \"common.glsl\"").unwrap();
+        let cycle = permutations(&dir, source).hash(&keys);
+
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_ne!(hash, changed);
+        assert_ne!(changed, cycle);
+    }
+
+    #[test]
+    fn test_hash_platform_independent() {
+        let mut hasher = FnvHasher::default();
+        hasher.write_usize(1);
+        let mut expected = FnvHasher::default();
+        expected.write(&[1, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(hasher.finish(), expected.finish());
+    }
+
+    #[test]
+    fn test_disk_cache_round_trip() {
+        let dir =
+            std::env::temp_dir().join(format!("rendy-permutation-cache-{}", std::process::id()));
+        let source = "#version 450\nvoid main() {}\n";
+        let keys = PermutationKeys::new().with_flag("FLAG", true);
+
+        let compiled = permutations(&dir, source)
+            .with_cache_dir(&dir)
+            .get(&keys)
+            .unwrap()
+            .clone();
+        let cache_file = dir.join(format!(
+            "{:016x}.spv",
+            permutations(&dir, source).hash(&keys)
+        ));
+        let stored = std::fs::read(&cache_file).unwrap();
+
+        // Replace cached bytecode with another shader,
+        // so that loading it proves the source was not compiled again.
+        let other = permutations(
+            &dir,
+            "#version 450\nlayout(location = 0) out vec4 color;\nvoid main() { color = vec4(1.0); }\n",
+        )
+        .get(&keys)
+        .unwrap()
+        .clone();
+        store(&cache_file, &other).unwrap();
+
+        let loaded = permutations(&dir, source)
+            .with_cache_dir(&dir)
+            .get(&keys)
+            .unwrap()
+            .clone();
+
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let stage = crate::shaderc::stage_from_kind(&ShaderKind::Fragment);
+        assert_eq!(
+            SpirvShader::from_bytes(&stored, stage, "main").unwrap(),
+            compiled
+        );
+        assert_ne!(compiled, other);
+        assert_eq!(loaded, other);
+    }
+
+    #[test]
+    fn test_hash_keys_and_options() {
+        let dir = std::env::temp_dir();
+        let source = "#version 450\nvoid main() {}\n";
+        let keys = PermutationKeys::new();
+        let hash = permutations(&dir, source).hash(&keys);
+
+        let flagged = PermutationKeys::new().with_flag("FLAG", true);
+        assert_ne!(hash, permutations(&dir, source).hash(&flagged));
+
+        let valued = PermutationKeys::new().with_value("FLAG", 1);
+        assert_ne!(
+            permutations(&dir, source).hash(&flagged),
+            permutations(&dir, source).hash(&valued)
+        );
+
+        let options = CompileOptions::new().with_define("FLAG", None);
+        assert_ne!(
+            hash,
+            permutations(&dir, source).with_options(options).hash(&keys)
+        );
+    }
+}
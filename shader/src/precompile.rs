@@ -0,0 +1,282 @@
+// This module is gated under "shader-compiler" feature
+use {
+    crate::shaderc::{
+        stage_from_kind, CompileOptions, FileShaderInfo, ShaderCError, ShaderKind, SourceLanguage,
+    },
+    std::{
+        collections::{BTreeSet, HashMap},
+        fmt::Write as _,
+        path::{Path, PathBuf},
+    },
+};
+
+/// Compiles shader sources at build time.
+///
+/// Intended to be used from `build.rs`.
+/// Writes Spir-V bytecode of every shader into output directory
+/// and generates Rust module with a function returning `SpirvShader` for each shader,
+/// so runtime code doesn't need shaderc.
+///
+/// Precompiler requires `shader-compiler` feature.
+/// With the default feature resolver Cargo merges features of build and normal dependencies,
+/// so shaderc would be linked into the runtime as well.
+/// To keep it build-only, enable the feature only for build dependency
+/// and opt into the version 2 resolver (Rust 1.51+):
+///
+/// ```toml
+/// [package]
+/// resolver = "2"
+///
+/// [dependencies]
+/// rendy-shader = "0.4"
+///
+/// [build-dependencies]
+/// rendy-shader = { version = "0.4", features = ["shader-compiler"] }
+/// ```
+///
+/// In a workspace `resolver = "2"` goes into the `[workspace]` section of the root manifest.
+///
+/// ```ignore
+/// // build.rs
+/// let module = rendy_shader::ShaderPrecompiler::from_env()
+///     .with_dir("shaders")
+///     .compile()
+///     .unwrap();
+///
+/// // main.rs
+/// mod shaders {
+///     include!(concat!(env!("OUT_DIR"), "/shaders.rs"));
+/// }
+/// let vertex = shaders::triangle_vert();
+/// ```
+#[derive(Clone, Debug)]
+pub struct ShaderPrecompiler {
+    out_dir: PathBuf,
+    module: String,
+    crate_path: String,
+    options: CompileOptions,
+    dirs: Vec<PathBuf>,
+    shaders: Vec<(PathBuf, ShaderKind, SourceLanguage, String)>,
+}
+
+impl ShaderPrecompiler {
+    /// Create precompiler that writes into specified directory.
+    pub fn new(out_dir: impl Into<PathBuf>) -> Self {
+        ShaderPrecompiler {
+            out_dir: out_dir.into(),
+            module: "shaders".to_owned(),
+            crate_path: "rendy_shader".to_owned(),
+            options: CompileOptions::default(),
+            dirs: Vec::new(),
+            shaders: Vec::new(),
+        }
+    }
+
+    /// Create precompiler that writes into `OUT_DIR` of the build script.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `OUT_DIR` environment variable is not set.
+    pub fn from_env() -> Self {
+        Self::new(std::env::var_os("OUT_DIR").expect("OUT_DIR is set for build scripts"))
+    }
+
+    /// Set name of the generated module file and Spir-V subdirectory.
+    /// Defaults to `shaders`.
+    pub fn with_module_name(mut self, name: impl Into<String>) -> Self {
+        self.module = name.into();
+        self
+    }
+
+    /// Set path to the `rendy-shader` crate used in generated code.
+    /// Defaults to `rendy_shader`. Use `rendy::shader` when depending on `rendy` crate.
+    pub fn with_crate_path(mut self, path: impl Into<String>) -> Self {
+        self.crate_path = path.into();
+        self
+    }
+
+    /// Set options to compile shaders with.
+    pub fn with_options(mut self, options: CompileOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Compile all shaders in the directory and its subdirectories.
+    /// Kind of the shader is derived from the file extension:
+    /// `vert`, `frag`, `geom`, `tesc`, `tese` or `comp`.
+    /// Files with additional `hlsl` extension, like `sprite.frag.hlsl`, are compiled as HLSL.
+    /// Entry point is `main`. Other files are ignored.
+    pub fn with_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.add_dir(dir);
+        self
+    }
+
+    /// Compile all shaders in the directory and its subdirectories.
+    /// See [`with_dir`](#method.with_dir).
+    pub fn add_dir(&mut self, dir: impl Into<PathBuf>) {
+        self.dirs.push(dir.into());
+    }
+
+    /// Compile the shader.
+    pub fn with_shader(
+        mut self,
+        path: impl Into<PathBuf>,
+        kind: ShaderKind,
+        lang: SourceLanguage,
+        entry: impl Into<String>,
+    ) -> Self {
+        self.add_shader(path, kind, lang, entry);
+        self
+    }
+
+    /// Compile the shader.
+    pub fn add_shader(
+        &mut self,
+        path: impl Into<PathBuf>,
+        kind: ShaderKind,
+        lang: SourceLanguage,
+        entry: impl Into<String>,
+    ) {
+        self.shaders.push((path.into(), kind, lang, entry.into()));
+    }
+
+    /// Compile shaders and generate module.
+    /// Prints `cargo:rerun-if-changed` for every source, directory and file included by shaders.
+    /// Returns path to the generated module.
+    ///
+    /// Fails if two shaders map to the same function name.
+    pub fn compile(&self) -> Result<PathBuf, ShaderCError> {
+        let mut shaders = self.shaders.clone();
+        for dir in &self.dirs {
+            println!("cargo:rerun-if-changed={}", dir.display());
+            collect_dir(dir, &mut shaders)?;
+        }
+
+        let spirv_dir = self.out_dir.join(&self.module);
+        std::fs::create_dir_all(&spirv_dir)?;
+
+        let mut module = String::new();
+        writeln!(
+            module,
+            "// This file is generated by `rendy_shader::ShaderPrecompiler`. Do not edit."
+        )
+        .unwrap();
+
+        let mut names = HashMap::new();
+        let mut included = BTreeSet::new();
+        for (path, kind, lang, entry) in shaders {
+            println!("cargo:rerun-if-changed={}", path.display());
+
+            let name = function_name(&path, &self.dirs);
+            if let Some(first) = names.get(&name) {
+                return Err(ShaderCError::DuplicateFunctionName {
+                    name,
+                    first: first.clone(),
+                    second: path,
+                });
+            }
+
+            let (spirv, includes) = FileShaderInfo::new(&path, kind, lang, entry.as_str())
+                .with_options(self.options.clone())
+                .spirv_with_includes()?;
+            included.extend(includes);
+
+            let spirv_file = spirv_dir.join(format!("{}.spv", name));
+            let bytes: Vec<u8> = spirv
+                .iter()
+                .flat_map(|word| word.to_le_bytes().to_vec())
+                .collect();
+            std::fs::write(&spirv_file, bytes)?;
+
+            writeln!(
+                module,
+                "\n/// `{}` compiled to Spir-V.\n\
+                 pub fn {}() -> {}::SpirvShader {{\n    \
+                 {}::SpirvShader::from_precompiled(include_bytes!({:?}), {}, {:?})\n\
+                 }}",
+                path.display(),
+                name,
+                self.crate_path,
+                self.crate_path,
+                spirv_file,
+                stage_from_kind(&kind).bits(),
+                entry,
+            )
+            .unwrap();
+
+            names.insert(name, path);
+        }
+
+        for path in included {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+
+        let module_file = self.out_dir.join(format!("{}.rs", self.module));
+        std::fs::write(&module_file, module)?;
+        Ok(module_file)
+    }
+}
+
+fn collect_dir(
+    dir: &Path,
+    shaders: &mut Vec<(PathBuf, ShaderKind, SourceLanguage, String)>,
+) -> Result<(), ShaderCError> {
+    let mut entries = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            collect_dir(&path, shaders)?;
+            continue;
+        }
+
+        let (stem, lang) = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("hlsl") => (path.file_stem().map(Path::new), SourceLanguage::HLSL),
+            _ => (Some(path.as_path()), SourceLanguage::GLSL),
+        };
+
+        let kind = match stem.and_then(Path::extension).and_then(|ext| ext.to_str()) {
+            Some("vert") => ShaderKind::Vertex,
+            Some("frag") => ShaderKind::Fragment,
+            Some("geom") => ShaderKind::Geometry,
+            Some("tesc") => ShaderKind::TessControl,
+            Some("tese") => ShaderKind::TessEvaluation,
+            Some("comp") => ShaderKind::Compute,
+            _ => continue,
+        };
+
+        shaders.push((path, kind, lang, "main".to_owned()));
+    }
+
+    Ok(())
+}
+
+/// Make function name from the shader path relative to the directory it was found in.
+/// `shaders/sprite/quad.vert` becomes `sprite_quad_vert`.
+fn function_name(path: &Path, dirs: &[PathBuf]) -> String {
+    let relative = dirs
+        .iter()
+        .filter_map(|dir| path.strip_prefix(dir).ok())
+        .next()
+        .or_else(|| path.file_name().map(Path::new))
+        .unwrap_or(path);
+
+    let mut name: String = relative
+        .to_string_lossy()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    if name.chars().next().map_or(true, |c| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    name
+}
@@ -0,0 +1,360 @@
+//! Memory layout of uniform, storage and push constant blocks.
+//!
+
+use super::{types::numeric_format, ReflectError};
+use gfx_hal::format::Format;
+use spirv_reflect::types::*;
+
+/// Kind of the block and where it is bound.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BlockKind {
+    /// Uniform buffer block. Laid out according to std140 rules.
+    Uniform {
+        /// Descriptor set index.
+        set: u32,
+        /// Binding index within the set.
+        binding: u32,
+    },
+    /// Storage buffer block. Usually laid out according to std430 rules.
+    Storage {
+        /// Descriptor set index.
+        set: u32,
+        /// Binding index within the set.
+        binding: u32,
+    },
+    /// Push constant block.
+    PushConstant,
+}
+
+/// Type of the block member.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BlockMemberType {
+    /// Scalar or vector.
+    Numeric(Format),
+    /// Matrix.
+    Matrix {
+        /// Format of a single column (or row for row-major matrices).
+        vector: Format,
+        /// Number of columns.
+        columns: u32,
+        /// Number of rows.
+        rows: u32,
+        /// Offset between columns (or rows for row-major matrices) in bytes.
+        stride: u32,
+        /// Matrix is stored row by row.
+        row_major: bool,
+    },
+    /// Nested structure.
+    Struct(Vec<BlockMember>),
+}
+
+/// Member of the block as laid out in memory.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockMember {
+    /// Member name.
+    pub name: String,
+    /// Member type.
+    pub ty: BlockMemberType,
+    /// Offset in bytes from the start of the enclosing structure.
+    pub offset: u32,
+    /// Size of the member in bytes.
+    /// Zero for runtime arrays.
+    pub size: u32,
+    /// Array dimensions. Empty if member is not an array.
+    pub array_dims: Vec<u32>,
+    /// Offset between array elements in bytes.
+    pub array_stride: u32,
+}
+
+/// Memory layout of the uniform, storage or push constant block.
+///
+/// See: https://www.khronos.org/registry/vulkan/specs/1.1-extensions/html/vkspec.html#interfaces-resources-layout
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockLayout {
+    /// Name of the block variable.
+    pub name: String,
+    /// Name of the block type.
+    pub type_name: String,
+    /// Kind of the block.
+    pub kind: BlockKind,
+    /// Size of the block in bytes.
+    pub size: u32,
+    /// Size of the block in bytes padded to its alignment.
+    pub padded_size: u32,
+    /// Members of the block.
+    pub members: Vec<BlockMember>,
+}
+
+impl BlockLayout {
+    /// Find member by name.
+    pub fn member(&self, name: &str) -> Option<&BlockMember> {
+        self.members.iter().find(|member| member.name == name)
+    }
+
+    /// Check that Rust type `T` matches the layout of the block.
+    ///
+    /// Size of `T` must be equal to the size or padded size of the block,
+    /// and every field in `fields` must be at the same offset as the block member with that name.
+    /// Block members not listed in `fields` aren't checked.
+    /// Field offsets can be collected with [`block_fields!`](macro.block_fields.html).
+    pub fn validate<T>(&self, fields: &[(&str, usize)]) -> Result<(), ReflectError> {
+        let size = std::mem::size_of::<T>();
+        if size != self.size as usize && size != self.padded_size as usize {
+            return Err(ReflectError::BlockSizeMismatch(
+                self.name.clone(),
+                self.size,
+                size,
+            ));
+        }
+
+        for &(name, offset) in fields {
+            let member = self.member(name).ok_or_else(|| {
+                ReflectError::BlockMemberMissing(self.name.clone(), name.to_string())
+            })?;
+
+            if member.offset as usize != offset {
+                return Err(ReflectError::BlockMemberOffsetMismatch(
+                    self.name.clone(),
+                    name.to_string(),
+                    member.offset,
+                    offset,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Collect names and offsets of the fields of a value
+/// for [`BlockLayout::validate`](struct.BlockLayout.html#method.validate).
+///
+/// ```ignore
+/// let layout = reflection.block("globals").unwrap();
+/// layout.validate::<Globals>(&rendy_shader::block_fields!(Globals::default(), [proj, view, time]))?;
+/// ```
+#[macro_export]
+macro_rules! block_fields {
+    ($value:expr, [$($field:ident),* $(,)*]) => {{
+        let value = &$value;
+        let base = value as *const _ as usize;
+        [$((stringify!($field), &value.$field as *const _ as usize - base)),*]
+    }};
+}
+
+pub(crate) fn convert_block(
+    kind: BlockKind,
+    name: &str,
+    block: &ReflectBlockVariable,
+    type_description: Option<&ReflectTypeDescription>,
+) -> Result<BlockLayout, ReflectError> {
+    Ok(BlockLayout {
+        name: name.to_string(),
+        type_name: type_description
+            .map(|ty| ty.type_name.clone())
+            .unwrap_or_default(),
+        kind,
+        size: block.size,
+        padded_size: block.padded_size,
+        members: convert_members(&block.members)?,
+    })
+}
+
+fn convert_members(members: &[ReflectBlockVariable]) -> Result<Vec<BlockMember>, ReflectError> {
+    members
+        .iter()
+        .map(|member| {
+            Ok(BlockMember {
+                name: member.name.clone(),
+                ty: convert_member_type(member)?,
+                offset: member.offset,
+                size: member.size,
+                array_dims: member.array.dims.clone(),
+                array_stride: member.array.stride,
+            })
+        })
+        .collect()
+}
+
+fn convert_member_type(member: &ReflectBlockVariable) -> Result<BlockMemberType, ReflectError> {
+    let flags = member
+        .type_description
+        .as_ref()
+        .map_or(ReflectTypeFlags::UNDEFINED, |ty| ty.type_flags);
+
+    if flags.contains(ReflectTypeFlags::STRUCT) {
+        Ok(BlockMemberType::Struct(convert_members(&member.members)?))
+    } else if flags.contains(ReflectTypeFlags::MATRIX) {
+        let matrix = &member.numeric.matrix;
+        let row_major = member
+            .decoration_flags
+            .contains(ReflectDecorationFlags::ROW_MAJOR);
+        let components = if row_major {
+            matrix.column_count
+        } else {
+            matrix.row_count
+        };
+
+        Ok(BlockMemberType::Matrix {
+            vector: numeric_format(flags, &member.numeric, components)?,
+            columns: matrix.column_count,
+            rows: matrix.row_count,
+            stride: matrix.stride,
+            row_major,
+        })
+    } else {
+        Ok(BlockMemberType::Numeric(numeric_format(
+            flags,
+            &member.numeric,
+            member.numeric.vector.component_count,
+        )?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn member(
+        name: &str,
+        type_flags: ReflectTypeFlags,
+        numeric: ReflectNumericTraits,
+        offset: u32,
+        size: u32,
+    ) -> ReflectBlockVariable {
+        ReflectBlockVariable {
+            name: name.to_string(),
+            offset,
+            absolute_offset: offset,
+            size,
+            padded_size: size,
+            numeric,
+            type_description: Some(ReflectTypeDescription {
+                type_flags,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn numeric(width: u32, components: u32) -> ReflectNumericTraits {
+        ReflectNumericTraits {
+            scalar: ReflectNumericTraitsScalar {
+                width,
+                signedness: 0,
+            },
+            vector: ReflectNumericTraitsVector {
+                component_count: components,
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Block as reflected from
+    ///
+    /// ```glsl
+    /// layout(std140, set = 0, binding = 1) uniform Globals {
+    ///     vec3 position;
+    ///     mat4 transform;
+    ///     bool enabled;
+    ///     float weights[4];
+    /// } globals;
+    /// ```
+    #[test]
+    fn test_convert_std140_block() {
+        let mut transform = member(
+            "transform",
+            ReflectTypeFlags::FLOAT | ReflectTypeFlags::VECTOR | ReflectTypeFlags::MATRIX,
+            numeric(32, 4),
+            16,
+            64,
+        );
+        transform.numeric.matrix = ReflectNumericTraitsMatrix {
+            column_count: 4,
+            row_count: 4,
+            stride: 16,
+        };
+
+        let mut weights = member(
+            "weights",
+            ReflectTypeFlags::FLOAT | ReflectTypeFlags::ARRAY,
+            numeric(32, 0),
+            96,
+            64,
+        );
+        weights.array = ReflectArrayTraits {
+            dims: vec![4],
+            stride: 16,
+        };
+
+        let block = ReflectBlockVariable {
+            name: "globals".to_string(),
+            size: 160,
+            padded_size: 160,
+            members: vec![
+                member(
+                    "position",
+                    ReflectTypeFlags::FLOAT | ReflectTypeFlags::VECTOR,
+                    numeric(32, 3),
+                    0,
+                    12,
+                ),
+                transform,
+                member("enabled", ReflectTypeFlags::BOOL, numeric(0, 0), 80, 4),
+                weights,
+            ],
+            ..Default::default()
+        };
+
+        let layout = convert_block(
+            BlockKind::Uniform { set: 0, binding: 1 },
+            "globals",
+            &block,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(layout.size, 160);
+        assert_eq!(
+            layout.member("position").unwrap().ty,
+            BlockMemberType::Numeric(Format::Rgb32Sfloat)
+        );
+        assert_eq!(
+            layout.member("transform").unwrap().ty,
+            BlockMemberType::Matrix {
+                vector: Format::Rgba32Sfloat,
+                columns: 4,
+                rows: 4,
+                stride: 16,
+                row_major: false,
+            }
+        );
+
+        let enabled = layout.member("enabled").unwrap();
+        assert_eq!(enabled.ty, BlockMemberType::Numeric(Format::R32Uint));
+        assert_eq!(enabled.offset, 80);
+
+        let weights = layout.member("weights").unwrap();
+        assert_eq!(weights.ty, BlockMemberType::Numeric(Format::R32Sfloat));
+        assert_eq!(weights.array_dims, vec![4]);
+        assert_eq!(weights.array_stride, 16);
+
+        #[repr(C)]
+        #[derive(Default)]
+        struct Globals {
+            position: [f32; 3],
+            _pad: f32,
+            transform: [[f32; 4]; 4],
+            enabled: u32,
+            _pad2: [u32; 3],
+            weights: [[f32; 4]; 4],
+        }
+
+        layout
+            .validate::<Globals>(&crate::block_fields!(
+                Globals::default(),
+                [position, transform, enabled, weights]
+            ))
+            .unwrap();
+    }
+}
@@ -1,10 +1,14 @@
 use gfx_hal::pso::ShaderStageFlags;
 use rendy_util::types::{vertex::VertexFormat, Layout, SetLayout};
-use spirv_reflect::ShaderModule;
+use spirv_reflect::{types::ReflectDescriptorType, ShaderModule};
 use std::collections::HashMap;
 use std::ops::{Bound, Range, RangeBounds};
 
+mod block;
+mod spec;
 pub(crate) mod types;
+pub use block::{BlockKind, BlockLayout, BlockMember, BlockMemberType};
+pub use spec::{SpecConstant, SpecConstantSetBuilder, SpecConstantType, SpecConstantValue};
 pub use types::ReflectTypeError;
 use types::*;
 
@@ -19,6 +23,8 @@ pub enum RetrievalKind {
     DescriptorSets,
     /// Push constants.
     PushConstants,
+    /// Descriptor bindings.
+    DescriptorBindings,
 }
 
 impl RetrievalKind {
@@ -28,6 +34,7 @@ impl RetrievalKind {
             RetrievalKind::OutputAttrib => "output attributes",
             RetrievalKind::DescriptorSets => "descriptor sets",
             RetrievalKind::PushConstants => "push constants",
+            RetrievalKind::DescriptorBindings => "descriptor bindings",
         }
     }
 }
@@ -51,6 +58,28 @@ pub enum ReflectError {
     Type(ReflectTypeError),
     /// Neither a vertex nor a compute shader has been provided.
     NoVertComputeProvided,
+    /// Vertex shader input attribute with the given name and array index is not provided by any vertex format.
+    AttributeMissing(String, u8),
+    /// Vertex shader input attribute format differs from the vertex format attribute.
+    /// Contains attribute name, format in the shader and format in the vertex format.
+    AttributeFormatMismatch(String, gfx_hal::format::Format, gfx_hal::format::Format),
+    /// Vertex shader input attribute location doesn't follow locations of
+    /// the attributes from previous vertex buffers.
+    AttributeLocationMismatch(String, u32),
+    /// Size of the Rust type differs from the size of the block.
+    /// Contains block name, block size and size of the type.
+    BlockSizeMismatch(String, u32, usize),
+    /// Block has no member with the field name.
+    /// Contains block name and field name.
+    BlockMemberMissing(String, String),
+    /// Field offset differs from the block member offset.
+    /// Contains block name, member name, offset in the block and offset in the type.
+    BlockMemberOffsetMismatch(String, String, u32, usize),
+    /// Value type differs from the type of the specialization constant.
+    /// Contains constant name, constant type and value type.
+    SpecConstantTypeMismatch(String, SpecConstantType, SpecConstantType),
+    /// Specialization constant with the given id does not exist.
+    SpecConstantIdDoesNotExist(u32),
 }
 
 impl std::error::Error for ReflectError {}
@@ -81,6 +110,42 @@ impl std::fmt::Display for ReflectError {
             ReflectError::NoVertComputeProvided => {
                 write!(f, "a vertex or compute shader must be provided")
             }
+            ReflectError::AttributeMissing(name, index) => write!(
+                f,
+                "attribute {}[{}] is not provided by any vertex format",
+                name, index
+            ),
+            ReflectError::AttributeFormatMismatch(name, shader, vertex) => write!(
+                f,
+                "attribute {} has format {:?} in shader but {:?} in vertex format",
+                name, shader, vertex
+            ),
+            ReflectError::AttributeLocationMismatch(name, location) => write!(
+                f,
+                "attribute {} at location {} must follow attributes of previous vertex buffers",
+                name, location
+            ),
+            ReflectError::BlockSizeMismatch(block, expected, size) => write!(
+                f,
+                "block {} has size {} but type has size {}",
+                block, expected, size
+            ),
+            ReflectError::BlockMemberMissing(block, name) => {
+                write!(f, "block {} has no member {}", block, name)
+            }
+            ReflectError::BlockMemberOffsetMismatch(block, name, expected, offset) => write!(
+                f,
+                "member {} of block {} is at offset {} but field is at offset {}",
+                name, block, expected, offset
+            ),
+            ReflectError::SpecConstantTypeMismatch(name, expected, ty) => write!(
+                f,
+                "specialization constant {} has type {:?} but value has type {:?}",
+                name, expected, ty
+            ),
+            ReflectError::SpecConstantIdDoesNotExist(id) => {
+                write!(f, "specialization constant with id {} does not exist", id)
+            }
         }
     }
 }
@@ -110,6 +175,10 @@ pub struct SpirvReflection {
     pub stage_flag: ShaderStageFlags,
     /// Push Constants
     pub push_constants: Vec<(ShaderStageFlags, Range<u32>)>,
+    /// Memory layouts of uniform, storage and push constant blocks
+    pub blocks: Vec<BlockLayout>,
+    /// Specialization constants with stages they are declared in
+    pub spec_constants: Vec<(ShaderStageFlags, SpecConstant)>,
     /// All possible entrypoints to this shader
     pub entrypoints: Vec<(ShaderStageFlags, String)>,
     /// User selected entry point or default
@@ -126,6 +195,8 @@ impl Default for SpirvReflection {
             descriptor_sets: Vec::new(),
             stage_flag: ShaderStageFlags::VERTEX,
             push_constants: Vec::new(),
+            blocks: Vec::new(),
+            spec_constants: Vec::new(),
             entrypoints: Vec::new(),
             entrypoint: None,
             cache: None,
@@ -142,6 +213,8 @@ impl SpirvReflection {
         output_attributes: HashMap<(String, u8), gfx_hal::pso::AttributeDesc>,
         descriptor_sets: Vec<Vec<gfx_hal::pso::DescriptorSetLayoutBinding>>,
         push_constants: Vec<(ShaderStageFlags, Range<u32>)>,
+        blocks: Vec<BlockLayout>,
+        spec_constants: Vec<(ShaderStageFlags, SpecConstant)>,
     ) -> Result<Self, ReflectError> {
         Ok(SpirvReflection {
             output_attributes,
@@ -149,6 +222,8 @@ impl SpirvReflection {
             descriptor_sets,
             stage_flag,
             push_constants,
+            blocks,
+            spec_constants,
             entrypoints,
             entrypoint: entrypoint,
             cache: None,
@@ -224,15 +299,55 @@ impl SpirvReflection {
                         .for_each(|mut set| set.stage_flags = stage_flag);
                 });
 
-                let push_constants: Result<Vec<_>, _> = module
-                    .enumerate_push_constant_blocks(None)
-                    .map_err(|e| {
+                let push_constant_blocks =
+                    module.enumerate_push_constant_blocks(None).map_err(|e| {
                         ReflectError::Retrieval(RetrievalKind::PushConstants, e.to_string())
-                    })?
+                    })?;
+
+                let push_constants: Result<Vec<_>, _> = push_constant_blocks
                     .iter()
                     .map(|c| convert_push_constant(stage_flag, c))
                     .collect();
 
+                let mut blocks = Vec::new();
+                for binding in module.enumerate_descriptor_bindings(None).map_err(|e| {
+                    ReflectError::Retrieval(RetrievalKind::DescriptorBindings, e.to_string())
+                })? {
+                    let (set, index) = (binding.set, binding.binding);
+                    let kind = match binding.descriptor_type {
+                        ReflectDescriptorType::UniformBuffer
+                        | ReflectDescriptorType::UniformBufferDynamic => BlockKind::Uniform {
+                            set,
+                            binding: index,
+                        },
+                        ReflectDescriptorType::StorageBuffer
+                        | ReflectDescriptorType::StorageBufferDynamic => BlockKind::Storage {
+                            set,
+                            binding: index,
+                        },
+                        _ => continue,
+                    };
+                    blocks.push(block::convert_block(
+                        kind,
+                        &binding.name,
+                        &binding.block,
+                        binding.type_description.as_ref(),
+                    )?);
+                }
+                for push_constant in &push_constant_blocks {
+                    blocks.push(block::convert_block(
+                        BlockKind::PushConstant,
+                        &push_constant.name,
+                        push_constant,
+                        push_constant.type_description.as_ref(),
+                    )?);
+                }
+
+                let spec_constants = spec::reflect_spec_constants(spirv)?
+                    .into_iter()
+                    .map(|constant| (stage_flag, constant))
+                    .collect();
+
                 let entrypoint = if let Some(e) = entrypoint { e } else { "main" };
 
                 Self::new(
@@ -247,6 +362,8 @@ impl SpirvReflection {
                     })?,
                     descriptor_sets_final,
                     push_constants?,
+                    blocks,
+                    spec_constants,
                 )
             }
             Err(e) => return Err(ReflectError::General(e.to_string())),
@@ -305,6 +422,72 @@ impl SpirvReflection {
         Ok(VertexFormat::new(attributes))
     }
 
+    /// Returns vertex input descriptions for vertex buffers with specified formats.
+    ///
+    /// Every input attribute of the vertex shader is matched by name and array index
+    /// to an attribute of one of the vertex formats. Attributes of the formats that aren't used by the shader are skipped.
+    /// One vertex buffer description is returned for each format, in the order provided.
+    ///
+    /// Locations are assigned sequentially starting from the first buffer,
+    /// so attributes of each buffer must occupy consecutive locations following the previous buffers.
+    pub fn vertex_input(
+        &self,
+        formats: &[(VertexFormat, gfx_hal::pso::VertexInputRate)],
+    ) -> Result<
+        Vec<(
+            Vec<gfx_hal::pso::Element<gfx_hal::format::Format>>,
+            gfx_hal::pso::ElemStride,
+            gfx_hal::pso::VertexInputRate,
+        )>,
+        ReflectError,
+    > {
+        let cache = self
+            .cache
+            .as_ref()
+            .ok_or(ReflectError::CacheNotConstructued(self.stage()))?;
+
+        let mut vertices: Vec<_> = formats
+            .iter()
+            .map(|(format, rate)| (Vec::new(), format.stride, *rate))
+            .collect();
+
+        let mut last_buffer = 0;
+        for (expected, (location, name, index, format)) in cache.vertices.iter().enumerate() {
+            let (buffer, attribute) = formats
+                .iter()
+                .enumerate()
+                .filter_map(|(buffer, (vertex, _))| {
+                    vertex
+                        .attributes
+                        .iter()
+                        .find(|a| a.index() == *index && name.eq_ignore_ascii_case(a.name()))
+                        .map(|attribute| (buffer, attribute))
+                })
+                .next()
+                .ok_or_else(|| ReflectError::AttributeMissing(name.clone(), *index))?;
+
+            if attribute.element().format != *format {
+                return Err(ReflectError::AttributeFormatMismatch(
+                    name.clone(),
+                    *format,
+                    attribute.element().format,
+                ));
+            }
+
+            if *location != expected as u32 || buffer < last_buffer {
+                return Err(ReflectError::AttributeLocationMismatch(
+                    name.clone(),
+                    *location,
+                ));
+            }
+
+            last_buffer = buffer;
+            vertices[buffer].0.push(attribute.element().clone());
+        }
+
+        Ok(vertices)
+    }
+
     /// Returns the merged descriptor set layouts of all shaders in this set in gfx_hal format in the form of a `Layout` structure.
     #[inline(always)]
     pub fn layout(&self) -> Result<Layout, ReflectError> {
@@ -322,6 +505,30 @@ impl SpirvReflection {
         self.stage_flag
     }
 
+    /// Returns memory layouts of all uniform, storage and push constant blocks.
+    #[inline]
+    pub fn blocks(&self) -> &[BlockLayout] {
+        &self.blocks
+    }
+
+    /// Returns memory layout of the block with specified variable or type name.
+    pub fn block(&self, name: &str) -> Option<&BlockLayout> {
+        self.blocks
+            .iter()
+            .find(|block| block.name == name || block.type_name == name)
+    }
+
+    /// Returns specialization constants declared in the shaders with stages they are declared in.
+    #[inline]
+    pub fn spec_constants(&self) -> &[(ShaderStageFlags, SpecConstant)] {
+        &self.spec_constants
+    }
+
+    /// Returns builder for specialization constants declared in the shaders.
+    pub fn spec_constant_builder(&self) -> SpecConstantSetBuilder {
+        SpecConstantSetBuilder::new(self)
+    }
+
     /// Returns the reflected push constants of this shader set in gfx_hal format.
     #[inline]
     pub fn push_constants(
@@ -352,6 +559,8 @@ pub(crate) fn merge(reflections: &[SpirvReflection]) -> Result<SpirvReflection,
     let mut set_stage_flags = ShaderStageFlags::empty();
     let mut set_entry_points = Vec::new();
     let mut input_attributes = HashMap::new();
+    let mut blocks = Vec::<BlockLayout>::new();
+    let mut spec_constants = Vec::new();
 
     for s in reflections.iter() {
         let current_layout = &s.descriptor_sets;
@@ -359,6 +568,17 @@ pub(crate) fn merge(reflections: &[SpirvReflection]) -> Result<SpirvReflection,
         set_stage_flags.insert(s.stage());
         set_entry_points.extend(s.entrypoints.clone());
         set_push_constants.extend(s.push_constants(None)?);
+        spec_constants.extend(s.spec_constants.iter().cloned());
+
+        for block in &s.blocks {
+            // Blocks shared by stages are declared in every shader that uses them.
+            if !blocks
+                .iter()
+                .any(|known| known.kind == block.kind && known.name == block.name)
+            {
+                blocks.push(block.clone());
+            }
+        }
 
         if s.stage() == ShaderStageFlags::VERTEX {
             input_attributes = s.input_attributes.clone();
@@ -393,6 +613,8 @@ pub(crate) fn merge(reflections: &[SpirvReflection]) -> Result<SpirvReflection,
         HashMap::new(),
         descriptor_sets,
         set_push_constants,
+        blocks,
+        spec_constants,
     )
 }
 
@@ -503,3 +725,129 @@ where
         Bound::Unbounded => true,
     })
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use gfx_hal::{
+        format::Format,
+        pso::{Element, VertexInputRate},
+    };
+    use rendy_util::types::vertex::{AsVertex, Color, PosColor, Position, TexCoord};
+
+    fn reflection(vertices: &[(u32, &str, Format)]) -> SpirvReflection {
+        SpirvReflection {
+            cache: Some(SpirvCachedGfxDescription {
+                vertices: vertices
+                    .iter()
+                    .map(|&(location, name, format)| (location, name.to_string(), 0, format))
+                    .collect(),
+                layout: Layout {
+                    sets: Vec::new(),
+                    push_constants: Vec::new(),
+                },
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_vertex_input_multiple_buffers() {
+        let reflection = reflection(&[
+            (0, "position", Format::Rgb32Sfloat),
+            (1, "tex_coord", Format::Rg32Sfloat),
+        ]);
+
+        let vertices = reflection
+            .vertex_input(&[
+                (Position::vertex(), VertexInputRate::Vertex),
+                (TexCoord::vertex(), VertexInputRate::Instance(1)),
+            ])
+            .unwrap();
+
+        assert_eq!(
+            vertices,
+            vec![
+                (
+                    vec![Element {
+                        format: Format::Rgb32Sfloat,
+                        offset: 0,
+                    }],
+                    12,
+                    VertexInputRate::Vertex,
+                ),
+                (
+                    vec![Element {
+                        format: Format::Rg32Sfloat,
+                        offset: 0,
+                    }],
+                    8,
+                    VertexInputRate::Instance(1),
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_vertex_input_skips_unused_attributes() {
+        let reflection = reflection(&[(0, "color", Format::Rgba32Sfloat)]);
+
+        let vertices = reflection
+            .vertex_input(&[(PosColor::vertex(), VertexInputRate::Vertex)])
+            .unwrap();
+
+        assert_eq!(vertices.len(), 1);
+        assert_eq!(
+            vertices[0].0,
+            vec![Element {
+                format: Format::Rgba32Sfloat,
+                offset: 12,
+            }]
+        );
+        assert_eq!(vertices[0].1, 28);
+    }
+
+    #[test]
+    fn test_vertex_input_missing_attribute() {
+        let reflection = reflection(&[
+            (0, "position", Format::Rgb32Sfloat),
+            (1, "normal", Format::Rgb32Sfloat),
+        ]);
+
+        match reflection.vertex_input(&[(PosColor::vertex(), VertexInputRate::Vertex)]) {
+            Err(ReflectError::AttributeMissing(name, 0)) => assert_eq!(name, "normal"),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_vertex_input_format_mismatch() {
+        let reflection = reflection(&[(0, "color", Format::Rgb32Sfloat)]);
+
+        match reflection.vertex_input(&[(Color::vertex(), VertexInputRate::Vertex)]) {
+            Err(ReflectError::AttributeFormatMismatch(name, shader, vertex)) => {
+                assert_eq!(name, "color");
+                assert_eq!(shader, Format::Rgb32Sfloat);
+                assert_eq!(vertex, Format::Rgba32Sfloat);
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_vertex_input_location_mismatch() {
+        // Attributes of the second buffer must follow attributes of the first one.
+        let reflection = reflection(&[
+            (0, "tex_coord", Format::Rg32Sfloat),
+            (1, "position", Format::Rgb32Sfloat),
+        ]);
+
+        match reflection.vertex_input(&[
+            (Position::vertex(), VertexInputRate::Vertex),
+            (TexCoord::vertex(), VertexInputRate::Vertex),
+        ]) {
+            Err(ReflectError::AttributeLocationMismatch(name, 1)) => assert_eq!(name, "position"),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,513 @@
+//! Specialization constants reflection.
+//!
+//! spirv-reflect doesn't expose specialization constants,
+//! so they are collected directly from Spir-V instructions.
+
+use super::ReflectError;
+use crate::SpecConstantSet;
+use gfx_hal::pso::{ShaderStageFlags, Specialization, SpecializationConstant};
+use std::{borrow::Cow, collections::HashMap};
+
+const OP_NAME: u32 = 5;
+const OP_TYPE_BOOL: u32 = 20;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_SPEC_CONSTANT_TRUE: u32 = 48;
+const OP_SPEC_CONSTANT_FALSE: u32 = 49;
+const OP_SPEC_CONSTANT: u32 = 50;
+const OP_DECORATE: u32 = 71;
+const DECORATION_SPEC_ID: u32 = 1;
+
+/// Type of the specialization constant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SpecConstantType {
+    /// Boolean. Stored as 32-bit integer.
+    Bool,
+    /// 32-bit unsigned integer.
+    U32,
+    /// 32-bit signed integer.
+    I32,
+    /// 32-bit float.
+    F32,
+    /// 64-bit unsigned integer.
+    U64,
+    /// 64-bit signed integer.
+    I64,
+    /// 64-bit float.
+    F64,
+}
+
+/// Value of the specialization constant.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpecConstantValue {
+    /// Boolean value.
+    Bool(bool),
+    /// 32-bit unsigned integer value.
+    U32(u32),
+    /// 32-bit signed integer value.
+    I32(i32),
+    /// 32-bit float value.
+    F32(f32),
+    /// 64-bit unsigned integer value.
+    U64(u64),
+    /// 64-bit signed integer value.
+    I64(i64),
+    /// 64-bit float value.
+    F64(f64),
+}
+
+impl SpecConstantValue {
+    /// Type of the value.
+    pub fn ty(&self) -> SpecConstantType {
+        match self {
+            SpecConstantValue::Bool(_) => SpecConstantType::Bool,
+            SpecConstantValue::U32(_) => SpecConstantType::U32,
+            SpecConstantValue::I32(_) => SpecConstantType::I32,
+            SpecConstantValue::F32(_) => SpecConstantType::F32,
+            SpecConstantValue::U64(_) => SpecConstantType::U64,
+            SpecConstantValue::I64(_) => SpecConstantType::I64,
+            SpecConstantValue::F64(_) => SpecConstantType::F64,
+        }
+    }
+
+    fn write(&self, data: &mut Vec<u8>) {
+        match *self {
+            SpecConstantValue::Bool(value) => data.extend(&(value as u32).to_ne_bytes()),
+            SpecConstantValue::U32(value) => data.extend(&value.to_ne_bytes()),
+            SpecConstantValue::I32(value) => data.extend(&value.to_ne_bytes()),
+            SpecConstantValue::F32(value) => data.extend(&value.to_bits().to_ne_bytes()),
+            SpecConstantValue::U64(value) => data.extend(&value.to_ne_bytes()),
+            SpecConstantValue::I64(value) => data.extend(&value.to_ne_bytes()),
+            SpecConstantValue::F64(value) => data.extend(&value.to_bits().to_ne_bytes()),
+        }
+    }
+}
+
+macro_rules! impl_from_value {
+    ($($ty:ty => $variant:ident),*) => {
+        $(
+            impl From<$ty> for SpecConstantValue {
+                fn from(value: $ty) -> Self {
+                    SpecConstantValue::$variant(value)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_value!(bool => Bool, u32 => U32, i32 => I32, f32 => F32, u64 => U64, i64 => I64, f64 => F64);
+
+/// Specialization constant declared in the shader.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpecConstant {
+    /// Constant id assigned with `constant_id` layout qualifier.
+    pub id: u32,
+    /// Constant name. Empty if shader is compiled without debug info.
+    pub name: String,
+    /// Constant type.
+    pub ty: SpecConstantType,
+    /// Value used when constant is not specialized.
+    pub default: SpecConstantValue,
+}
+
+/// Collect specialization constants declared in Spir-V module.
+/// Constants of types that can't be represented by `SpecConstantType` are skipped.
+pub(crate) fn reflect_spec_constants(spirv: &[u32]) -> Result<Vec<SpecConstant>, ReflectError> {
+    let mut names = HashMap::new();
+    let mut spec_ids = HashMap::new();
+    let mut types = HashMap::new();
+    let mut constants = Vec::new();
+
+    // Skip header: magic, version, generator, bound and schema.
+    let mut words = spirv.get(5..).unwrap_or(&[]);
+    while !words.is_empty() {
+        let count = (words[0] >> 16) as usize;
+        let opcode = words[0] & 0xffff;
+        if count == 0 || count > words.len() {
+            return Err(ReflectError::General(
+                "malformed Spir-V instruction stream".to_string(),
+            ));
+        }
+        let operands = &words[1..count];
+        words = &words[count..];
+
+        let operand = |index: usize| operands.get(index).cloned().unwrap_or(0);
+
+        match opcode {
+            OP_NAME if !operands.is_empty() => {
+                names.insert(operands[0], decode_string(&operands[1..]));
+            }
+            OP_DECORATE if operand(1) == DECORATION_SPEC_ID => {
+                spec_ids.insert(operand(0), operand(2));
+            }
+            OP_TYPE_BOOL => {
+                types.insert(operand(0), SpecConstantType::Bool);
+            }
+            OP_TYPE_INT => {
+                let ty = match (operand(1), operand(2)) {
+                    (32, 0) => SpecConstantType::U32,
+                    (32, _) => SpecConstantType::I32,
+                    (64, 0) => SpecConstantType::U64,
+                    (64, _) => SpecConstantType::I64,
+                    _ => continue,
+                };
+                types.insert(operand(0), ty);
+            }
+            OP_TYPE_FLOAT => {
+                let ty = match operand(1) {
+                    32 => SpecConstantType::F32,
+                    64 => SpecConstantType::F64,
+                    _ => continue,
+                };
+                types.insert(operand(0), ty);
+            }
+            OP_SPEC_CONSTANT_TRUE => {
+                constants.push((operand(1), SpecConstantValue::Bool(true)));
+            }
+            OP_SPEC_CONSTANT_FALSE => {
+                constants.push((operand(1), SpecConstantValue::Bool(false)));
+            }
+            OP_SPEC_CONSTANT => {
+                let (result, low) = (operand(1), operand(2));
+                let wide = u64::from(low) | (u64::from(operand(3)) << 32);
+                let value = match types.get(&operand(0)) {
+                    Some(SpecConstantType::U32) => SpecConstantValue::U32(low),
+                    Some(SpecConstantType::I32) => SpecConstantValue::I32(low as i32),
+                    Some(SpecConstantType::F32) => SpecConstantValue::F32(f32::from_bits(low)),
+                    Some(SpecConstantType::U64) => SpecConstantValue::U64(wide),
+                    Some(SpecConstantType::I64) => SpecConstantValue::I64(wide as i64),
+                    Some(SpecConstantType::F64) => SpecConstantValue::F64(f64::from_bits(wide)),
+                    Some(SpecConstantType::Bool) | None => {
+                        log::warn!(
+                            "Skip specialization constant %{} of unsupported type",
+                            result
+                        );
+                        continue;
+                    }
+                };
+                constants.push((result, value));
+            }
+            _ => {}
+        }
+    }
+
+    let mut constants: Vec<_> = constants
+        .into_iter()
+        .filter_map(|(result, default)| {
+            // Constants without `SpecId` can't be specialized.
+            let id = *spec_ids.get(&result)?;
+            Some(SpecConstant {
+                id,
+                name: names.remove(&result).unwrap_or_default(),
+                ty: default.ty(),
+                default,
+            })
+        })
+        .collect();
+    constants.sort_by_key(|constant| constant.id);
+
+    Ok(constants)
+}
+
+/// Decode null-terminated UTF-8 string packed into words.
+fn decode_string(words: &[u32]) -> String {
+    let bytes: Vec<u8> = words
+        .iter()
+        .flat_map(|word| word.to_le_bytes().to_vec())
+        .take_while(|&byte| byte != 0)
+        .collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Builder for `SpecConstantSet` that sets specialization constants by name.
+/// Values are checked against constant types reflected from the shaders
+/// and packed into per-stage `Specialization`.
+#[derive(Clone, Debug)]
+pub struct SpecConstantSetBuilder {
+    constants: Vec<(ShaderStageFlags, SpecConstant)>,
+    values: HashMap<(ShaderStageFlags, u32), SpecConstantValue>,
+}
+
+impl SpecConstantSetBuilder {
+    /// Create builder for specialization constants declared in the reflected shaders.
+    pub fn new(reflection: &super::SpirvReflection) -> Self {
+        SpecConstantSetBuilder {
+            constants: reflection.spec_constants.clone(),
+            values: HashMap::new(),
+        }
+    }
+
+    /// Set value of the constant with specified name in all stages that declare it.
+    pub fn with_constant(
+        mut self,
+        name: &str,
+        value: impl Into<SpecConstantValue>,
+    ) -> Result<Self, ReflectError> {
+        self.set_constant(name, value)?;
+        Ok(self)
+    }
+
+    /// Set value of the constant with specified name in all stages that declare it.
+    /// Nothing is set if value type differs from the type of any such constant.
+    pub fn set_constant(
+        &mut self,
+        name: &str,
+        value: impl Into<SpecConstantValue>,
+    ) -> Result<(), ReflectError> {
+        if self.set_matching(value.into(), |constant| constant.name == name)? {
+            Ok(())
+        } else {
+            Err(ReflectError::NameDoesNotExist(name.to_string()))
+        }
+    }
+
+    /// Set value of the constant with specified id in all stages that declare it.
+    pub fn with_constant_id(
+        mut self,
+        id: u32,
+        value: impl Into<SpecConstantValue>,
+    ) -> Result<Self, ReflectError> {
+        self.set_constant_id(id, value)?;
+        Ok(self)
+    }
+
+    /// Set value of the constant with specified id in all stages that declare it.
+    /// Nothing is set if value type differs from the type of any such constant.
+    pub fn set_constant_id(
+        &mut self,
+        id: u32,
+        value: impl Into<SpecConstantValue>,
+    ) -> Result<(), ReflectError> {
+        if self.set_matching(value.into(), |constant| constant.id == id)? {
+            Ok(())
+        } else {
+            Err(ReflectError::SpecConstantIdDoesNotExist(id))
+        }
+    }
+
+    /// Set value of all constants accepted by the filter.
+    /// Returns `false` if no constant was accepted.
+    fn set_matching(
+        &mut self,
+        value: SpecConstantValue,
+        filter: impl Fn(&SpecConstant) -> bool,
+    ) -> Result<bool, ReflectError> {
+        let matching: Vec<_> = self
+            .constants
+            .iter()
+            .filter(|(_, constant)| filter(constant))
+            .collect();
+
+        // Check all matches first so that failed call leaves the builder unchanged.
+        if let Some((_, constant)) = matching.iter().find(|(_, c)| c.ty != value.ty()) {
+            let name = if constant.name.is_empty() {
+                format!("#{}", constant.id)
+            } else {
+                constant.name.clone()
+            };
+            return Err(ReflectError::SpecConstantTypeMismatch(
+                name,
+                constant.ty,
+                value.ty(),
+            ));
+        }
+
+        for (stage, constant) in &matching {
+            self.values.insert((*stage, constant.id), value);
+        }
+
+        Ok(!matching.is_empty())
+    }
+
+    /// Build specialization for every stage.
+    /// Only constants that were set are specialized, others keep default values.
+    pub fn build(&self) -> SpecConstantSet {
+        let mut set = SpecConstantSet::default();
+
+        for (stage, slot) in vec![
+            (ShaderStageFlags::VERTEX, &mut set.vertex),
+            (ShaderStageFlags::FRAGMENT, &mut set.fragment),
+            (ShaderStageFlags::GEOMETRY, &mut set.geometry),
+            (ShaderStageFlags::HULL, &mut set.hull),
+            (ShaderStageFlags::DOMAIN, &mut set.domain),
+            (ShaderStageFlags::COMPUTE, &mut set.compute),
+        ] {
+            let mut constants = Vec::new();
+            let mut data = Vec::new();
+
+            for (constant_stage, constant) in &self.constants {
+                if *constant_stage != stage {
+                    continue;
+                }
+                if let Some(value) = self.values.get(&(stage, constant.id)) {
+                    let start = data.len() as u16;
+                    value.write(&mut data);
+                    constants.push(SpecializationConstant {
+                        id: constant.id,
+                        range: start..data.len() as u16,
+                    });
+                }
+            }
+
+            if !constants.is_empty() {
+                *slot = Some(Specialization {
+                    constants: Cow::Owned(constants),
+                    data: Cow::Owned(data),
+                });
+            }
+        }
+
+        set
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn op(opcode: u32, operands: &[u32]) -> Vec<u32> {
+        let mut words = vec![((operands.len() as u32 + 1) << 16) | opcode];
+        words.extend_from_slice(operands);
+        words
+    }
+
+    fn name(id: u32, name: &str) -> Vec<u32> {
+        let mut bytes = name.as_bytes().to_vec();
+        bytes.resize(name.len() / 4 * 4 + 4, 0);
+        let mut operands = vec![id];
+        operands.extend(
+            bytes
+                .chunks(4)
+                .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])),
+        );
+        op(OP_NAME, &operands)
+    }
+
+    fn constant(id: u32, name: &str, default: SpecConstantValue) -> SpecConstant {
+        SpecConstant {
+            id,
+            name: name.to_string(),
+            ty: default.ty(),
+            default,
+        }
+    }
+
+    #[test]
+    fn test_reflect_spec_constants() {
+        let spirv: Vec<u32> = vec![
+            vec![0x0723_0203, 0x0001_0000, 0, 14, 0],
+            name(10, "count"),
+            name(11, "enabled"),
+            name(12, "scale"),
+            op(OP_DECORATE, &[10, DECORATION_SPEC_ID, 3]),
+            op(OP_DECORATE, &[11, DECORATION_SPEC_ID, 1]),
+            op(OP_DECORATE, &[12, DECORATION_SPEC_ID, 2]),
+            op(OP_TYPE_BOOL, &[1]),
+            op(OP_TYPE_INT, &[2, 32, 1]),
+            op(OP_TYPE_FLOAT, &[3, 32]),
+            op(OP_SPEC_CONSTANT, &[2, 10, -7i32 as u32]),
+            op(OP_SPEC_CONSTANT_TRUE, &[1, 11]),
+            op(OP_SPEC_CONSTANT, &[3, 12, 1.5f32.to_bits()]),
+            // Constant without `SpecId` is not a specialization constant.
+            op(OP_SPEC_CONSTANT, &[2, 13, 5]),
+        ]
+        .concat();
+
+        assert_eq!(
+            reflect_spec_constants(&spirv).unwrap(),
+            vec![
+                constant(1, "enabled", SpecConstantValue::Bool(true)),
+                constant(2, "scale", SpecConstantValue::F32(1.5)),
+                constant(3, "count", SpecConstantValue::I32(-7)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reflect_spec_constants_malformed() {
+        let spirv = vec![0x0723_0203, 0x0001_0000, 0, 1, 0, 0];
+        assert!(reflect_spec_constants(&spirv).is_err());
+    }
+
+    fn builder(constants: Vec<(ShaderStageFlags, SpecConstant)>) -> SpecConstantSetBuilder {
+        SpecConstantSetBuilder::new(&super::super::SpirvReflection {
+            spec_constants: constants,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_build_spec_constants() {
+        let set = builder(vec![
+            (
+                ShaderStageFlags::VERTEX,
+                constant(0, "count", SpecConstantValue::U32(1)),
+            ),
+            (
+                ShaderStageFlags::FRAGMENT,
+                constant(0, "count", SpecConstantValue::U32(1)),
+            ),
+            (
+                ShaderStageFlags::FRAGMENT,
+                constant(4, "scale", SpecConstantValue::F32(1.0)),
+            ),
+        ])
+        .with_constant_id(0, 3u32)
+        .unwrap()
+        .with_constant("scale", 2.0f32)
+        .unwrap()
+        .build();
+
+        let vertex = set.vertex.unwrap();
+        assert_eq!(vertex.constants.len(), 1);
+        assert_eq!(vertex.constants[0].id, 0);
+        assert_eq!(vertex.constants[0].range, 0..4);
+        assert_eq!(&*vertex.data, &3u32.to_ne_bytes());
+
+        let fragment = set.fragment.unwrap();
+        assert_eq!(fragment.constants.len(), 2);
+        assert_eq!(fragment.constants[1].id, 4);
+        assert_eq!(fragment.constants[1].range, 4..8);
+        assert_eq!(&fragment.data[4..], &2.0f32.to_bits().to_ne_bytes());
+
+        assert!(set.compute.is_none());
+    }
+
+    #[test]
+    fn test_set_spec_constant_errors() {
+        let mut builder = builder(vec![
+            (
+                ShaderStageFlags::VERTEX,
+                constant(0, "value", SpecConstantValue::F32(0.0)),
+            ),
+            (
+                ShaderStageFlags::FRAGMENT,
+                constant(0, "value", SpecConstantValue::U32(0)),
+            ),
+        ]);
+
+        match builder.set_constant("value", 1.0f32) {
+            Err(ReflectError::SpecConstantTypeMismatch(name, SpecConstantType::U32, _)) => {
+                assert_eq!(name, "value")
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        match builder.set_constant_id(0, 1u32) {
+            Err(ReflectError::SpecConstantTypeMismatch(_, SpecConstantType::F32, _)) => {}
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        match builder.set_constant("missing", 1u32) {
+            Err(ReflectError::NameDoesNotExist(name)) => assert_eq!(name, "missing"),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        match builder.set_constant_id(5, 1u32) {
+            Err(ReflectError::SpecConstantIdDoesNotExist(5)) => {}
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        // Failed calls must not set constants in any stage.
+        let set = builder.build();
+        assert!(set.vertex.is_none());
+        assert!(set.fragment.is_none());
+    }
+}
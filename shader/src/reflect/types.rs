@@ -90,6 +90,19 @@ impl ReflectInto<Format> for ReflectFormat {
 pub(crate) fn type_element_format(
     flags: ReflectTypeFlags,
     traits: &ReflectTypeDescriptionTraits,
+) -> Result<Format, ReflectTypeError> {
+    numeric_format(
+        flags,
+        &traits.numeric,
+        traits.numeric.vector.component_count,
+    )
+}
+
+/// Format of the scalar or vector with specified number of components.
+pub(crate) fn numeric_format(
+    flags: ReflectTypeFlags,
+    numeric: &ReflectNumericTraits,
+    component_count: u32,
 ) -> Result<Format, ReflectTypeError> {
     enum NumTy {
         SInt,
@@ -97,19 +110,22 @@ pub(crate) fn type_element_format(
         Float,
     }
 
-    let num_ty = if flags.contains(ReflectTypeFlags::INT) {
-        match traits.numeric.scalar.signedness {
-            0 => NumTy::UInt,
-            1 => NumTy::SInt,
+    let (num_ty, width) = if flags.contains(ReflectTypeFlags::BOOL) {
+        // Booleans have no defined width and are stored as 32-bit integers in buffers.
+        (NumTy::UInt, 32)
+    } else if flags.contains(ReflectTypeFlags::INT) {
+        match numeric.scalar.signedness {
+            0 => (NumTy::UInt, numeric.scalar.width),
+            1 => (NumTy::SInt, numeric.scalar.width),
             unk => return Err(ReflectTypeError::UnrecognizedNumericSignedness(unk)),
         }
     } else if flags.contains(ReflectTypeFlags::FLOAT) {
-        NumTy::Float
+        (NumTy::Float, numeric.scalar.width)
     } else {
         return Err(ReflectTypeError::UnrecognizedNumericTypeFlags(flags));
     };
 
-    let current_type = match (num_ty, traits.numeric.scalar.width) {
+    let current_type = match (num_ty, width) {
         (NumTy::SInt, 8) => Format::R8Sint,
         (NumTy::SInt, 16) => Format::R16Sint,
         (NumTy::SInt, 32) => Format::R32Sint,
@@ -118,51 +134,53 @@ pub(crate) fn type_element_format(
         (NumTy::UInt, 16) => Format::R16Uint,
         (NumTy::UInt, 32) => Format::R32Uint,
         (NumTy::UInt, 64) => Format::R64Uint,
+        (NumTy::Float, 16) => Format::R16Sfloat,
         (NumTy::Float, 32) => Format::R32Sfloat,
         (NumTy::Float, 64) => Format::R64Sfloat,
         (_, width) => return Err(ReflectTypeError::UnrecognizedNumericTypeWidth(width)),
     };
 
-    if traits.numeric.vector.component_count > 1 {
-        Ok(
-            match (current_type, traits.numeric.vector.component_count) {
-                (Format::R8Sint, 2) => Format::Rg8Sint,
-                (Format::R8Sint, 3) => Format::Rgb8Sint,
-                (Format::R8Sint, 4) => Format::Rgba8Sint,
-                (Format::R16Sint, 2) => Format::Rg16Sint,
-                (Format::R16Sint, 3) => Format::Rgb16Sint,
-                (Format::R16Sint, 4) => Format::Rgba16Sint,
-                (Format::R32Sint, 2) => Format::Rg32Sint,
-                (Format::R32Sint, 3) => Format::Rgb32Sint,
-                (Format::R32Sint, 4) => Format::Rgba32Sint,
-                (Format::R64Sint, 2) => Format::Rg64Sint,
-                (Format::R64Sint, 3) => Format::Rgb64Sint,
-                (Format::R64Sint, 4) => Format::Rgba64Sint,
-                (Format::R8Uint, 2) => Format::Rg8Uint,
-                (Format::R8Uint, 3) => Format::Rgb8Uint,
-                (Format::R8Uint, 4) => Format::Rgba8Uint,
-                (Format::R16Uint, 2) => Format::Rg16Uint,
-                (Format::R16Uint, 3) => Format::Rgb16Uint,
-                (Format::R16Uint, 4) => Format::Rgba16Uint,
-                (Format::R32Uint, 2) => Format::Rg32Uint,
-                (Format::R32Uint, 3) => Format::Rgb32Uint,
-                (Format::R32Uint, 4) => Format::Rgba32Uint,
-                (Format::R64Uint, 2) => Format::Rg64Uint,
-                (Format::R64Uint, 3) => Format::Rgb64Uint,
-                (Format::R64Uint, 4) => Format::Rgba64Uint,
-                (Format::R32Sfloat, 2) => Format::Rg32Sfloat,
-                (Format::R32Sfloat, 3) => Format::Rgb32Sfloat,
-                (Format::R32Sfloat, 4) => Format::Rgba32Sfloat,
-                (Format::R64Sfloat, 2) => Format::Rg64Sfloat,
-                (Format::R64Sfloat, 3) => Format::Rgb64Sfloat,
-                (Format::R64Sfloat, 4) => Format::Rgba64Sfloat,
-                (format, count) => {
-                    return Err(ReflectTypeError::UnrecognizedNumericArrayCount(
-                        format, count,
-                    ))
-                }
-            },
-        )
+    if component_count > 1 {
+        Ok(match (current_type, component_count) {
+            (Format::R8Sint, 2) => Format::Rg8Sint,
+            (Format::R8Sint, 3) => Format::Rgb8Sint,
+            (Format::R8Sint, 4) => Format::Rgba8Sint,
+            (Format::R16Sint, 2) => Format::Rg16Sint,
+            (Format::R16Sint, 3) => Format::Rgb16Sint,
+            (Format::R16Sint, 4) => Format::Rgba16Sint,
+            (Format::R32Sint, 2) => Format::Rg32Sint,
+            (Format::R32Sint, 3) => Format::Rgb32Sint,
+            (Format::R32Sint, 4) => Format::Rgba32Sint,
+            (Format::R64Sint, 2) => Format::Rg64Sint,
+            (Format::R64Sint, 3) => Format::Rgb64Sint,
+            (Format::R64Sint, 4) => Format::Rgba64Sint,
+            (Format::R8Uint, 2) => Format::Rg8Uint,
+            (Format::R8Uint, 3) => Format::Rgb8Uint,
+            (Format::R8Uint, 4) => Format::Rgba8Uint,
+            (Format::R16Uint, 2) => Format::Rg16Uint,
+            (Format::R16Uint, 3) => Format::Rgb16Uint,
+            (Format::R16Uint, 4) => Format::Rgba16Uint,
+            (Format::R32Uint, 2) => Format::Rg32Uint,
+            (Format::R32Uint, 3) => Format::Rgb32Uint,
+            (Format::R32Uint, 4) => Format::Rgba32Uint,
+            (Format::R64Uint, 2) => Format::Rg64Uint,
+            (Format::R64Uint, 3) => Format::Rgb64Uint,
+            (Format::R64Uint, 4) => Format::Rgba64Uint,
+            (Format::R16Sfloat, 2) => Format::Rg16Sfloat,
+            (Format::R16Sfloat, 3) => Format::Rgb16Sfloat,
+            (Format::R16Sfloat, 4) => Format::Rgba16Sfloat,
+            (Format::R32Sfloat, 2) => Format::Rg32Sfloat,
+            (Format::R32Sfloat, 3) => Format::Rgb32Sfloat,
+            (Format::R32Sfloat, 4) => Format::Rgba32Sfloat,
+            (Format::R64Sfloat, 2) => Format::Rg64Sfloat,
+            (Format::R64Sfloat, 3) => Format::Rgb64Sfloat,
+            (Format::R64Sfloat, 4) => Format::Rgba64Sfloat,
+            (format, count) => {
+                return Err(ReflectTypeError::UnrecognizedNumericArrayCount(
+                    format, count,
+                ))
+            }
+        })
     } else {
         Ok(current_type)
     }
@@ -0,0 +1,211 @@
+//! Shader hot-reload.
+
+use {
+    crate::{Shader, ShaderSetBuilder, SpecConstantSet, SpirvShader},
+    gfx_hal::pso::ShaderStageFlags,
+    std::{
+        path::{Path, PathBuf},
+        time::{Duration, Instant, SystemTime},
+    },
+};
+
+/// Watches shader source files for changes.
+/// Files are polled for modification time, so no platform specific notifications are required.
+#[derive(Clone, Debug)]
+pub struct ShaderWatcher {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    interval: Duration,
+    last_poll: Option<Instant>,
+}
+
+impl Default for ShaderWatcher {
+    fn default() -> Self {
+        ShaderWatcher {
+            files: Vec::new(),
+            interval: Duration::from_millis(500),
+            last_poll: None,
+        }
+    }
+}
+
+impl ShaderWatcher {
+    /// Create new watcher that polls files at most every 500 milliseconds.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set minimal interval between polls.
+    /// Zero interval makes every call to `changed` poll the files.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.set_interval(interval);
+        self
+    }
+
+    /// Set minimal interval between polls.
+    /// Zero interval makes every call to `changed` poll the files.
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+
+    /// Start watching the file.
+    pub fn with_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.add_file(path);
+        self
+    }
+
+    /// Start watching the file.
+    pub fn add_file(&mut self, path: impl Into<PathBuf>) {
+        let path = path.into();
+        if self.files.iter().all(|(watched, _)| *watched != path) {
+            let modified = modified(&path);
+            self.files.push((path, modified));
+        }
+    }
+
+    /// Get watched files.
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().map(|(path, _)| path.as_path())
+    }
+
+    /// Check if any of the watched files was changed since previous check.
+    /// Missing files are not considered changed until they reappear,
+    /// so editors that replace files on save don't trigger reload of half-written sources.
+    pub fn changed(&mut self) -> bool {
+        let now = Instant::now();
+        if let Some(last_poll) = self.last_poll {
+            if now.duration_since(last_poll) < self.interval {
+                return false;
+            }
+        }
+        self.last_poll = Some(now);
+
+        let mut changed = false;
+        for (path, last_modified) in &mut self.files {
+            if let Some(modified) = modified(path) {
+                if *last_modified != Some(modified) {
+                    log::debug!("Shader source {:?} changed", path);
+                    *last_modified = Some(modified);
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Error reloading shaders.
+#[derive(Clone, Debug)]
+pub struct ReloadError {
+    /// Stage of the shader that failed to reload.
+    pub stage: ShaderStageFlags,
+    /// Error message.
+    pub message: String,
+}
+
+impl std::error::Error for ReloadError {}
+impl std::fmt::Display for ReloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "failed to reload {:?} shader: {}",
+            self.stage, self.message
+        )
+    }
+}
+
+/// Shader set which is recompiled when shader sources change.
+/// Keeps last successfully compiled shaders when recompilation fails.
+#[derive(derivative::Derivative)]
+#[derivative(Debug)]
+pub struct ReloadableShaderSet {
+    #[derivative(Debug = "ignore")]
+    shaders: Vec<Box<dyn Fn() -> Result<SpirvShader, ReloadError> + Send + Sync>>,
+    watcher: ShaderWatcher,
+    builder: ShaderSetBuilder,
+    spec_constants: SpecConstantSet,
+}
+
+impl ReloadableShaderSet {
+    /// Create empty reloadable shader set using specified watcher.
+    pub fn new(watcher: ShaderWatcher) -> Self {
+        ReloadableShaderSet {
+            shaders: Vec::new(),
+            watcher,
+            builder: ShaderSetBuilder::default(),
+            spec_constants: SpecConstantSet::default(),
+        }
+    }
+
+    /// Add shader that is recompiled when any of the `sources` changes.
+    /// Shader is compiled immediately.
+    ///
+    /// For `FileShaderInfo` source is the file it is loaded from.
+    pub fn with_shader<S>(
+        mut self,
+        shader: S,
+        sources: impl IntoIterator<Item = PathBuf>,
+    ) -> Result<Self, ReloadError>
+    where
+        S: Shader + Send + Sync + 'static,
+    {
+        for source in sources {
+            self.watcher.add_file(source);
+        }
+
+        let compile = move || -> Result<SpirvShader, ReloadError> {
+            let spirv = shader.spirv().map_err(|e| ReloadError {
+                stage: shader.stage(),
+                message: format!("{:?}", e),
+            })?;
+            Ok(SpirvShader::new(
+                spirv.into_owned(),
+                shader.stage(),
+                shader.entry(),
+            ))
+        };
+
+        let spirv = compile()?;
+        self.builder = self.builder.with_spirv(&spirv)?;
+        self.shaders.push(Box::new(compile));
+        Ok(self)
+    }
+
+    /// Set specialization constants for the shaders.
+    pub fn with_specialization(mut self, spec_constants: SpecConstantSet) -> Self {
+        self.spec_constants = spec_constants;
+        self
+    }
+
+    /// Get builder with last successfully compiled shaders.
+    pub fn builder(&self) -> &ShaderSetBuilder {
+        &self.builder
+    }
+
+    /// Get specialization constants for the shaders.
+    pub fn spec_constants(&self) -> &SpecConstantSet {
+        &self.spec_constants
+    }
+
+    /// Recompile shaders if sources changed.
+    /// Returns `true` if shaders were recompiled.
+    /// On failure shaders compiled last time are kept.
+    pub fn reload(&mut self) -> Result<bool, ReloadError> {
+        if !self.watcher.changed() {
+            return Ok(false);
+        }
+
+        let mut builder = ShaderSetBuilder::default();
+        for compile in &self.shaders {
+            builder = builder.with_spirv(&compile()?)?;
+        }
+
+        self.builder = builder;
+        Ok(true)
+    }
+}
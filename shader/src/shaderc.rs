@@ -1,7 +1,7 @@
 // This module is gated under "shader-compiler" feature
 use super::Shader;
 use crate::SpirvShader;
-pub use shaderc::{self, ShaderKind, SourceLanguage};
+pub use shaderc::{self, OptimizationLevel, ShaderKind, SourceLanguage, TargetEnv};
 
 macro_rules! vk_make_version {
     ($major: expr, $minor: expr, $patch: expr) => {{
@@ -21,6 +21,24 @@ pub enum ShaderCError {
     Io(std::io::Error),
     /// Shaderc returned an error.
     ShaderC(::shaderc::Error),
+    /// Included file was not found relative to the including source nor in any include directory.
+    IncludeNotFound {
+        /// Requested include path.
+        requested: String,
+        /// Source that requested the include.
+        source: String,
+    },
+    /// Included file could not be read.
+    IncludeRead(std::path::PathBuf, std::io::Error),
+    /// Two precompiled shaders map to the same generated function name.
+    DuplicateFunctionName {
+        /// Generated function name.
+        name: String,
+        /// Shader that was assigned the name first.
+        first: std::path::PathBuf,
+        /// Shader that maps to the same name.
+        second: std::path::PathBuf,
+    },
 }
 
 impl std::error::Error for ShaderCError {}
@@ -33,6 +51,25 @@ impl std::fmt::Display for ShaderCError {
             }
             ShaderCError::Io(e) => write!(f, "{}", e),
             ShaderCError::ShaderC(e) => write!(f, "{}", e),
+            ShaderCError::IncludeNotFound { requested, source } => {
+                write!(
+                    f,
+                    "file {:?} included from {:?} not found",
+                    requested, source
+                )
+            }
+            ShaderCError::IncludeRead(path, e) => {
+                write!(f, "failed to read included file {:?}: {}", path, e)
+            }
+            ShaderCError::DuplicateFunctionName {
+                name,
+                first,
+                second,
+            } => write!(
+                f,
+                "shaders {:?} and {:?} both generate function {}",
+                first, second, name
+            ),
         }
     }
 }
@@ -49,13 +86,202 @@ impl From<::shaderc::Error> for ShaderCError {
     }
 }
 
+/// Options to compile shader sources with.
+#[derive(Clone, Debug)]
+pub struct CompileOptions {
+    defines: Vec<(String, Option<String>)>,
+    include_dirs: Vec<std::path::PathBuf>,
+    optimization: OptimizationLevel,
+    debug_info: bool,
+    target_env: TargetEnv,
+    target_env_version: u32,
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        CompileOptions {
+            defines: Vec::new(),
+            include_dirs: Vec::new(),
+            optimization: OptimizationLevel::Performance,
+            debug_info: true,
+            target_env: TargetEnv::Vulkan,
+            target_env_version: vk_make_version!(1, 0, 0),
+        }
+    }
+}
+
+impl CompileOptions {
+    /// Default options.
+    /// Compile for Vulkan 1.0 optimizing for performance and keeping debug info.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add preprocessor definition.
+    /// Same as `#define name value` at the beginning of the source.
+    pub fn with_define(mut self, name: impl Into<String>, value: Option<&str>) -> Self {
+        self.add_define(name, value);
+        self
+    }
+
+    /// Add preprocessor definition.
+    /// Same as `#define name value` at the beginning of the source.
+    pub fn add_define(&mut self, name: impl Into<String>, value: Option<&str>) {
+        self.defines
+            .push((name.into(), value.map(|value| value.to_owned())));
+    }
+
+    /// Add directory to search `#include` directives in.
+    /// Relative includes are searched for relative to the including source first.
+    pub fn with_include_dir(mut self, dir: impl Into<std::path::PathBuf>) -> Self {
+        self.add_include_dir(dir);
+        self
+    }
+
+    /// Add directory to search `#include` directives in.
+    /// Relative includes are searched for relative to the including source first.
+    pub fn add_include_dir(&mut self, dir: impl Into<std::path::PathBuf>) {
+        self.include_dirs.push(dir.into());
+    }
+
+    /// Set optimization level.
+    pub fn with_optimization(mut self, optimization: OptimizationLevel) -> Self {
+        self.optimization = optimization;
+        self
+    }
+
+    /// Keep or strip debug info.
+    pub fn with_debug_info(mut self, debug_info: bool) -> Self {
+        self.debug_info = debug_info;
+        self
+    }
+
+    /// Set target environment and its version.
+    /// Version for Vulkan environment is encoded as `VK_MAKE_VERSION` does.
+    pub fn with_target_env(mut self, env: TargetEnv, version: u32) -> Self {
+        self.target_env = env;
+        self.target_env_version = version;
+        self
+    }
+
+    /// Get preprocessor definitions.
+    pub fn defines(&self) -> &[(String, Option<String>)] {
+        &self.defines
+    }
+
+    /// Get include directories.
+    pub fn include_dirs(&self) -> &[std::path::PathBuf] {
+        &self.include_dirs
+    }
+
+    /// Feed every option into the hasher.
+    pub(crate) fn hash_into<H: std::hash::Hasher>(&self, state: &mut H) {
+        use std::hash::Hash;
+
+        self.defines.hash(state);
+        self.include_dirs.hash(state);
+        (self.optimization as u32).hash(state);
+        self.debug_info.hash(state);
+        (self.target_env as u32).hash(state);
+        self.target_env_version.hash(state);
+    }
+
+    pub(crate) fn resolve_include(
+        &self,
+        requested: &str,
+        include_type: shaderc::IncludeType,
+        source: &str,
+    ) -> Result<shaderc::ResolvedInclude, ShaderCError> {
+        let relative = match include_type {
+            shaderc::IncludeType::Relative => std::path::Path::new(source)
+                .parent()
+                .map(|dir| dir.join(requested)),
+            shaderc::IncludeType::Standard => None,
+        };
+
+        let path = relative
+            .into_iter()
+            .chain(self.include_dirs.iter().map(|dir| dir.join(requested)))
+            .find(|path| path.is_file())
+            .ok_or_else(|| ShaderCError::IncludeNotFound {
+                requested: requested.to_owned(),
+                source: source.to_owned(),
+            })?;
+
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| ShaderCError::IncludeRead(path.clone(), e))?;
+
+        Ok(shaderc::ResolvedInclude {
+            resolved_name: path.to_string_lossy().into_owned(),
+            content,
+        })
+    }
+
+    /// Compile shader source into Spir-V bytecode.
+    /// Returns paths of all files included by the source along with the bytecode.
+    fn compile(
+        &self,
+        source: &str,
+        kind: ShaderKind,
+        lang: SourceLanguage,
+        path: &std::path::Path,
+        entry: &str,
+    ) -> Result<(Vec<u32>, Vec<std::path::PathBuf>), ShaderCError> {
+        let name = path
+            .to_str()
+            .ok_or_else(|| ShaderCError::NonUtf8Path(path.to_owned()))?;
+
+        let include_error = std::cell::RefCell::new(None);
+        let included = std::cell::RefCell::new(Vec::new());
+
+        let result = {
+            let mut ops = shaderc::CompileOptions::new().ok_or(ShaderCError::Init)?;
+            ops.set_target_env(self.target_env, self.target_env_version);
+            ops.set_source_language(lang);
+            ops.set_optimization_level(self.optimization);
+            if self.debug_info {
+                ops.set_generate_debug_info();
+            }
+            for (name, value) in &self.defines {
+                ops.add_macro_definition(name, value.as_ref().map(String::as_str));
+            }
+            ops.set_include_callback(|requested, include_type, source, _depth| {
+                self.resolve_include(requested, include_type, source)
+                    .map(|resolved| {
+                        included
+                            .borrow_mut()
+                            .push(std::path::PathBuf::from(&resolved.resolved_name));
+                        resolved
+                    })
+                    .map_err(|e| {
+                        let message = e.to_string();
+                        include_error.borrow_mut().get_or_insert(e);
+                        message
+                    })
+            });
+
+            shaderc::Compiler::new()
+                .ok_or(ShaderCError::Init)?
+                .compile_into_spirv(source, kind, name, entry, Some(&ops))
+        };
+
+        match result {
+            Ok(artifact) => Ok((artifact.as_binary().into(), included.into_inner())),
+            Err(e) => Err(include_error
+                .into_inner()
+                .unwrap_or(ShaderCError::ShaderC(e))),
+        }
+    }
+}
+
 /// Shader loaded from a source in the filesystem.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct FileShaderInfo<P, E> {
     path: P,
     kind: ShaderKind,
     lang: SourceLanguage,
     entry: E,
+    options: CompileOptions,
 }
 
 impl<P, E> FileShaderInfo<P, E> {
@@ -66,8 +292,48 @@ impl<P, E> FileShaderInfo<P, E> {
             kind,
             lang,
             entry,
+            options: CompileOptions::default(),
         }
     }
+
+    /// Set options to compile shader with.
+    pub fn with_options(mut self, options: CompileOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Get options to compile shader with.
+    pub fn options(&self) -> &CompileOptions {
+        &self.options
+    }
+
+    /// Path to the shader source.
+    /// Can be watched for changes with `ReloadableShaderSet`.
+    pub fn path(&self) -> &P {
+        &self.path
+    }
+}
+
+impl<P, E> FileShaderInfo<P, E>
+where
+    P: AsRef<std::path::Path>,
+    E: AsRef<str>,
+{
+    /// Compile shader into Spir-V bytecode.
+    /// Returns paths of all files included by the shader along with the bytecode.
+    pub(crate) fn spirv_with_includes(
+        &self,
+    ) -> Result<(Vec<u32>, Vec<std::path::PathBuf>), ShaderCError> {
+        let code = std::fs::read_to_string(&self.path)?;
+
+        self.options.compile(
+            &code,
+            self.kind,
+            self.lang,
+            self.path.as_ref(),
+            self.entry.as_ref(),
+        )
+    }
 }
 
 impl<P, E> FileShaderInfo<P, E>
@@ -95,30 +361,8 @@ where
     type Error = ShaderCError;
 
     fn spirv(&self) -> Result<std::borrow::Cow<'static, [u32]>, ShaderCError> {
-        let code = std::fs::read_to_string(&self.path)?;
-
-        let artifact = shaderc::Compiler::new()
-            .ok_or(ShaderCError::Init)?
-            .compile_into_spirv(
-                &code,
-                self.kind,
-                self.path
-                    .as_ref()
-                    .to_str()
-                    .ok_or_else(|| ShaderCError::NonUtf8Path(self.path.as_ref().to_owned()))?,
-                self.entry.as_ref(),
-                Some({
-                    let mut ops = shaderc::CompileOptions::new().ok_or(ShaderCError::Init)?;
-                    ops.set_target_env(shaderc::TargetEnv::Vulkan, vk_make_version!(1, 0, 0));
-                    ops.set_source_language(self.lang);
-                    ops.set_generate_debug_info();
-                    ops.set_optimization_level(shaderc::OptimizationLevel::Performance);
-                    ops
-                })
-                .as_ref(),
-            )?;
-
-        Ok(std::borrow::Cow::Owned(artifact.as_binary().into()))
+        let (spirv, _) = self.spirv_with_includes()?;
+        Ok(std::borrow::Cow::Owned(spirv))
     }
 
     fn entry(&self) -> &str {
@@ -131,13 +375,14 @@ where
 }
 
 /// Shader loaded from a source in the filesystem.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct SourceCodeShaderInfo<P, E, S> {
     source: S,
     path: P,
     kind: ShaderKind,
     lang: SourceLanguage,
     entry: E,
+    options: CompileOptions,
 }
 
 impl<P, E, S> SourceCodeShaderInfo<P, E, S> {
@@ -149,8 +394,21 @@ impl<P, E, S> SourceCodeShaderInfo<P, E, S> {
             kind,
             lang,
             entry,
+            options: CompileOptions::default(),
         }
     }
+
+    /// Set options to compile shader with.
+    /// Relative includes are resolved relative to the `path`.
+    pub fn with_options(mut self, options: CompileOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Get options to compile shader with.
+    pub fn options(&self) -> &CompileOptions {
+        &self.options
+    }
 }
 
 impl<P, E, S> SourceCodeShaderInfo<P, E, S>
@@ -179,28 +437,15 @@ where
     type Error = ShaderCError;
 
     fn spirv(&self) -> Result<std::borrow::Cow<'static, [u32]>, ShaderCError> {
-        let artifact = shaderc::Compiler::new()
-            .ok_or(ShaderCError::Init)?
-            .compile_into_spirv(
-                self.source.as_ref(),
-                self.kind,
-                self.path
-                    .as_ref()
-                    .to_str()
-                    .ok_or_else(|| ShaderCError::NonUtf8Path(self.path.as_ref().to_owned()))?,
-                self.entry.as_ref(),
-                Some({
-                    let mut ops = shaderc::CompileOptions::new().ok_or(ShaderCError::Init)?;
-                    ops.set_target_env(shaderc::TargetEnv::Vulkan, vk_make_version!(1, 0, 0));
-                    ops.set_source_language(self.lang);
-                    ops.set_generate_debug_info();
-                    ops.set_optimization_level(shaderc::OptimizationLevel::Performance);
-                    ops
-                })
-                .as_ref(),
-            )?;
-
-        Ok(std::borrow::Cow::Owned(artifact.as_binary().into()))
+        let (spirv, _) = self.options.compile(
+            self.source.as_ref(),
+            self.kind,
+            self.lang,
+            self.path.as_ref(),
+            self.entry.as_ref(),
+        )?;
+
+        Ok(std::borrow::Cow::Owned(spirv))
     }
 
     fn entry(&self) -> &str {
@@ -225,7 +470,7 @@ pub type StaticShaderInfo = FileShaderInfo<&'static str, &'static str>;
 /// Shader info with a PathBuf for the path and static string for entry
 pub type PathBufShaderInfo = FileShaderInfo<std::path::PathBuf, &'static str>;
 
-fn stage_from_kind(kind: &ShaderKind) -> gfx_hal::pso::ShaderStageFlags {
+pub(crate) fn stage_from_kind(kind: &ShaderKind) -> gfx_hal::pso::ShaderStageFlags {
     use gfx_hal::pso::ShaderStageFlags;
     match kind {
         ShaderKind::Vertex => ShaderStageFlags::VERTEX,
@@ -237,3 +482,101 @@ fn stage_from_kind(kind: &ShaderKind) -> gfx_hal::pso::ShaderStageFlags {
         _ => panic!("Invalid shader type specified"),
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Creates directory with shader and include files, removed on drop.
+    struct IncludeTree(PathBuf);
+
+    impl IncludeTree {
+        fn new(name: &str) -> Self {
+            let root =
+                std::env::temp_dir().join(format!("rendy-shader-{}-{}", name, std::process::id()));
+            std::fs::create_dir_all(root.join("shaders")).unwrap();
+            std::fs::create_dir_all(root.join("include")).unwrap();
+            std::fs::write(root.join("shaders/common.glsl"), "relative").unwrap();
+            std::fs::write(root.join("include/common.glsl"), "standard").unwrap();
+            std::fs::write(root.join("include/only.glsl"), "fallback").unwrap();
+            IncludeTree(root)
+        }
+
+        fn options(&self) -> CompileOptions {
+            CompileOptions::new().with_include_dir(self.0.join("include"))
+        }
+
+        fn source(&self) -> String {
+            self.0
+                .join("shaders/shader.frag")
+                .to_string_lossy()
+                .into_owned()
+        }
+    }
+
+    impl Drop for IncludeTree {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_resolve_relative_include() {
+        let tree = IncludeTree::new("relative");
+        let resolved = tree
+            .options()
+            .resolve_include(
+                "common.glsl",
+                shaderc::IncludeType::Relative,
+                &tree.source(),
+            )
+            .unwrap();
+        assert_eq!(resolved.content, "relative");
+        assert_eq!(
+            PathBuf::from(resolved.resolved_name),
+            tree.0.join("shaders/common.glsl")
+        );
+    }
+
+    #[test]
+    fn test_resolve_standard_include() {
+        let tree = IncludeTree::new("standard");
+        let resolved = tree
+            .options()
+            .resolve_include(
+                "common.glsl",
+                shaderc::IncludeType::Standard,
+                &tree.source(),
+            )
+            .unwrap();
+        assert_eq!(resolved.content, "standard");
+    }
+
+    #[test]
+    fn test_resolve_relative_include_falls_back_to_include_dirs() {
+        let tree = IncludeTree::new("fallback");
+        let resolved = tree
+            .options()
+            .resolve_include("only.glsl", shaderc::IncludeType::Relative, &tree.source())
+            .unwrap();
+        assert_eq!(resolved.content, "fallback");
+    }
+
+    #[test]
+    fn test_resolve_missing_include() {
+        let tree = IncludeTree::new("missing");
+        let result = tree.options().resolve_include(
+            "missing.glsl",
+            shaderc::IncludeType::Relative,
+            &tree.source(),
+        );
+        match result {
+            Err(ShaderCError::IncludeNotFound { requested, .. }) => {
+                assert_eq!(requested, "missing.glsl")
+            }
+            Err(e) => panic!("Unexpected error: {}", e),
+            Ok(_) => panic!("Include must not be resolved"),
+        }
+    }
+}
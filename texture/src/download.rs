@@ -0,0 +1,29 @@
+use {
+    crate::{
+        factory::{Download, Factory},
+        pixel::AsPixel,
+    },
+    gfx_hal::Backend,
+};
+
+/// Read content of complete image download as pixels.
+/// Returns `None` if download is not complete.
+///
+/// # Panics
+///
+/// Panics if format of pixel `P` doesn't match format of downloaded image.
+pub fn read_pixels<B, P>(
+    download: &Download<B>,
+    factory: &Factory<B>,
+) -> Result<Option<Vec<P>>, gfx_hal::device::MapError>
+where
+    B: Backend,
+    P: AsPixel,
+{
+    assert_eq!(
+        download.format(),
+        Some(P::FORMAT),
+        "Pixel format must match format of downloaded image"
+    );
+    download.read::<P>(factory)
+}
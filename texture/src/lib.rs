@@ -17,8 +17,9 @@ use rendy_memory as memory;
 use rendy_resource as resource;
 use rendy_util as util;
 
+mod download;
 mod format;
 pub mod pixel;
 mod texture;
 
-pub use crate::{format::*, pixel::Rgba8Unorm, texture::*};
+pub use crate::{download::*, format::*, pixel::Rgba8Unorm, texture::*};
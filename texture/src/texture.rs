@@ -397,16 +397,18 @@ impl<'a> TextureBuilder<'a> {
             }
         } else if mip_levels > 1 && !generate_mips {
             unsafe {
-                factory.transition_image(
-                    image.clone(),
-                    image::SubresourceRange {
-                        aspects: info.format.surface_desc().aspects,
-                        levels: 1..mip_levels,
-                        layers: 0..info.kind.num_layers(),
-                    },
-                    image::Layout::Undefined,
-                    next_state,
-                );
+                factory
+                    .transition_image(
+                        image.clone(),
+                        image::SubresourceRange {
+                            aspects: info.format.surface_desc().aspects,
+                            levels: 1..mip_levels,
+                            layers: 0..info.kind.num_layers(),
+                        },
+                        image::Layout::Undefined,
+                        next_state,
+                    )
+                    .map_err(BuildError::Mipmap)?;
             }
         }
 
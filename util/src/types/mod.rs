@@ -4,6 +4,24 @@
 #[doc(inline)]
 pub mod vertex;
 
+use std::hash::{Hash, Hasher};
+
+/// Feed descriptor set layout bindings into the hasher field by field,
+/// as `gfx_hal::pso::DescriptorSetLayoutBinding` doesn't implement `Hash`.
+pub fn hash_bindings<H: Hasher>(
+    bindings: &[gfx_hal::pso::DescriptorSetLayoutBinding],
+    state: &mut H,
+) {
+    bindings.len().hash(state);
+    for binding in bindings {
+        binding.binding.hash(state);
+        binding.ty.hash(state);
+        binding.count.hash(state);
+        binding.stage_flags.hash(state);
+        binding.immutable_samplers.hash(state);
+    }
+}
+
 /// Set layout
 #[derive(Clone, Debug, Default)]
 pub struct SetLayout {
@@ -11,8 +29,14 @@ pub struct SetLayout {
     pub bindings: Vec<gfx_hal::pso::DescriptorSetLayoutBinding>,
 }
 
+impl Hash for SetLayout {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_bindings(&self.bindings, state);
+    }
+}
+
 /// Pipeline layout
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Hash)]
 pub struct Layout {
     /// Sets in pipeline layout.
     pub sets: Vec<SetLayout>,